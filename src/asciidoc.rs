@@ -0,0 +1,936 @@
+//! AsciiDoc to Typst conversion
+//!
+//! Converts the commonly written part of [AsciiDoc](https://asciidoc.org) to
+//! Typst for embedding in templates: section titles, paragraphs, constrained
+//! and unconstrained formatting, links, cross references, images, `stem`
+//! math, bullet, numbered and checklists, listing, literal, quote, example
+//! and sidebar blocks, admonition paragraphs, tables and thematic and page
+//! breaks. Raw Typst can be written with `pass:[...]` and `++++` blocks.
+//! Document attributes, includes and conditionals are not supported.
+//!
+//! An anchor before a section title (`[[label]]` or `[#label]`) becomes a
+//! Typst label, and `<<label>>` cross references point to it.
+
+use std::collections::HashSet;
+
+use crate::convert::escape_text;
+use crate::djot::raw_block;
+use crate::markdown::{escape_typst, is_label, math_markup, string_literal, TASK_DONE, TASK_OPEN};
+
+/// Paragraph prefixes rendered as admonitions, with their caption
+const ADMONITIONS: &[(&str, &str)] = &[
+    ("NOTE", "Note"),
+    ("TIP", "Tip"),
+    ("IMPORTANT", "Important"),
+    ("WARNING", "Warning"),
+    ("CAUTION", "Caution"),
+];
+
+/// Convert AsciiDoc text to Typst markup
+///
+/// Cross references to a label in `labels` become Typst references.
+pub fn asciidoc_to_typst(adoc: &str, labels: &HashSet<String>) -> String {
+    let lines: Vec<&str> = adoc.lines().collect();
+    let converter = Converter { labels };
+    let mut output = converter.blocks(&lines);
+    while output.ends_with('\n') {
+        output.pop();
+    }
+    output
+}
+
+/// Labels given to section titles with an anchor line (`[[label]]`)
+pub fn heading_labels(adoc: &str) -> HashSet<String> {
+    let lines: Vec<&str> = adoc.lines().map(str::trim).collect();
+    lines
+        .windows(2)
+        .filter(|pair| heading_level(pair[1]).is_some())
+        .filter_map(|pair| attributes(pair[0])?.id)
+        .filter(|id| is_label(id))
+        .collect()
+}
+
+/// A block attribute line, like `[source,rust]`, `[[id]]` or `[#id%header]`
+#[derive(Debug, Default)]
+struct Attributes {
+    id: Option<String>,
+    /// Block style, the first positional attribute (`source`, `quote`, ...)
+    style: Option<String>,
+    /// Positional attributes after the style
+    positional: Vec<String>,
+    /// Options, from `%option` or `options="..."`
+    options: Vec<String>,
+}
+
+/// Parse a line consisting only of a block attribute list
+fn attributes(line: &str) -> Option<Attributes> {
+    if let Some(inner) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+        let id = inner.split(',').next()?.trim();
+        return (!id.is_empty()).then(|| Attributes {
+            id: Some(id.to_string()),
+            ..Attributes::default()
+        });
+    }
+
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    if inner.trim().is_empty() || inner.contains(['[', ']']) {
+        return None;
+    }
+
+    let mut attributes = Attributes::default();
+    for (n, part) in inner.split(',').enumerate() {
+        let part = part.trim();
+        if let Some((key, value)) = part.split_once('=') {
+            if matches!(key.trim(), "options" | "opts") {
+                let value = value.trim().trim_matches('"');
+                attributes.options.push(value.to_string());
+            }
+        } else if n > 0 {
+            attributes.positional.push(part.to_string());
+        } else {
+            // The style may carry `#id`, `.role` and `%option` shorthands
+            let mut marker = None;
+            let mut value = String::new();
+            for c in part.chars().chain(std::iter::once('.')) {
+                if !matches!(c, '#' | '.' | '%') {
+                    value.push(c);
+                    continue;
+                }
+                let value = std::mem::take(&mut value);
+                match marker {
+                    None if value.contains(char::is_whitespace) => return None,
+                    None if !value.is_empty() => attributes.style = Some(value),
+                    Some('#') => attributes.id = Some(value),
+                    Some('%') => attributes.options.push(value),
+                    _ => {}
+                }
+                marker = Some(c);
+            }
+        }
+    }
+    Some(attributes)
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.len() - line.trim_start_matches('=').len();
+    let rest = line[level..].strip_prefix(' ')?;
+    ((1..=6).contains(&level) && !rest.trim().is_empty()).then_some(level)
+}
+
+/// A `.Title` line naming the next block
+fn block_title(line: &str) -> Option<&str> {
+    let title = line.strip_prefix('.')?;
+    title
+        .starts_with(|c: char| !c.is_whitespace() && c != '.')
+        .then_some(title)
+}
+
+/// Fence character of a delimited block line like `----` or `____`
+fn delimiter(line: &str) -> Option<char> {
+    let c = line.chars().next()?;
+    (line.len() >= 4
+        && matches!(c, '-' | '.' | '+' | '_' | '/' | '=' | '*')
+        && line.chars().all(|other| other == c))
+    .then_some(c)
+}
+
+/// Index of the line closing the delimited block opened at `start`
+fn closing(lines: &[&str], start: usize) -> usize {
+    let fence = lines[start].trim();
+    (start + 1..lines.len())
+        .find(|&j| lines[j].trim() == fence)
+        .unwrap_or(lines.len())
+}
+
+/// Index of the first blank line or delimiter after `start`
+fn paragraph_end(lines: &[&str], start: usize) -> usize {
+    (start + 1..lines.len())
+        .find(|&j| {
+            let line = lines[j].trim();
+            line.is_empty() || line == "|===" || delimiter(line).is_some()
+        })
+        .unwrap_or(lines.len())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MarkerKind {
+    /// `*`, `**`, ... or `-`, with its nesting depth
+    Bullet(char, usize),
+    /// `.`, `..`, ... or `1.`, with its nesting depth
+    Ordered(usize),
+}
+
+/// A list item marker
+#[derive(Debug, Clone, Copy)]
+struct Marker {
+    kind: MarkerKind,
+    /// Offset of the item's text in the trimmed line
+    content: usize,
+    task: Option<bool>,
+}
+
+fn list_marker(line: &str) -> Option<Marker> {
+    let line = line.trim_start();
+    let run = |c: char| line.len() - line.trim_start_matches(c).len();
+
+    let (kind, width) = match line.chars().next()? {
+        '*' => (MarkerKind::Bullet('*', run('*')), run('*')),
+        '-' => (MarkerKind::Bullet('-', 1), 1),
+        '.' => (MarkerKind::Ordered(run('.')), run('.')),
+        c if c.is_ascii_digit() => {
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if !line[digits..].starts_with('.') {
+                return None;
+            }
+            (MarkerKind::Ordered(1), digits + 1)
+        }
+        _ => return None,
+    };
+    let text = line[width..].strip_prefix(' ')?;
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let mut content = width + 1;
+    let task = match text.get(..4) {
+        Some("[ ] ") => Some(false),
+        Some("[x] " | "[X] " | "[*] ") => Some(true),
+        _ => None,
+    }
+    .filter(|_| matches!(kind, MarkerKind::Bullet(..)));
+    if task.is_some() {
+        content += 4;
+    }
+
+    Some(Marker {
+        kind,
+        content,
+        task,
+    })
+}
+
+/// Cells of a table line, `| a | b`
+fn table_cells(line: &str) -> Vec<String> {
+    line.split('|')
+        .skip(1)
+        .map(|cell| cell.trim().to_string())
+        .collect()
+}
+
+struct Converter<'a> {
+    labels: &'a HashSet<String>,
+}
+
+impl Converter<'_> {
+    /// Convert a sequence of block-level lines
+    fn blocks(&self, lines: &[&str]) -> String {
+        let mut output = String::new();
+        let mut pending: Option<Attributes> = None;
+        let mut title: Option<&str> = None;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let trimmed = lines[i].trim();
+            if trimmed.is_empty() {
+                i += 1;
+                continue;
+            }
+            if trimmed.starts_with("//") && delimiter(trimmed).is_none() {
+                i += 1;
+                continue;
+            }
+            if let Some(attributes) = attributes(trimmed) {
+                pending = Some(attributes);
+                i += 1;
+                continue;
+            }
+            if let Some(text) = block_title(trimmed) {
+                title = Some(text);
+                i += 1;
+                continue;
+            }
+            let attributes = pending.take().unwrap_or_default();
+            let title = title.take();
+
+            // Tables and images take their title as a caption
+            let captioned = trimmed == "|===" || trimmed.starts_with("image::");
+            if let Some(title) = title.filter(|_| !captioned) {
+                output.push_str(&format!("#strong[{}]\n\n", self.inline(title)));
+            }
+
+            let end = if let Some(level) = heading_level(trimmed) {
+                output.push_str(&self.heading(level, trimmed[level..].trim(), &attributes));
+                i + 1
+            } else if trimmed == "'''" {
+                output.push_str("#line(length: 100%)\n\n");
+                i + 1
+            } else if trimmed == "<<<" {
+                output.push_str("#pagebreak()\n\n");
+                i + 1
+            } else if trimmed == "|===" {
+                let close = closing(lines, i);
+                let table = self.table(&lines[i + 1..close], &attributes);
+                output.push_str(&captioned_figure(table, title.map(|t| self.inline(t))));
+                output.push_str("\n\n");
+                close + 1
+            } else if let Some(fence) = delimiter(trimmed) {
+                let close = closing(lines, i);
+                output.push_str(&self.delimited(fence, &lines[i + 1..close], &attributes));
+                close + 1
+            } else if let Some(image) = trimmed.strip_prefix("image::").and_then(image_call) {
+                output.push_str(&captioned_figure(image, title.map(|t| self.inline(t))));
+                output.push_str("\n\n");
+                i + 1
+            } else if list_marker(lines[i]).is_some() {
+                self.list(lines, i, &mut output)
+            } else {
+                let end = paragraph_end(lines, i);
+                output.push_str(&self.paragraph(&lines[i..end], &attributes));
+                output.push_str("\n\n");
+                end
+            };
+            i = end;
+        }
+
+        output
+    }
+
+    fn heading(&self, level: usize, text: &str, attributes: &Attributes) -> String {
+        let body = self.inline(text);
+        let mut heading = if attributes.style.as_deref() == Some("discrete") {
+            format!(
+                "#heading(level: {}, numbering: none, outlined: false)[{}]",
+                level, body
+            )
+        } else {
+            format!("{} {}", "=".repeat(level), body)
+        };
+        if let Some(id) = attributes.id.as_deref().filter(|id| is_label(id)) {
+            heading.push_str(&format!(" <{}>", id));
+        }
+        heading.push_str("\n\n");
+        heading
+    }
+
+    fn paragraph(&self, lines: &[&str], attributes: &Attributes) -> String {
+        let style = attributes.style.as_deref().unwrap_or("");
+
+        // An indented paragraph is literal
+        if matches!(style, "source" | "listing" | "literal") || lines[0].starts_with([' ', '\t']) {
+            let indent = lines
+                .iter()
+                .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+                .min()
+                .unwrap_or(0);
+            let code: Vec<&str> = lines.iter().map(|line| &line[indent..]).collect();
+            let lang = if style == "source" {
+                language(attributes)
+            } else {
+                ""
+            };
+            return raw_block(&code.join("\n"), lang);
+        }
+
+        let text: Vec<&str> = lines.iter().map(|line| line.trim()).collect();
+        let text = text.join("\n");
+        if style == "quote" {
+            return quote(&self.inline(&text), attributes);
+        }
+        if let Some((_, caption)) = ADMONITIONS.iter().find(|(name, _)| *name == style) {
+            return format!("#strong[{}:] {}", caption, self.inline(&text));
+        }
+        for (name, caption) in ADMONITIONS {
+            if let Some(rest) = text.strip_prefix(name).and_then(|r| r.strip_prefix(": ")) {
+                return format!("#strong[{}:] {}", caption, self.inline(rest.trim_start()));
+            }
+        }
+        self.inline(&text)
+    }
+
+    /// Convert the body of a delimited block
+    fn delimited(&self, fence: char, body: &[&str], attributes: &Attributes) -> String {
+        let style = attributes.style.as_deref().unwrap_or("");
+        let mut block = match fence {
+            // Comment
+            '/' => return String::new(),
+            '-' => {
+                let lang = if style == "source" {
+                    language(attributes)
+                } else {
+                    ""
+                };
+                raw_block(&body.join("\n"), lang)
+            }
+            '.' => raw_block(&body.join("\n"), ""),
+            '+' => body.join("\n"),
+            '_' => quote(self.blocks(body).trim_end(), attributes),
+            _ => {
+                let content = self.blocks(body);
+                match ADMONITIONS.iter().find(|(name, _)| *name == style) {
+                    Some((_, caption)) => format!("#strong[{}:] {}", caption, content.trim_end()),
+                    None => content.trim_end().to_string(),
+                }
+            }
+        };
+        if !block.is_empty() {
+            block.push_str("\n\n");
+        }
+        block
+    }
+
+    /// Convert the list starting at line `start`, returning the line after it
+    fn list(&self, lines: &[&str], start: usize, output: &mut String) -> usize {
+        let mut items: Vec<(Marker, Vec<&str>)> = Vec::new();
+        let mut i = start;
+
+        while let Some(marker) = lines.get(i).and_then(|line| list_marker(line)) {
+            let mut text = vec![lines[i].trim_start()[marker.content..].trim()];
+            i += 1;
+            while let Some(line) = lines.get(i).map(|line| line.trim()) {
+                if line.is_empty() || list_marker(line).is_some() || delimiter(line).is_some() {
+                    break;
+                }
+                // A list continuation (`+`) joins the next paragraph to the item
+                if line != "+" {
+                    text.push(line);
+                }
+                i += 1;
+            }
+            items.push((marker, text));
+
+            // Blank lines between items do not end the list
+            match (i..lines.len()).find(|&j| !lines[j].trim().is_empty()) {
+                Some(j) if list_marker(lines[j]).is_some() => i = j,
+                _ => break,
+            }
+        }
+
+        // Nesting follows the order in which marker kinds first appear
+        let mut levels: Vec<MarkerKind> = Vec::new();
+        for (marker, text) in items {
+            match levels.iter().position(|kind| *kind == marker.kind) {
+                Some(n) => levels.truncate(n + 1),
+                None => levels.push(marker.kind),
+            }
+            let indent = "  ".repeat(levels.len() - 1);
+            let mut prefix = match marker.kind {
+                MarkerKind::Bullet(..) => "- ".to_string(),
+                MarkerKind::Ordered(_) => "+ ".to_string(),
+            };
+            match marker.task {
+                Some(true) => prefix.push_str(&format!("{} ", TASK_DONE)),
+                Some(false) => prefix.push_str(&format!("{} ", TASK_OPEN)),
+                None => {}
+            }
+
+            let body = self.inline(&text.join("\n"));
+            for (n, line) in body.lines().enumerate() {
+                output.push_str(&indent);
+                output.push_str(if n == 0 { &prefix } else { "  " });
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+        output.push('\n');
+        i
+    }
+
+    fn table(&self, lines: &[&str], attributes: &Attributes) -> String {
+        let mut cells: Vec<String> = Vec::new();
+        let mut columns = 0;
+        // Without a header option, a first line followed by a blank line is the header
+        let mut header = attributes.options.iter().any(|o| o == "header");
+
+        for (n, line) in lines.iter().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if !line.starts_with('|') {
+                // Continuation of the previous cell
+                if let Some(cell) = cells.last_mut() {
+                    cell.push('\n');
+                    cell.push_str(line);
+                }
+                continue;
+            }
+            let row = table_cells(line);
+            if columns == 0 {
+                columns = row.len();
+                header |= lines.get(n + 1).is_some_and(|next| next.trim().is_empty());
+            }
+            cells.extend(row);
+        }
+
+        let mut rows: Vec<Vec<String>> = cells
+            .chunks(columns.max(1))
+            .map(|row| row.iter().map(|cell| self.inline(cell)).collect())
+            .collect();
+        let cells = |row: &[String]| {
+            (0..columns)
+                .map(|n| format!("[{}]", row.get(n).map(String::as_str).unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut table = format!(
+            "#table(\n  columns: ({}),\n",
+            vec!["auto"; columns].join(", ")
+        );
+        if header && rows.len() > 1 {
+            let header = rows.remove(0);
+            table.push_str(&format!("  table.header({}),\n", cells(&header)));
+        }
+        for row in &rows {
+            table.push_str(&format!("  {},\n", cells(row)));
+        }
+        table.push(')');
+        table
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let mut parser = Inline {
+            chars: text.chars().collect(),
+            pos: 0,
+            labels: self.labels,
+        };
+        parser.parse(&[]).0
+    }
+}
+
+/// Source language of a `[source,lang]` block
+fn language(attributes: &Attributes) -> &str {
+    attributes
+        .positional
+        .first()
+        .map(String::as_str)
+        .unwrap_or("")
+}
+
+/// A block quote with the attribution of `[quote, attribution]`
+fn quote(body: &str, attributes: &Attributes) -> String {
+    match attributes.positional.first().filter(|a| !a.is_empty()) {
+        Some(attribution) => format!(
+            "#quote(block: true, attribution: {})[\n{}\n]",
+            string_literal(attribution),
+            body
+        ),
+        None => format!("#quote(block: true)[\n{}\n]", body),
+    }
+}
+
+/// `#image(...)` for the target and attributes of an image macro,
+/// `photo.png[Alt text,300]`
+fn image_call(target: &str) -> Option<String> {
+    let (src, attributes) = target.split_once('[')?;
+    let attributes = attributes.strip_suffix(']')?;
+    if src.is_empty() || src.contains(char::is_whitespace) {
+        return None;
+    }
+    let mut call = format!("#image({}", string_literal(src));
+    let alt = attributes.split(',').next().unwrap_or("").trim();
+    if !alt.is_empty() {
+        call.push_str(&format!(", alt: {}", string_literal(alt)));
+    }
+    call.push(')');
+    Some(call)
+}
+
+/// Wrap a block in a figure if it has a caption
+fn captioned_figure(block: String, caption: Option<String>) -> String {
+    match caption {
+        Some(caption) => format!("#figure(caption: [{}])[\n{}\n]", caption, block),
+        None => block,
+    }
+}
+
+/// Inline parser over one paragraph
+struct Inline<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    labels: &'a HashSet<String>,
+}
+
+impl Inline<'_> {
+    /// Parse until the last of `closers` closes the span, returning the
+    /// output and whether it was closed. Stops without closing when an outer
+    /// closer is reached.
+    fn parse(&mut self, closers: &[&str]) -> (String, bool) {
+        let mut output = String::new();
+        let mut text = String::new();
+
+        while self.pos < self.chars.len() {
+            if let Some((own, outer)) = closers.split_last() {
+                if self.closes(own) {
+                    flush(&mut output, &mut text);
+                    self.pos += own.chars().count();
+                    return (output, true);
+                }
+                if outer.iter().any(|closer| self.closes(closer)) {
+                    break;
+                }
+            }
+
+            let c = self.chars[self.pos];
+            let next = self.chars.get(self.pos + 1).copied();
+            match c {
+                '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                    text.extend(next);
+                    self.pos += 2;
+                }
+                // ` +` at the end of a line is a hard line break
+                '+' if self.pos > 0
+                    && self.chars[self.pos - 1] == ' '
+                    && matches!(next, None | Some('\n')) =>
+                {
+                    flush(&mut output, &mut text);
+                    output.push('\\');
+                    self.pos += 1;
+                }
+                '+' => match self.enclosed('+') {
+                    Some(literal) => text.push_str(&literal),
+                    None => {
+                        text.push('+');
+                        self.pos += 1;
+                    }
+                },
+                '`' => match self.enclosed('`') {
+                    Some(code) => {
+                        flush(&mut output, &mut text);
+                        let code = code
+                            .strip_prefix('+')
+                            .and_then(|c| c.strip_suffix('+'))
+                            .unwrap_or(&code);
+                        if code.contains('`') {
+                            output.push_str(&format!("#raw({})", string_literal(code)));
+                        } else {
+                            output.push_str(&format!("`{}`", code));
+                        }
+                    }
+                    None => {
+                        text.push('`');
+                        self.pos += 1;
+                    }
+                },
+                '*' | '_' | '#' | '^' | '~' if self.opens(c) => {
+                    let call = match c {
+                        '*' => "#strong",
+                        '_' => "#emph",
+                        '#' => "#highlight",
+                        '^' => "#super",
+                        _ => "#sub",
+                    };
+                    let closer = if next == Some(c) && matches!(c, '*' | '_' | '#') {
+                        format!("{}{}", c, c)
+                    } else {
+                        c.to_string()
+                    };
+                    self.pos += closer.len();
+                    self.span(&mut output, &mut text, closers, &closer, call);
+                }
+                '<' if self.at("<<") => match self.cross_reference() {
+                    Some(reference) => {
+                        flush(&mut output, &mut text);
+                        output.push_str(&reference);
+                    }
+                    None => {
+                        text.push_str("<<");
+                        self.pos += 2;
+                    }
+                },
+                c if c.is_ascii_alphabetic() && self.word_start() => match self.macro_call() {
+                    Some(call) => {
+                        flush(&mut output, &mut text);
+                        output.push_str(&call);
+                    }
+                    None => {
+                        text.push(c);
+                        self.pos += 1;
+                    }
+                },
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        flush(&mut output, &mut text);
+        (output, closers.is_empty())
+    }
+
+    /// Parse a span opened by a delimiter just consumed
+    fn span(
+        &mut self,
+        output: &mut String,
+        text: &mut String,
+        closers: &[&str],
+        closer: &str,
+        call: &str,
+    ) {
+        flush(output, text);
+
+        let mut inner_closers = closers.to_vec();
+        inner_closers.push(closer);
+        let (inner, closed) = self.parse(&inner_closers);
+        if closed {
+            output.push_str(&format!("{}[{}]", call, inner));
+        } else {
+            text.push_str(closer);
+            flush(output, text);
+            output.push_str(&inner);
+        }
+    }
+
+    /// Text between `delimiter` at the cursor and the next one, like
+    /// `+literal+` or `` `code` ``
+    fn enclosed(&mut self, delimiter: char) -> Option<String> {
+        let first = *self.chars.get(self.pos + 1)?;
+        if first.is_whitespace() || first == delimiter {
+            return None;
+        }
+        let end = self.chars[self.pos + 1..]
+            .iter()
+            .position(|&c| c == delimiter)?;
+        let text: String = self.chars[self.pos + 1..self.pos + 1 + end]
+            .iter()
+            .collect();
+        if text.ends_with(char::is_whitespace) {
+            return None;
+        }
+        self.pos += end + 2;
+        Some(text.replace('\n', " "))
+    }
+
+    /// `<<label>>` or `<<label,text>>`
+    fn cross_reference(&mut self) -> Option<String> {
+        let rest: String = self.chars[self.pos + 2..].iter().collect();
+        let end = rest.find(">>")?;
+        let (id, text) = match rest[..end].split_once(',') {
+            Some((id, text)) => (id.trim(), Some(text.trim())),
+            None => (rest[..end].trim(), None),
+        };
+        if !is_label(id) {
+            return None;
+        }
+        self.pos += 2 + rest[..end].chars().count() + 2;
+
+        let known = self.labels.contains(id);
+        Some(match text {
+            Some(text) if known => format!("#link(<{}>)[{}]", id, self.nested(text)),
+            Some(text) => self.nested(text),
+            None if known => format!("#ref(<{}>)", id),
+            None => escape_typst(id),
+        })
+    }
+
+    /// A macro or URL at the cursor, like `link:file.pdf[Terms]`,
+    /// `https://example.com[Example]`, `image:logo.png[]` or `stem:[x^2]`
+    fn macro_call(&mut self) -> Option<String> {
+        let start = self.pos;
+        let call = self.macro_inner();
+        if call.is_none() {
+            self.pos = start;
+        }
+        call
+    }
+
+    fn macro_inner(&mut self) -> Option<String> {
+        let name: String = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if self.chars.get(self.pos + name.len()) != Some(&':') {
+            return None;
+        }
+
+        let url = matches!(name.as_str(), "http" | "https" | "mailto");
+        if !url {
+            self.pos += name.len() + 1;
+        }
+        let target: String = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| !c.is_whitespace() && **c != '[')
+            .collect();
+        self.pos += target.chars().count();
+        let attributes = self.bracketed();
+
+        match name.as_str() {
+            "stem" | "latexmath" if target.is_empty() => Some(math_markup(&attributes?, false)),
+            "pass" if target.is_empty() => attributes,
+            "image" if !target.is_empty() => image_call(&format!("{}[{}]", target, attributes?)),
+            "xref" => {
+                let id = target.trim_start_matches('#');
+                let text = attributes.filter(|t| !t.is_empty());
+                Some(match text {
+                    Some(text) if self.labels.contains(id) => {
+                        format!("#link(<{}>)[{}]", id, self.nested(&text))
+                    }
+                    Some(text) => self.nested(&text),
+                    None if self.labels.contains(id) => format!("#ref(<{}>)", id),
+                    None => escape_typst(id),
+                })
+            }
+            "link" if !target.is_empty() => Some(self.link(&target, attributes)),
+            _ if url && target.len() > name.len() + 1 => {
+                // Trailing punctuation of a bare URL belongs to the sentence
+                let mut target = target;
+                if attributes.is_none() {
+                    while target.ends_with(['.', ',', ';', ':', '!', '?', ')']) {
+                        target.pop();
+                        self.pos -= 1;
+                    }
+                }
+                Some(self.link(&target, attributes))
+            }
+            _ => None,
+        }
+    }
+
+    fn link(&self, target: &str, text: Option<String>) -> String {
+        let text = text.map(|t| t.trim_end_matches('^').to_string());
+        match text.filter(|t| !t.is_empty()) {
+            Some(text) => format!("#link({})[{}]", string_literal(target), self.nested(&text)),
+            None => format!("#link({})", string_literal(target)),
+        }
+    }
+
+    /// `[...]` at the cursor
+    fn bracketed(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'[') {
+            return None;
+        }
+        let end = self.chars[self.pos..].iter().position(|&c| c == ']')?;
+        let text: String = self.chars[self.pos + 1..self.pos + end].iter().collect();
+        self.pos += end + 1;
+        Some(text)
+    }
+
+    /// Convert inline text found inside a macro or cross reference
+    fn nested(&self, text: &str) -> String {
+        let mut parser = Inline {
+            chars: text.chars().collect(),
+            pos: 0,
+            labels: self.labels,
+        };
+        parser.parse(&[]).0
+    }
+
+    fn at(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(n, c)| self.chars.get(self.pos + n) == Some(&c))
+    }
+
+    fn word_start(&self) -> bool {
+        self.pos == 0 || !self.chars[self.pos - 1].is_alphanumeric()
+    }
+
+    /// Whether the formatting mark at the cursor can open a span
+    ///
+    /// Single (constrained) `*`, `_` and `#` must start a word; doubled
+    /// (unconstrained) ones may appear anywhere.
+    fn opens(&self, c: char) -> bool {
+        let next = self.chars.get(self.pos + 1).copied();
+        if next == Some(c) && matches!(c, '*' | '_' | '#') {
+            return self
+                .chars
+                .get(self.pos + 2)
+                .is_some_and(|n| !n.is_whitespace());
+        }
+        let constrained = matches!(c, '*' | '_' | '#');
+        (!constrained || self.word_start()) && next.is_some_and(|n| !n.is_whitespace() && n != c)
+    }
+
+    /// Whether `closer` closes a span at the cursor
+    fn closes(&self, closer: &str) -> bool {
+        if !self.at(closer) || self.pos == 0 || self.chars[self.pos - 1].is_whitespace() {
+            return false;
+        }
+        match closer {
+            "*" | "_" | "#" => !self
+                .chars
+                .get(self.pos + 1)
+                .is_some_and(|c| c.is_alphanumeric()),
+            _ => true,
+        }
+    }
+}
+
+/// Append escaped text to the output
+fn flush(output: &mut String, text: &mut String) {
+    if !text.is_empty() {
+        let escaped = escape_text(output, text);
+        output.push_str(&escaped);
+        text.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(adoc: &str) -> String {
+        asciidoc_to_typst(adoc, &HashSet::new())
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            convert("Some *strong* and _emphasized_ text with `code` and **b**old."),
+            "Some #strong[strong] and #emph[emphasized] text with `code` and #strong[b]old."
+        );
+        assert_eq!(
+            convert("H~2~O, x^2^, #marked# and +*literal*+"),
+            "H#sub[2]O, x#super[2], #highlight[marked] and \\*literal\\*"
+        );
+        assert_eq!(
+            convert("See https://example.com[the site], https://example.org. and link:terms.pdf[]"),
+            "See #link(\"https://example.com\")[the site], #link(\"https://example.org\"). and #link(\"terms.pdf\")"
+        );
+        assert_eq!(
+            convert("Area stem:[\\pi r^2], pass:[#pagebreak()] and $5 #1"),
+            "Area $pi r^2$, #pagebreak() and \\$5 \\#1"
+        );
+        assert_eq!(convert("line one +\nline two"), "line one \\\nline two");
+    }
+
+    #[test]
+    fn test_unclosed_delimiters() {
+        assert_eq!(
+            convert("2 * 3 and snake_case_name"),
+            "2 \\* 3 and snake\\_case\\_name"
+        );
+        assert_eq!(convert("*open"), "\\*open");
+        assert_eq!(convert("C++ and 1 + 2"), "C++ and 1 + 2");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let adoc =
+            "[discrete]\n== Intro\n\n* one\n** nested\n* [x] two\n\n//\n\n. three\n. four\n\n\
+                    NOTE: Mind the gap.\n\n[quote, Ada]\n____\nquoted\n____\n\n\
+                    [source,rust]\n----\nfn main() {}\n----\n\n// a comment\n\n\
+                    .Prices\n|===\n| A | B\n\n| 1 | 2\n|===\n\n<<<";
+        let expected = "#heading(level: 2, numbering: none, outlined: false)[Intro]\n\n\
+                        - one\n  - nested\n- ☑ two\n\n\
+                        + three\n+ four\n\n\
+                        #strong[Note:] Mind the gap.\n\n\
+                        #quote(block: true, attribution: \"Ada\")[\nquoted\n]\n\n\
+                        #raw(\"fn main() {}\", block: true, lang: \"rust\")\n\n\
+                        #figure(caption: [Prices])[\n#table(\n  columns: (auto, auto),\n  table.header([A], [B]),\n  [1], [2],\n)\n]\n\n\
+                        #pagebreak()";
+        assert_eq!(convert(adoc), expected);
+    }
+
+    #[test]
+    fn test_labels() {
+        let adoc = "[[terms]]\n== Terms\n\nSee <<terms>> and <<terms,the terms>>, not <<other>>.";
+        let labels = heading_labels(adoc);
+        assert!(labels.contains("terms"));
+        assert_eq!(
+            asciidoc_to_typst(adoc, &labels),
+            "== Terms <terms>\n\nSee #ref(<terms>) and #link(<terms>)[the terms], not other."
+        );
+    }
+}
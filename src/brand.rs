@@ -9,11 +9,17 @@ use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::color::{Color, MIN_LARGE_TEXT_CONTRAST, MIN_TEXT_CONTRAST};
 use crate::config::expand_str_path;
 use crate::error::{Error, Result};
+use crate::language::{LanguageChain, DEFAULT_LANGUAGE_KEY};
+use crate::merge::deep_merge;
+use crate::typst::toml_to_json;
 
 const BRAND_FILE_NAME: &str = "brand.toml";
-const DEFAULT_LANGUAGE_KEY: &str = "default";
+
+/// Version of the layout produced by [`Brand::to_data`]
+pub const BRAND_DATA_VERSION: u32 = 1;
 
 /// A localized string supporting multiple language codes.
 #[derive(Debug, Clone, Serialize, Default, PartialEq, Eq)]
@@ -23,27 +29,10 @@ pub struct LocalizedText(BTreeMap<String, String>);
 impl LocalizedText {
     /// Resolve the best matching string for a language code with fallbacks.
     pub fn resolve(&self, lang: Option<&str>, default_lang: Option<&str>) -> Option<&str> {
-        if self.0.is_empty() {
-            return None;
-        }
-
-        if let Some(lang) = lang {
-            if let Some(val) = self.0.get(lang) {
-                return Some(val);
-            }
-        }
-
-        if let Some(lang) = default_lang {
-            if let Some(val) = self.0.get(lang) {
-                return Some(val);
-            }
-        }
-
-        if let Some(val) = self.0.get(DEFAULT_LANGUAGE_KEY) {
-            return Some(val);
-        }
-
-        self.0.values().next().map(|s| s.as_str())
+        LanguageChain::new(lang, default_lang)
+            .select(self.0.keys().map(String::as_str))
+            .and_then(|key| self.0.get(key))
+            .map(String::as_str)
     }
 
     /// Language codes present in this text (excluding the default bucket).
@@ -112,6 +101,14 @@ pub struct Brand {
     pub extra: toml::value::Table,
     /// Source information
     pub source: BrandSource,
+    /// Selected variant, if any
+    pub variant: Option<String>,
+    /// Variants that can be selected with `id:variant`
+    pub variants: Vec<String>,
+    /// Brand files and variants merged into this brand, base first
+    pub layers: Vec<BrandLayer>,
+    /// Name of the layer that set each value, by dotted key
+    pub origins: BTreeMap<String, String>,
 }
 
 /// Metadata about where the brand was loaded from.
@@ -123,6 +120,15 @@ pub struct BrandSource {
     pub root_dir: PathBuf,
 }
 
+/// One brand file or variant contributing to a resolved brand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrandLayer {
+    /// Brand id, or `id:variant` for a variant
+    pub name: String,
+    /// Directory the layer's asset paths are relative to
+    pub root_dir: PathBuf,
+}
+
 /// Colors defined for the brand.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BrandColors {
@@ -133,6 +139,148 @@ pub struct BrandColors {
     pub text: Option<String>,
     #[serde(default)]
     pub palette: BTreeMap<String, String>,
+    /// Percentages of white to mix into primary, secondary and accent for
+    /// generated `<name>-tint-<n>` palette entries
+    #[serde(default, skip_serializing)]
+    pub tints: Vec<i64>,
+    /// Percentages of black for generated `<name>-shade-<n>` entries
+    #[serde(default, skip_serializing)]
+    pub shades: Vec<i64>,
+}
+
+/// Contrast between two brand colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastCheck {
+    pub foreground: &'static str,
+    pub background: &'static str,
+    /// Contrast ratio, from 1 to 21
+    pub ratio: f64,
+    /// WCAG AA minimum for this pair
+    pub required: f64,
+}
+
+impl ContrastCheck {
+    pub fn passes(&self) -> bool {
+        self.ratio >= self.required
+    }
+}
+
+impl BrandColors {
+    /// The named colors (`primary` to `text`) that are set.
+    pub fn named(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("primary", &self.primary),
+            ("secondary", &self.secondary),
+            ("accent", &self.accent),
+            ("background", &self.background),
+            ("text", &self.text),
+        ]
+        .into_iter()
+        .filter_map(|(name, color)| color.as_deref().map(|c| (name, c)))
+        .collect()
+    }
+
+    /// Problems with the color values, one message per value.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let palette = self
+            .palette
+            .iter()
+            .map(|(name, color)| (format!("palette.{}", name), color.as_str()));
+        let named = self
+            .named()
+            .into_iter()
+            .map(|(name, color)| (name.to_string(), color));
+        for (name, color) in named.chain(palette) {
+            if Color::parse(color).is_none() {
+                problems.push(format!(
+                    "colors.{}: '{}' is not a valid color (use #rrggbb or rgb(r, g, b))",
+                    name, color
+                ));
+            }
+        }
+        for (key, steps) in [("tints", &self.tints), ("shades", &self.shades)] {
+            for step in steps.iter().filter(|s| !(1..=99).contains(*s)) {
+                problems.push(format!(
+                    "colors.{}: {} is not a percentage between 1 and 99",
+                    key, step
+                ));
+            }
+        }
+        problems
+    }
+
+    /// WCAG contrast of `text` and `primary` on `background` (white if not
+    /// set). Pairs with an unset or invalid color are skipped.
+    pub fn contrast_checks(&self) -> Vec<ContrastCheck> {
+        let background = match &self.background {
+            Some(color) => Color::parse(color),
+            None => Some(Color::WHITE),
+        };
+        let Some(background) = background else {
+            return Vec::new();
+        };
+        [
+            ("text", &self.text, MIN_TEXT_CONTRAST),
+            ("primary", &self.primary, MIN_LARGE_TEXT_CONTRAST),
+        ]
+        .into_iter()
+        .filter_map(|(name, color, required)| {
+            let color = Color::parse(color.as_deref()?)?;
+            Some(ContrastCheck {
+                foreground: name,
+                background: "background",
+                ratio: color.contrast(background),
+                required,
+            })
+        })
+        .collect()
+    }
+
+    /// Rewrite valid colors as lowercase hex and add the generated tints
+    /// and shades. Invalid values are kept for `brands validate` to report.
+    fn normalize(&mut self) {
+        for color in [
+            &mut self.primary,
+            &mut self.secondary,
+            &mut self.accent,
+            &mut self.background,
+            &mut self.text,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.palette.values_mut())
+        {
+            if let Some(parsed) = Color::parse(color) {
+                *color = parsed.to_hex();
+            }
+        }
+
+        let bases = [
+            ("primary", &self.primary),
+            ("secondary", &self.secondary),
+            ("accent", &self.accent),
+        ];
+        let mut generated = Vec::new();
+        for (name, base) in bases {
+            let Some(base) = base.as_deref().and_then(Color::parse) else {
+                continue;
+            };
+            let steps = [
+                ("tint", &self.tints, Color::WHITE),
+                ("shade", &self.shades, Color::BLACK),
+            ];
+            for (kind, steps, target) in steps {
+                for step in steps.iter().filter(|s| (1..=99).contains(*s)) {
+                    let color = base.mix(target, *step as f64 / 100.0);
+                    generated.push((format!("{}-{}-{}", name, kind, step), color.to_hex()));
+                }
+            }
+        }
+        for (key, color) in generated {
+            self.palette.entry(key).or_insert(color);
+        }
+    }
 }
 
 /// Logo asset references.
@@ -250,37 +398,117 @@ impl BrandRegistry {
         Ok(summaries)
     }
 
-    /// Load a brand by id or explicit path.
-    pub fn load(&self, id_or_path: &str) -> Result<Brand> {
+    /// Load a brand by id or explicit path, optionally followed by
+    /// `:variant`.
+    pub fn load(&self, spec: &str) -> Result<Brand> {
+        let (id_or_path, variant) = split_variant(spec);
+        let file = self.locate(id_or_path)?;
+        let layer = RawLayer::read(&file)?;
+        self.resolve(layer, variant)
+    }
+
+    /// Find the `brand.toml` for an id or explicit path.
+    fn locate(&self, id_or_path: &str) -> Result<PathBuf> {
         let direct_path = PathBuf::from(id_or_path);
         if direct_path.exists() {
-            let path = if direct_path.is_dir() {
+            return Ok(if direct_path.is_dir() {
                 direct_path.join(BRAND_FILE_NAME)
             } else {
                 direct_path
-            };
-            return Brand::from_file(path);
+            });
         }
 
         for path in &self.search_paths {
             let candidate = path.join(id_or_path).join(BRAND_FILE_NAME);
             if candidate.exists() {
-                return Brand::from_file(candidate);
+                return Ok(candidate);
             }
             let alt = path.join(format!("{}.toml", id_or_path));
             if alt.exists() {
-                return Brand::from_file(alt);
+                return Ok(alt);
             }
         }
 
         Err(Error::Brand(format!("brand '{}' not found", id_or_path)))
     }
+
+    /// Find the parent named by `extends`: a path relative to the brand,
+    /// a sibling brand directory, then the search paths.
+    fn locate_parent(&self, layer: &RawLayer, parent: &str) -> Result<PathBuf> {
+        let root = &layer.source.root_dir;
+        let relative = root.join(parent);
+        if relative.is_dir() && relative.join(BRAND_FILE_NAME).exists() {
+            return Ok(relative.join(BRAND_FILE_NAME));
+        }
+        if relative.is_file() {
+            return Ok(relative);
+        }
+        if let Some(dir) = root.parent() {
+            let sibling = dir.join(parent).join(BRAND_FILE_NAME);
+            if sibling.exists() {
+                return Ok(sibling);
+            }
+        }
+        self.locate(parent).map_err(|_| {
+            Error::Brand(format!(
+                "brand '{}' extends '{}', which was not found",
+                layer.name, parent
+            ))
+        })
+    }
+
+    /// Load the parents of `layer` and merge the chain.
+    fn resolve(&self, layer: RawLayer, variant: Option<&str>) -> Result<Brand> {
+        let mut chain = vec![layer];
+        while let Some(parent) = chain.last().and_then(|l| l.extends.clone()) {
+            let file = self.locate_parent(chain.last().unwrap(), &parent)?;
+            let key = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+            let seen = chain.iter().any(|l| {
+                fs::canonicalize(&l.source.file).unwrap_or_else(|_| l.source.file.clone()) == key
+            });
+            let parent = RawLayer::read(&file)?;
+            if seen {
+                let mut names: Vec<&str> = chain.iter().map(|l| l.name.as_str()).collect();
+                names.push(&parent.name);
+                return Err(Error::Brand(format!(
+                    "brand inheritance cycle: {}",
+                    names.join(" -> ")
+                )));
+            }
+            chain.push(parent);
+        }
+        chain.reverse();
+        Brand::from_layers(chain, variant)
+    }
 }
 
-impl Brand {
-    /// Load a brand from a `brand.toml` file.
-    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
-        let path = path.as_ref();
+/// Split `id:variant`; an existing path is never split.
+fn split_variant(spec: &str) -> (&str, Option<&str>) {
+    if Path::new(spec).exists() {
+        return (spec, None);
+    }
+    match spec.rsplit_once(':') {
+        Some((id, variant))
+            if !id.is_empty() && !variant.is_empty() && !variant.contains(['/', '\\']) =>
+        {
+            (id, Some(variant))
+        }
+        _ => (spec, None),
+    }
+}
+
+/// A parsed brand file before merging.
+struct RawLayer {
+    name: String,
+    source: BrandSource,
+    extends: Option<String>,
+    /// Brand keys with asset paths made absolute
+    table: toml::value::Table,
+    variants: toml::value::Table,
+}
+
+impl RawLayer {
+    fn read(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 Error::FileNotFound {
@@ -296,7 +524,7 @@ impl Brand {
             .map(|p| p.to_path_buf())
             .unwrap_or_else(|| PathBuf::from("."));
 
-        Self::from_str(
+        Self::parse(
             &content,
             BrandSource {
                 file: path.to_path_buf(),
@@ -305,10 +533,212 @@ impl Brand {
         )
     }
 
+    fn parse(content: &str, source: BrandSource) -> Result<Self> {
+        let mut table: toml::value::Table = toml::from_str(content)?;
+        let extends = match table.remove("extends") {
+            None => None,
+            Some(toml::Value::String(parent)) => Some(parent),
+            Some(_) => {
+                return Err(Error::Brand(
+                    "extends must be a brand id or path".to_string(),
+                ))
+            }
+        };
+        let mut variants = match table.remove("variants") {
+            None => toml::value::Table::new(),
+            Some(toml::Value::Table(variants)) => variants,
+            Some(_) => return Err(Error::Brand("variants must be a table".to_string())),
+        };
+
+        resolve_assets(&mut table, &source.root_dir)?;
+        for (name, variant) in variants.iter_mut() {
+            let toml::Value::Table(variant) = variant else {
+                return Err(Error::Brand(format!("variants.{} must be a table", name)));
+            };
+            resolve_assets(variant, &source.root_dir)?;
+        }
+
+        let name = table
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                source
+                    .root_dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+
+        Ok(Self {
+            name,
+            source,
+            extends,
+            table,
+            variants,
+        })
+    }
+}
+
+/// Make logo and font file paths absolute so they stay relative to the
+/// brand file that declared them after merging.
+fn resolve_assets(table: &mut toml::value::Table, root: &Path) -> Result<()> {
+    fn resolve_value(value: &mut toml::Value, root: &Path) -> Result<()> {
+        if let toml::Value::String(path) = value {
+            *path = resolve_path(root, path)?.to_string_lossy().into_owned();
+        }
+        Ok(())
+    }
+    fn resolve_faces(faces: &mut toml::value::Table, root: &Path) -> Result<()> {
+        for (usage, face) in faces.iter_mut() {
+            let Some(face) = face.as_table_mut() else {
+                continue;
+            };
+            if usage == "extra" {
+                resolve_faces(face, root)?;
+            } else if let Some(toml::Value::Array(files)) = face.get_mut("files") {
+                for file in files {
+                    resolve_value(file, root)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    if let Some(toml::Value::Table(logos)) = table.get_mut("logos") {
+        for (_, logo) in logos.iter_mut() {
+            resolve_value(logo, root)?;
+        }
+    }
+    if let Some(toml::Value::Table(faces)) = table.get_mut("typography") {
+        resolve_faces(faces, root)?;
+    }
+    Ok(())
+}
+
+/// Record `layer` as the origin of every value in `table`.
+fn record_origins(
+    table: &toml::value::Table,
+    prefix: &str,
+    layer: &str,
+    origins: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let nested = format!("{}.", path);
+        if let toml::Value::Table(child) = value {
+            origins.remove(&path);
+            record_origins(child, &path, layer, origins);
+        } else {
+            origins.retain(|k, _| !k.starts_with(&nested));
+            origins.insert(path, layer.to_string());
+        }
+    }
+}
+
+impl Brand {
+    /// Load a brand from a `brand.toml` file.
+    ///
+    /// Parents named by `extends` are looked up relative to the file and
+    /// among its sibling brand directories.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let layer = RawLayer::read(path.as_ref())?;
+        BrandRegistry::new(Vec::new()).resolve(layer, None)
+    }
+
     /// Parse a brand from raw TOML content.
     pub fn from_str(content: &str, source: BrandSource) -> Result<Self> {
-        let config: BrandConfig = toml::from_str(content)?;
-        Brand::from_config(config, source)
+        let layer = RawLayer::parse(content, source)?;
+        BrandRegistry::new(Vec::new()).resolve(layer, None)
+    }
+
+    /// Merge an inheritance chain (base first) and an optional variant.
+    fn from_layers(chain: Vec<RawLayer>, variant: Option<&str>) -> Result<Self> {
+        let Some((source, id)) = chain.last().map(|l| (l.source.clone(), l.name.clone())) else {
+            return Err(Error::Brand("no brand to load".to_string()));
+        };
+
+        let mut merged = toml::Value::Table(toml::value::Table::new());
+        let mut origins = BTreeMap::new();
+        let mut layers = Vec::new();
+        let mut variants: Vec<(String, BrandLayer, toml::value::Table)> = Vec::new();
+
+        for layer in chain {
+            record_origins(&layer.table, "", &layer.name, &mut origins);
+            deep_merge(&mut merged, toml::Value::Table(layer.table));
+            for (name, table) in layer.variants {
+                if let toml::Value::Table(table) = table {
+                    let variant_layer = BrandLayer {
+                        name: format!("{}:{}", layer.name, name),
+                        root_dir: layer.source.root_dir.clone(),
+                    };
+                    variants.push((name, variant_layer, table));
+                }
+            }
+            layers.push(BrandLayer {
+                name: layer.name,
+                root_dir: layer.source.root_dir,
+            });
+        }
+
+        let mut names: Vec<String> = variants.iter().map(|(name, _, _)| name.clone()).collect();
+        names.sort();
+        names.dedup();
+
+        if let Some(selected) = variant {
+            if !names.iter().any(|n| n == selected) {
+                let brand = layers.last().map(|l| l.name.as_str()).unwrap_or_default();
+                let available = if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                };
+                return Err(Error::Brand(format!(
+                    "brand '{}' has no variant '{}' (available: {})",
+                    brand, selected, available
+                )));
+            }
+            for (_, layer, table) in variants.into_iter().filter(|(n, _, _)| n == selected) {
+                record_origins(&table, "", &layer.name, &mut origins);
+                deep_merge(&mut merged, toml::Value::Table(table));
+                layers.push(layer);
+            }
+        }
+
+        // The id names the brand that was loaded and is never inherited
+        if let toml::Value::Table(table) = &mut merged {
+            table.insert("id".to_string(), toml::Value::String(id.clone()));
+        }
+        origins.insert("id".to_string(), id);
+
+        let config: BrandConfig = merged.try_into()?;
+        let mut brand = Brand::from_config(config, source)?;
+        brand.variant = variant.map(str::to_string);
+        brand.variants = names;
+        brand.layers = layers;
+        brand.origins = origins;
+        Ok(brand)
+    }
+
+    /// Name of the layer that set a dotted key (e.g. `colors.primary`).
+    ///
+    /// For a table such as localized text, the most specific layer that set
+    /// any of its entries.
+    pub fn origin(&self, key: &str) -> Option<&str> {
+        if let Some(layer) = self.origins.get(key) {
+            return Some(layer);
+        }
+        let nested = format!("{}.", key);
+        let position = |name: &str| self.layers.iter().position(|l| l.name == name);
+        self.origins
+            .iter()
+            .filter(|(k, _)| k.starts_with(&nested))
+            .map(|(_, layer)| layer.as_str())
+            .max_by_key(|layer| position(layer))
     }
 
     fn from_config(config: BrandConfig, source: BrandSource) -> Result<Self> {
@@ -373,18 +803,32 @@ impl Brand {
         let logos = BrandLogos::from_config(config.logos, &source.root_dir)?;
         let typography = BrandTypography::from_config(config.typography, &source.root_dir)?;
 
+        let mut colors = config.colors;
+        colors.normalize();
+        for problem in colors.problems() {
+            log::warn!("brand '{}': {}", config.id, problem);
+        }
+
+        let id = config.id;
         Ok(Brand {
-            id: config.id,
             default_language,
             languages,
             name: config.name,
             description: config.description,
-            colors: config.colors,
+            colors,
             logos,
             typography,
             contact: config.contact.map(|c| c.into_contact()),
-            extra: config.extra,
+            extra: fold_extra(config.extra),
+            layers: vec![BrandLayer {
+                name: id.clone(),
+                root_dir: source.root_dir.clone(),
+            }],
+            id,
             source,
+            variant: None,
+            variants: Vec::new(),
+            origins: BTreeMap::new(),
         })
     }
 
@@ -399,6 +843,109 @@ impl Brand {
             .as_ref()
             .and_then(|desc| desc.resolve(lang, self.default_language.as_deref()))
     }
+
+    /// Serialize the whole brand as template data (`data.brand`).
+    ///
+    /// The layout is versioned by `data_version` ([`BRAND_DATA_VERSION`]).
+    /// Localized text stays keyed by language and is resolved with the
+    /// document language when the data is prepared. Unknown keys of the
+    /// brand and its contact sit next to the known ones; known keys win.
+    pub fn to_data(&self) -> Result<serde_json::Value> {
+        let asset = |asset: &Option<AssetPath>| {
+            asset
+                .as_ref()
+                .map(|a| a.resolved.to_string_lossy().into_owned())
+        };
+        let faces = self.typography.faces();
+
+        let mut data = extra_to_json(&self.extra)?;
+        let known = serde_json::json!({
+            "data_version": BRAND_DATA_VERSION,
+            "id": self.id,
+            "variant": self.variant,
+            "name": self.name,
+            "description": self.description,
+            "default_language": self.default_language,
+            "languages": self.languages,
+            "colors": self.colors,
+            "logos": {
+                "primary": asset(&self.logos.primary),
+                "secondary": asset(&self.logos.secondary),
+                "monochrome": asset(&self.logos.monochrome),
+                "favicon": asset(&self.logos.favicon)
+            },
+            "logo": asset(&self.logos.primary),
+            "typography": faces
+                .iter()
+                .map(|(usage, face)| (usage.to_string(), face.to_data()))
+                .collect::<serde_json::Map<_, _>>(),
+            "fonts": faces
+                .iter()
+                .map(|(usage, face)| (usage.to_string(), face.family.clone().into()))
+                .collect::<serde_json::Map<_, _>>(),
+            "root": self.source.root_dir.to_string_lossy(),
+            "layers": self
+                .layers
+                .iter()
+                .map(|layer| serde_json::json!({
+                    "name": layer.name,
+                    "root": layer.root_dir.to_string_lossy()
+                }))
+                .collect::<Vec<_>>()
+        });
+        if let serde_json::Value::Object(known) = known {
+            data.extend(known);
+        }
+
+        if let Some(contact) = &self.contact {
+            let mut map = extra_to_json(&contact.extra)?;
+            if let serde_json::Value::Object(known) = serde_json::json!({
+                "company": contact.company,
+                "address": contact.address,
+                "phone": contact.phone,
+                "email": contact.email,
+                "website": contact.website
+            }) {
+                map.extend(known);
+            }
+            data.insert("contact".to_string(), serde_json::Value::Object(map));
+        }
+
+        let mut data = serde_json::Value::Object(data);
+        strip_nulls(&mut data);
+        Ok(data)
+    }
+}
+
+impl BrandTypography {
+    /// All font faces by usage: `body`, `heading`, `mono`, then the extras.
+    pub fn faces(&self) -> Vec<(&str, &FontFace)> {
+        let named = [
+            ("body", self.body.as_ref()),
+            ("heading", self.heading.as_ref()),
+            ("mono", self.mono.as_ref()),
+        ];
+        named
+            .into_iter()
+            .filter_map(|(usage, face)| face.map(|f| (usage, f)))
+            .chain(
+                self.extra
+                    .iter()
+                    .map(|(usage, face)| (usage.as_str(), face)),
+            )
+            .collect()
+    }
+}
+
+impl FontFace {
+    fn to_data(&self) -> serde_json::Value {
+        serde_json::json!({
+            "family": self.family,
+            "files": self.files,
+            "weight": self.weight,
+            "style": self.style
+        })
+    }
 }
 
 /// Internal representation of a brand TOML file.
@@ -420,7 +967,8 @@ struct BrandConfig {
     pub typography: BrandTypographyConfig,
     #[serde(default)]
     pub contact: Option<BrandContactConfig>,
-    #[serde(default)]
+    /// Any other top-level keys
+    #[serde(flatten)]
     pub extra: toml::value::Table,
 }
 
@@ -470,8 +1018,9 @@ struct BrandTypographyConfig {
     pub body: Option<FontFaceConfig>,
     pub heading: Option<FontFaceConfig>,
     pub mono: Option<FontFaceConfig>,
-    #[serde(default)]
-    pub extra: BTreeMap<String, FontFaceConfig>,
+    /// Further font faces by usage name
+    #[serde(flatten)]
+    pub extra: toml::value::Table,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -492,7 +1041,10 @@ impl BrandTypography {
         let mono = FontFace::from_config(config.mono, base_dir)?;
 
         let mut extra = BTreeMap::new();
-        for (key, cfg) in config.extra {
+        for (key, value) in fold_extra(config.extra) {
+            let cfg: FontFaceConfig = value
+                .try_into()
+                .map_err(|e| Error::Brand(format!("typography.{}: {}", key, e)))?;
             if let Some(face) = FontFace::from_config(Some(cfg), base_dir)? {
                 extra.insert(key, face);
             }
@@ -544,7 +1096,8 @@ struct BrandContactConfig {
     pub phone: Option<String>,
     pub email: Option<String>,
     pub website: Option<String>,
-    #[serde(default)]
+    /// Any other contact keys (legal form, street, people, ...)
+    #[serde(flatten)]
     pub extra: toml::value::Table,
 }
 
@@ -556,9 +1109,36 @@ impl BrandContactConfig {
             phone: self.phone,
             email: self.email,
             website: self.website,
-            extra: self.extra,
+            extra: fold_extra(self.extra),
+        }
+    }
+}
+
+fn extra_to_json(table: &toml::value::Table) -> Result<serde_json::Map<String, serde_json::Value>> {
+    table
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), toml_to_json(value)?)))
+        .collect()
+}
+
+/// Drop `null` members so that absent brand values don't shadow content data
+fn strip_nulls(value: &mut serde_json::Value) {
+    if let serde_json::Value::Object(map) = value {
+        map.retain(|_, v| !v.is_null());
+        map.values_mut().for_each(strip_nulls);
+    }
+}
+
+/// Collect unknown keys, folding in an explicit `[extra]` table and dropping
+/// the `$schema` reference.
+fn fold_extra(mut table: toml::value::Table) -> toml::value::Table {
+    table.remove("$schema");
+    if let Some(toml::Value::Table(nested)) = table.remove("extra") {
+        for (key, value) in nested {
+            table.entry(key).or_insert(value);
         }
     }
+    table
 }
 
 fn resolve_path(base_dir: &Path, path: &str) -> Result<PathBuf> {
@@ -664,6 +1244,209 @@ email = "hello@example.com"
         assert_eq!(brand.typography.body.as_ref().unwrap().files.len(), 1);
     }
 
+    #[test]
+    fn serializes_whole_brand_as_data() {
+        let source = BrandSource {
+            file: PathBuf::from("/brands/acme/brand.toml"),
+            root_dir: PathBuf::from("/brands/acme"),
+        };
+        let toml = format!(
+            "logo-width = \"4cm\"\n{}\n\
+             [typography.caption]\nfamily = \"Inter\"\nweight = 300\nstyle = \"italic\"\n\n\
+             [extra]\nslogan = \"Owls at work\"\n",
+            sample_brand().replace(
+                "email = \"hello@example.com\"",
+                "email = \"hello@example.com\"\nlegal-form = \"GmbH\"\n\
+                 street = { de = \"Zukunftsmeile 2\", en = \"2 Future Mile\" }\n\
+                 people = [\"Tommy\", \"Laban\"]",
+            )
+        );
+        let brand = Brand::from_str(&toml, source).unwrap();
+        let data = brand.to_data().unwrap();
+
+        assert_eq!(data["data_version"], BRAND_DATA_VERSION);
+        assert_eq!(data["name"]["de"], "ByteOwlz GmbH");
+        assert_eq!(data["description"]["en"], "Default brand");
+        assert_eq!(data["logo-width"], "4cm");
+        assert_eq!(data["slogan"], "Owls at work");
+        assert!(data.get("$schema").is_none());
+        assert_eq!(data["logo"], "/brands/acme/logo.svg");
+        assert!(data["logos"].get("secondary").is_none());
+        assert!(data["colors"].get("secondary").is_none());
+
+        assert_eq!(data["typography"]["caption"]["weight"], 300);
+        assert_eq!(data["typography"]["caption"]["style"], "italic");
+        assert_eq!(
+            data["typography"]["body"]["files"][0],
+            "/brands/acme/fonts/Inter-Regular.ttf"
+        );
+        assert_eq!(data["fonts"]["heading"], "Inter Tight");
+
+        assert_eq!(data["contact"]["legal-form"], "GmbH");
+        assert_eq!(data["contact"]["street"]["en"], "2 Future Mile");
+        assert_eq!(data["contact"]["people"][1], "Laban");
+        assert_eq!(data["contact"]["email"], "hello@example.com");
+        assert!(data["contact"].get("phone").is_none());
+    }
+
+    #[test]
+    fn merges_parent_chain_and_variants() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("base")).unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("base/brand.toml"), sample_brand()).unwrap();
+        fs::write(
+            root.join("sub/brand.toml"),
+            r##"
+id = "sub"
+extends = "base"
+name = "Sub"
+
+[colors]
+primary = "#ff0000"
+
+[logos]
+primary = "sub-logo.svg"
+
+[variants.dark.colors]
+background = "#000000"
+
+[variants.print.logos]
+primary = "print.svg"
+"##,
+        )
+        .unwrap();
+
+        let registry = BrandRegistry::new(vec![root.to_path_buf()]);
+        let brand = registry.load("sub").unwrap();
+        assert_eq!(brand.id, "sub");
+        assert_eq!(brand.name_for(Some("de")), Some("Sub"));
+        assert_eq!(brand.colors.primary.as_deref(), Some("#ff0000"));
+        assert_eq!(brand.colors.accent.as_deref(), Some("#38bdf8"));
+        assert_eq!(
+            brand.logos.primary.as_ref().unwrap().resolved,
+            root.join("sub/sub-logo.svg")
+        );
+        assert_eq!(
+            brand.logos.monochrome.as_ref().unwrap().resolved,
+            root.join("base/assets/logo-mono.svg")
+        );
+        assert_eq!(
+            brand.typography.body.as_ref().unwrap().files[0],
+            root.join("base/fonts/Inter-Regular.ttf")
+        );
+        assert_eq!(brand.variants, vec!["dark", "print"]);
+        assert_eq!(brand.origin("colors.primary"), Some("sub"));
+        assert_eq!(brand.origin("colors.accent"), Some("byteowlz"));
+        assert_eq!(brand.origin("name"), Some("sub"));
+        assert_eq!(brand.origin("contact.company"), Some("byteowlz"));
+
+        let dark = registry.load("sub:dark").unwrap();
+        assert_eq!(dark.variant.as_deref(), Some("dark"));
+        assert_eq!(dark.colors.background.as_deref(), Some("#000000"));
+        assert_eq!(dark.origin("colors.background"), Some("sub:dark"));
+        let names: Vec<&str> = dark.layers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["byteowlz", "sub", "sub:dark"]);
+
+        let print = registry.load("sub:print").unwrap();
+        assert_eq!(
+            print.logos.primary.as_ref().unwrap().resolved,
+            root.join("sub/print.svg")
+        );
+
+        let err = registry.load("sub:neon").unwrap_err().to_string();
+        assert!(
+            err.contains("no variant 'neon' (available: dark, print)"),
+            "{}",
+            err
+        );
+
+        // A child without an id is named after its directory, not its parent
+        fs::create_dir_all(root.join("leaf")).unwrap();
+        fs::write(
+            root.join("leaf/brand.toml"),
+            "extends = \"sub\"\nname = \"Leaf\"\n",
+        )
+        .unwrap();
+        let leaf = registry.load("leaf").unwrap();
+        assert_eq!(leaf.id, "leaf");
+        assert_eq!(leaf.origin("id"), Some("leaf"));
+        let names: Vec<&str> = leaf.layers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["byteowlz", "sub", "leaf"]);
+    }
+
+    #[test]
+    fn rejects_inheritance_cycles() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for (id, parent) in [("a", "b"), ("b", "c"), ("c", "a")] {
+            fs::create_dir_all(root.join(id)).unwrap();
+            fs::write(
+                root.join(id).join("brand.toml"),
+                format!(
+                    "id = \"{}\"\nname = \"{}\"\nextends = \"{}\"\n",
+                    id, id, parent
+                ),
+            )
+            .unwrap();
+        }
+
+        let registry = BrandRegistry::new(vec![root.to_path_buf()]);
+        let err = registry.load("a").unwrap_err().to_string();
+        assert!(err.contains("cycle: a -> b -> c -> a"), "{}", err);
+    }
+
+    #[test]
+    fn normalizes_colors_and_checks_contrast() {
+        let source = BrandSource {
+            file: PathBuf::from("brand.toml"),
+            root_dir: PathBuf::from("."),
+        };
+        let toml = r##"
+id = "acme"
+name = "Acme"
+
+[colors]
+primary = "rgb(15, 23, 42)"
+accent = "#38BDF8"
+text = "#AAA"
+tints = [50]
+shades = [50, 150, 300, -10]
+
+[colors.palette]
+highlight = "yellow"
+primary-tint-50 = "#eeeeee"
+"##;
+        let brand = Brand::from_str(toml, source).unwrap();
+        let colors = &brand.colors;
+        assert_eq!(colors.primary.as_deref(), Some("#0f172a"));
+        assert_eq!(colors.accent.as_deref(), Some("#38bdf8"));
+        assert_eq!(colors.text.as_deref(), Some("#aaaaaa"));
+        assert_eq!(colors.palette["primary-tint-50"], "#eeeeee");
+        assert_eq!(colors.palette["primary-shade-50"], "#080c15");
+        assert_eq!(colors.palette["accent-tint-50"], "#9cdefc");
+        assert!(!colors.palette.contains_key("primary-shade-150"));
+
+        let problems = colors.problems();
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems[0].starts_with("colors.palette.highlight: 'yellow'"));
+        assert!(problems[1].contains("colors.shades: 150"));
+        assert!(problems[2].contains("colors.shades: 300"));
+        assert!(problems[3].contains("colors.shades: -10"));
+
+        let checks = colors.contrast_checks();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].foreground, "text");
+        assert!(!checks[0].passes());
+        assert_eq!(checks[1].foreground, "primary");
+        assert!(checks[1].passes());
+
+        let data = brand.to_data().unwrap();
+        assert!(data["colors"].get("tints").is_none());
+        assert_eq!(data["colors"]["palette"]["primary-shade-50"], "#080c15");
+    }
+
     #[test]
     fn registry_discovers_brands_in_search_paths() {
         let dir = tempdir().unwrap();
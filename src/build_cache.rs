@@ -0,0 +1,431 @@
+//! Content-addressed compile cache
+//!
+//! Compiled artifacts are stored under `<cache_dir>/builds/<key>/`, where the
+//! key hashes every input of a compile. A later compile with the same key
+//! copies the stored artifacts instead of running Typst.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use crate::typst::{CompileResult, ElementPosition, OutputFormat, OutputTarget, PageInfo};
+
+/// Subdirectory of the cache directory holding build entries
+const BUILDS_DIR: &str = "builds";
+
+/// Metadata stored next to the artifacts of an entry
+const ENTRY_FILE: &str = "entry.json";
+
+/// Incrementally hashes compile inputs into a cache key
+pub struct CacheKey(Sha256);
+
+impl Default for CacheKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheKey {
+    pub fn new() -> Self {
+        Self(Sha256::new())
+    }
+
+    /// Add a labelled value
+    pub fn add(&mut self, label: &str, value: impl AsRef<[u8]>) -> &mut Self {
+        let value = value.as_ref();
+        self.0.update(label.as_bytes());
+        self.0.update((value.len() as u64).to_le_bytes());
+        self.0.update(value);
+        self
+    }
+
+    /// Add a file's path and contents (missing files hash as absent)
+    pub fn add_file(&mut self, path: &Path) -> &mut Self {
+        self.add("file", path.to_string_lossy().as_bytes());
+        match fs::read(path) {
+            Ok(bytes) => self.add("contents", bytes),
+            Err(_) => self.add("missing", []),
+        }
+    }
+
+    /// Add every file below a directory, in sorted order
+    pub fn add_dir(&mut self, dir: &Path) -> &mut Self {
+        let mut files = Vec::new();
+        collect_files(dir, &mut files);
+        files.sort();
+        self.add("dir", dir.to_string_lossy().as_bytes());
+        for file in files {
+            self.add_file(&file);
+        }
+        self
+    }
+
+    /// Hex digest
+    pub fn finish(&self) -> String {
+        self.0
+            .clone()
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, out);
+        } else {
+            out.push(path);
+        }
+    }
+}
+
+/// What is stored for one cached artifact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    format: String,
+    /// Number of page files (paged formats only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pages: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    positions: Option<Vec<ElementPosition>>,
+    /// Warnings of the original compile, reported again on a hit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Result of `tmpltr cache prune`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub remaining: usize,
+}
+
+/// The on-disk build cache
+#[derive(Debug, Clone)]
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    /// Build cache inside the tmpltr cache directory
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join(BUILDS_DIR),
+        }
+    }
+
+    /// Directory holding the entries
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Copy a cached artifact to `target`, if an entry for `key` exists.
+    ///
+    /// Returns `None` on a miss or when the entry is incomplete.
+    pub fn restore(&self, key: &str, target: &OutputTarget) -> Result<Option<CompileResult>> {
+        let entry_dir = self.dir.join(key);
+        let Some(entry) = fs::read_to_string(entry_dir.join(ENTRY_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str::<Entry>(&json).ok())
+        else {
+            return Ok(None);
+        };
+        let ext = target.format.typst_format();
+
+        let (output, pages) = match entry.pages {
+            Some(total) => {
+                let mut pages = Vec::new();
+                for page in 1..=total {
+                    let file = page_path(&target.output, page, total);
+                    copy_artifact(&entry_dir.join(format!("page-{}.{}", page, ext)), &file)?;
+                    pages.push(PageInfo { page, file });
+                }
+                (None, Some(pages))
+            }
+            None => {
+                copy_artifact(&entry_dir.join(format!("output.{}", ext)), &target.output)?;
+                (Some(target.output.clone()), None)
+            }
+        };
+
+        touch(&entry_dir.join(ENTRY_FILE));
+        Ok(Some(CompileResult {
+            status: "ok".to_string(),
+            format: entry.format,
+            output,
+            pages,
+            positions: entry.positions,
+            cached: true,
+            diagnostics: entry.diagnostics,
+        }))
+    }
+
+    /// Store the artifacts of a fresh compile under `key`
+    pub fn store(&self, key: &str, result: &CompileResult) -> Result<()> {
+        let entry_dir = self.dir.join(key);
+        if entry_dir.join(ENTRY_FILE).exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| Error::Cache(format!("creating {}: {}", self.dir.display(), e)))?;
+
+        // Fill a temp dir first so concurrent compiles never see half an entry
+        let staging = tempfile::Builder::new()
+            .prefix(".tmp-")
+            .tempdir_in(&self.dir)
+            .map_err(|e| Error::Cache(format!("creating build cache entry: {}", e)))?;
+        let ext = OutputFormat::from_str(&result.format)
+            .map(|f| f.typst_format())
+            .unwrap_or("pdf");
+
+        let pages = match (&result.output, &result.pages) {
+            (_, Some(pages)) => {
+                for page in pages {
+                    copy_artifact(
+                        &page.file,
+                        &staging.path().join(format!("page-{}.{}", page.page, ext)),
+                    )?;
+                }
+                Some(pages.len() as u32)
+            }
+            (Some(output), None) => {
+                copy_artifact(output, &staging.path().join(format!("output.{}", ext)))?;
+                None
+            }
+            (None, None) => return Ok(()),
+        };
+
+        let entry = Entry {
+            format: result.format.clone(),
+            pages,
+            positions: result.positions.clone(),
+            diagnostics: result.diagnostics.clone(),
+        };
+        fs::write(
+            staging.path().join(ENTRY_FILE),
+            serde_json::to_string_pretty(&entry)?,
+        )
+        .map_err(|e| Error::Cache(format!("writing build cache entry: {}", e)))?;
+
+        let staging = staging.keep();
+        if fs::rename(&staging, &entry_dir).is_err() {
+            // Another process stored the same key first
+            let _ = fs::remove_dir_all(&staging);
+        }
+        Ok(())
+    }
+
+    /// Remove entries not used within `older_than` (all entries if `None`)
+    pub fn prune(&self, older_than: Option<Duration>) -> Result<PruneReport> {
+        let mut report = PruneReport::default();
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(report);
+        };
+        let now = SystemTime::now();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            // Entries being written have no entry file yet; use the dir's age
+            let last_used = fs::metadata(path.join(ENTRY_FILE))
+                .or_else(|_| fs::metadata(&path))
+                .and_then(|m| m.modified())
+                .ok();
+            let expired = match (older_than, last_used) {
+                (None, _) | (_, None) => true,
+                (Some(max_age), Some(used)) => {
+                    now.duration_since(used).unwrap_or_default() > max_age
+                }
+            };
+            if !expired {
+                report.remaining += 1;
+                continue;
+            }
+
+            let size = dir_size(&path);
+            fs::remove_dir_all(&path)
+                .map_err(|e| Error::Cache(format!("removing {}: {}", path.display(), e)))?;
+            report.removed += 1;
+            report.freed_bytes += size;
+        }
+
+        Ok(report)
+    }
+}
+
+/// File for page `page` of a paged output pattern
+fn page_path(pattern: &Path, page: u32, total: u32) -> PathBuf {
+    let width = total.to_string().len();
+    let name = pattern
+        .to_string_lossy()
+        .replace("{0p}", &format!("{:0width$}", page, width = width))
+        .replace("{p}", &page.to_string())
+        .replace("{t}", &total.to_string());
+    PathBuf::from(name)
+}
+
+fn copy_artifact(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| Error::Cache(format!("creating {}: {}", parent.display(), e)))?;
+    }
+    fs::copy(from, to).map_err(|e| {
+        Error::Cache(format!(
+            "copying {} to {}: {}",
+            from.display(),
+            to.display(),
+            e
+        ))
+    })?;
+    Ok(())
+}
+
+/// Mark an entry as recently used
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    files
+        .iter()
+        .filter_map(|f| fs::metadata(f).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(
+        format: &str,
+        output: Option<PathBuf>,
+        pages: Option<Vec<PageInfo>>,
+    ) -> CompileResult {
+        CompileResult {
+            status: "ok".to_string(),
+            format: format.to_string(),
+            output,
+            pages,
+            positions: None,
+            cached: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_key_changes_with_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("template.typ");
+        fs::write(&file, "Hello").unwrap();
+
+        let key = |data: &str| CacheKey::new().add("data", data).add_file(&file).finish();
+        let first = key("{}");
+        assert_eq!(first, key("{}"));
+        assert_ne!(first, key("{\"a\":1}"));
+
+        fs::write(&file, "Hello!").unwrap();
+        assert_ne!(first, key("{}"));
+    }
+
+    #[test]
+    fn test_store_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::new(&dir.path().join("cache"));
+
+        let pdf = dir.path().join("a.pdf");
+        fs::write(&pdf, "%PDF").unwrap();
+        cache
+            .store("k1", &result("pdf", Some(pdf.clone()), None))
+            .unwrap();
+
+        let pages: Vec<PageInfo> = (1..=2)
+            .map(|page| {
+                let file = dir.path().join(format!("a-{}.svg", page));
+                fs::write(&file, format!("<svg>{}</svg>", page)).unwrap();
+                PageInfo { page, file }
+            })
+            .collect();
+        cache
+            .store("k2", &result("svg", None, Some(pages)))
+            .unwrap();
+
+        let target = OutputTarget {
+            format: OutputFormat::Pdf,
+            output: dir.path().join("out/b.pdf"),
+        };
+        let restored = cache.restore("k1", &target).unwrap().unwrap();
+        assert!(restored.cached);
+        assert_eq!(fs::read_to_string(&target.output).unwrap(), "%PDF");
+
+        let target = OutputTarget {
+            format: OutputFormat::Svg,
+            output: dir.path().join("b-{0p}.svg"),
+        };
+        let restored = cache.restore("k2", &target).unwrap().unwrap();
+        let pages = restored.pages.unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("b-2.svg")).unwrap(),
+            "<svg>2</svg>"
+        );
+
+        assert!(cache.restore("missing", &target).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::new(dir.path());
+        let pdf = dir.path().join("a.pdf");
+        fs::write(&pdf, "%PDF").unwrap();
+        cache.store("k1", &result("pdf", Some(pdf), None)).unwrap();
+
+        let report = cache.prune(Some(Duration::from_secs(3600))).unwrap();
+        assert_eq!((report.removed, report.remaining), (0, 1));
+
+        let report = cache.prune(None).unwrap();
+        assert_eq!((report.removed, report.remaining), (1, 0));
+        assert!(report.freed_bytes > 4);
+        assert!(cache
+            .restore(
+                "k1",
+                &OutputTarget {
+                    format: OutputFormat::Pdf,
+                    output: dir.path().join("b.pdf"),
+                }
+            )
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_page_path() {
+        assert_eq!(
+            page_path(Path::new("out/p-{0p}-of-{t}.png"), 3, 12),
+            PathBuf::from("out/p-03-of-12.png")
+        );
+        assert_eq!(
+            page_path(Path::new("p-{p}.svg"), 3, 12),
+            PathBuf::from("p-3.svg")
+        );
+    }
+}
@@ -1,22 +1,40 @@
 //! Command implementations for tmpltr
 
+use std::collections::BTreeSet;
 use std::fs;
 use std::io::{self, IsTerminal, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use crate::brand::BrandRegistry;
+use crate::build_cache::BuildCache;
 use crate::cache::{DocumentCache, RecentDocument};
 use crate::config::{load_or_create_config, write_default_config, ResolvedPaths};
-use crate::content::{ContentBuilder, ContentFile};
-use crate::error::{Error, Result};
+use crate::content::{
+    is_image_width, write_block_data, BlockType, ContentBlock, ContentBuilder, ContentFile,
+};
+use crate::convert::ConverterRegistry;
+use crate::error::{Error, ErrorReport, Result};
+use crate::merge::{load_rows, merge_row, MergeEntry, MergeManifest};
+use crate::migrate::migrate_document;
+use crate::output::{
+    expand as expand_output, extension as output_extension, path_for_format, resolve_output_path,
+};
+use crate::schema::{load_schema, validate_against_schema, SchemaViolation};
+use crate::serve::PreviewServer;
 use crate::template::{TemplateInfo, TemplateRegistry, TemplateSummary};
-use crate::typst::{CompileOptions, OutputFormat, TypstCompiler};
+use crate::typst::{
+    resolve_data, toml_to_json, CompileOptions, CompileResult, OutputFormat, OutputTarget,
+    TypstCompiler,
+};
+use crate::watch::{is_within, WatchSet};
 
 use super::{
     AddCommand, AddFontArgs, AddLogoArgs, AddTemplateArgs, BlocksArgs, BrandsCommand,
-    BrandsListArgs, BrandsNewArgs, BrandsShowArgs, BrandsValidateArgs, CommonOpts, CompileArgs,
-    ConfigCommand, ExampleArgs, GetArgs, InitArgs, NewArgs, NewTemplateArgs, RecentArgs, SetArgs,
-    TemplatesArgs, ValidateArgs, WatchArgs,
+    BrandsListArgs, BrandsNewArgs, BrandsShowArgs, BrandsValidateArgs, CacheCommand, CommonOpts,
+    CompileArgs, ConfigCommand, ExampleArgs, GetArgs, InitArgs, MergeArgs, NewArgs,
+    NewTemplateArgs, RecentArgs, SetArgs, TemplatesArgs, ValidateArgs, WatchArgs,
 };
 
 /// Runtime context for command execution
@@ -91,9 +109,13 @@ pub fn handle_init(ctx: &Context, args: InitArgs) -> Result<()> {
         }
     }
 
+    if args.update {
+        return handle_init_update(ctx, &args, &template);
+    }
+
     // Build content file
     let mut builder =
-        ContentBuilder::new(&args.template.display().to_string()).template_id(&template.id);
+        ContentBuilder::new(args.template.display().to_string()).template_id(&template.id);
 
     if let Some(ref version) = template.version {
         builder = builder.template_version(version);
@@ -104,8 +126,7 @@ pub fn handle_init(ctx: &Context, args: InitArgs) -> Result<()> {
 
     // Add fields from editable() calls
     for field in &template.fields {
-        let value = field.default.clone().unwrap_or_default();
-        builder = builder.field(&field.path, toml::Value::String(value));
+        builder = builder.field(&field.path, field.default_value());
     }
 
     // If --analyze-data, also extract data access patterns
@@ -163,13 +184,21 @@ pub fn handle_init(ctx: &Context, args: InitArgs) -> Result<()> {
     // Add blocks from editable-block() calls
     for block in &template.blocks {
         let title = block.title.clone().unwrap_or_else(|| block.path.clone());
-        let content = block.default_content.clone().unwrap_or_default();
-        builder = builder.block(
-            block.path.strip_prefix("blocks.").unwrap_or(&block.path),
-            title,
-            block.format,
-            content,
-        );
+        let name = block.path.strip_prefix("blocks.").unwrap_or(&block.path);
+        builder = if block.block_type == BlockType::Text {
+            let content = block.default_content.clone().unwrap_or_default();
+            builder.block(name, title, block.format, content)
+        } else {
+            builder.typed_block(
+                name,
+                ContentBlock::skeleton(
+                    title,
+                    block.block_type,
+                    &block.fields,
+                    block.default_content.as_deref(),
+                ),
+            )
+        };
     }
 
     let content = builder.build()?;
@@ -231,6 +260,64 @@ pub fn handle_init(ctx: &Context, args: InitArgs) -> Result<()> {
     ctx.output(&result, &message)
 }
 
+/// Update an existing content file to match its template (migration mode)
+fn handle_init_update(ctx: &Context, args: &InitArgs, template: &TemplateInfo) -> Result<()> {
+    let content_path = args.content.as_ref().ok_or_else(|| {
+        Error::Content("--update requires an existing CONTENT file to migrate".to_string())
+    })?;
+
+    let content_str = fs::read_to_string(content_path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            Error::FileNotFound {
+                path: content_path.clone(),
+            }
+        } else {
+            Error::Io(e)
+        }
+    })?;
+    let mut doc: toml_edit::DocumentMut = content_str
+        .parse()
+        .map_err(|e| Error::Content(format!("parsing TOML: {}", e)))?;
+
+    let template_content = fs::read_to_string(&args.template).map_err(|e| {
+        Error::Io(std::io::Error::new(
+            e.kind(),
+            format!("reading template {}: {}", args.template.display(), e),
+        ))
+    })?;
+    let data_accesses = TemplateInfo::extract_data_access(&template_content);
+
+    let mut report = migrate_document(&mut doc, template, &data_accesses)?;
+    report.file = content_path.clone();
+
+    if ctx.common.dry_run {
+        log::info!("dry-run: would update {}", content_path.display());
+        report.status = "dry-run".to_string();
+    } else if report.has_changes() {
+        let temp_path = content_path.with_extension("toml.tmp");
+        fs::write(&temp_path, doc.to_string())?;
+        fs::rename(&temp_path, content_path)?;
+    }
+
+    let human = if report.has_changes() {
+        format!(
+            "{} {}: {} fields added, {} blocks added, {} obsolete keys flagged",
+            if ctx.common.dry_run {
+                "Would update"
+            } else {
+                "Updated"
+            },
+            content_path.display(),
+            report.added_fields.len(),
+            report.added_blocks.len(),
+            report.flagged.len()
+        )
+    } else {
+        format!("{} is up to date", content_path.display())
+    };
+    ctx.output(&report, &human)
+}
+
 /// Handle new command
 pub fn handle_new(ctx: &Context, args: NewArgs) -> Result<()> {
     let search_paths = vec![
@@ -319,7 +406,10 @@ pub fn handle_compile(ctx: &mut Context, args: CompileArgs) -> Result<()> {
     // Update cache
     ctx.cache.update(&content)?;
 
-    let compiler = TypstCompiler::from_config(&ctx.config)?;
+    let mut compiler = TypstCompiler::from_config(&ctx.config)?;
+    if !args.no_cache && !ctx.common.dry_run {
+        compiler = compiler.with_build_cache(BuildCache::new(&ctx.paths.cache_dir));
+    }
 
     // Load brand if specified
     let (brand_data, brand_font_paths) = load_brand_for_compile(ctx, args.brand.as_deref())?;
@@ -331,9 +421,11 @@ pub fn handle_compile(ctx: &mut Context, args: CompileArgs) -> Result<()> {
             format: None,
             brand_data,
             brand_font_paths,
+            lang: args.lang.clone(),
             with_positions: false,
             experimental_html: false,
             check_only: true,
+            ppi: None,
         };
 
         if ctx.common.dry_run {
@@ -344,14 +436,16 @@ pub fn handle_compile(ctx: &mut Context, args: CompileArgs) -> Result<()> {
             return Ok(());
         }
 
-        compiler.compile(&content, &options)?;
+        let result = compiler.compile(&content, &options)?;
+        print_diagnostics(ctx, std::slice::from_ref(&result));
 
         ctx.output(
             &serde_json::json!({
                 "status": "ok",
                 "valid": true,
                 "content": args.content,
-                "template": content.meta.template
+                "template": content.meta.template,
+                "diagnostics": result.diagnostics
             }),
             &format!(
                 "{}: valid (template: {})",
@@ -360,49 +454,49 @@ pub fn handle_compile(ctx: &mut Context, args: CompileArgs) -> Result<()> {
             ),
         )
     } else {
-        let output = args.output.unwrap_or_else(|| {
-            let stem = args
-                .content
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("output");
-            PathBuf::from(format!("{}.pdf", stem))
-        });
-
-        let format = args.format.as_deref().and_then(OutputFormat::from_str);
+        let targets = output_targets(
+            ctx,
+            &content,
+            args.output.as_deref(),
+            args.format.as_deref(),
+        )?;
 
         let options = CompileOptions {
-            output,
-            format,
+            output: targets[0].output.clone(),
+            format: Some(targets[0].format),
             brand_data,
             brand_font_paths,
+            lang: args.lang.clone(),
             with_positions: args.with_positions,
             experimental_html: args.experimental_html,
             check_only: false,
+            ppi: Some(args.ppi.unwrap_or(ctx.config.output.png_ppi)),
         };
 
         if ctx.common.dry_run {
-            log::info!(
-                "dry-run: would compile {} to {}",
-                args.content.display(),
-                options.output.display()
-            );
+            for target in &targets {
+                log::info!(
+                    "dry-run: would compile {} to {}",
+                    args.content.display(),
+                    target.output.display()
+                );
+            }
             return Ok(());
         }
 
-        let result = compiler.compile(&content, &options)?;
+        let results = compiler.compile_all(&content, &options, &targets)?;
+        print_diagnostics(ctx, &results);
 
         if ctx.common.json {
-            let json = serde_json::to_string_pretty(&result)?;
+            let json = match results.as_slice() {
+                [result] => serde_json::to_string_pretty(result)?,
+                results => serde_json::to_string_pretty(results)?,
+            };
             println!("{}", json);
         } else {
-            match result.output {
-                Some(ref path) => println!("Compiled to {}", path.display()),
-                None => {
-                    if let Some(ref pages) = result.pages {
-                        println!("Compiled {} pages", pages.len());
-                    }
-                }
+            for result in &results {
+                let cached = if result.cached { " (cached)" } else { "" };
+                println!("Compiled to {}{}", describe_result(result), cached);
             }
         }
 
@@ -410,6 +504,237 @@ pub fn handle_compile(ctx: &mut Context, args: CompileArgs) -> Result<()> {
     }
 }
 
+/// Print Typst warnings to stderr (once, even when several formats were built)
+fn print_diagnostics(ctx: &Context, results: &[CompileResult]) {
+    if ctx.common.json || ctx.common.quiet {
+        return;
+    }
+    let mut seen = Vec::new();
+    for diagnostic in results.iter().flat_map(|r| &r.diagnostics) {
+        if !seen.contains(&diagnostic) {
+            eprintln!("{}", diagnostic);
+            seen.push(diagnostic);
+        }
+    }
+}
+
+/// Resolve one output target per requested format (`--format pdf,svg,png`).
+///
+/// With several formats, each path gets the format's extension and paged
+/// formats get a `{p}` page placeholder.
+fn output_targets(
+    ctx: &Context,
+    content: &ContentFile,
+    output: Option<&Path>,
+    formats: Option<&str>,
+) -> Result<Vec<OutputTarget>> {
+    let formats = match formats {
+        Some(spec) => OutputFormat::parse_list(spec)?,
+        None => Vec::new(),
+    };
+
+    if formats.len() <= 1 {
+        let format = formats.first().copied();
+        let output = resolve_output_path(content, output, &ctx.config.output, format)?;
+        let format = format
+            .or_else(|| OutputFormat::from_path(&output))
+            .unwrap_or_default();
+        return Ok(vec![OutputTarget { format, output }]);
+    }
+
+    formats
+        .into_iter()
+        .map(|format| {
+            let output = resolve_output_path(content, output, &ctx.config.output, Some(format))?;
+            Ok(OutputTarget {
+                format,
+                output: path_for_format(&output, format),
+            })
+        })
+        .collect()
+}
+
+/// Where a compile result was written, for human-readable output
+fn describe_result(result: &CompileResult) -> String {
+    match (&result.output, &result.pages) {
+        (Some(path), _) => path.display().to_string(),
+        (None, Some(pages)) => match pages.as_slice() {
+            [page] => page.file.display().to_string(),
+            pages => format!("{} {} pages", pages.len(), result.format),
+        },
+        (None, None) => result.format.clone(),
+    }
+}
+
+/// Handle merge command
+pub fn handle_merge(ctx: &Context, args: MergeArgs) -> Result<()> {
+    let content = ContentFile::load(&args.content)?;
+    let rows = load_rows(&args.data)?;
+    if rows.is_empty() {
+        return Err(Error::Merge(format!(
+            "{} contains no rows",
+            args.data.display()
+        )));
+    }
+
+    let mut compiler = TypstCompiler::from_config(&ctx.config)?;
+    if !args.no_cache {
+        compiler = compiler.with_build_cache(BuildCache::new(&ctx.paths.cache_dir));
+    }
+    let (brand_data, brand_font_paths) = load_brand_for_compile(ctx, args.brand.as_deref())?;
+    let format = match args.format.as_deref() {
+        Some(f) => Some(
+            OutputFormat::from_str(f)
+                .ok_or_else(|| Error::Config(format!("unknown output format '{}'", f)))?,
+        ),
+        None => None,
+    };
+
+    let ext = output_extension(format, &ctx.config.output);
+
+    let manifest_path = args.manifest.clone().unwrap_or_else(|| {
+        let parent = Path::new(&args.output)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty() && !p.to_string_lossy().contains('{'))
+            .unwrap_or(Path::new("."));
+        parent.join("merge-manifest.json")
+    });
+
+    // Resolve every document up front so name collisions are caught early
+    let mut jobs = Vec::with_capacity(rows.len());
+    let mut documents = Vec::with_capacity(rows.len());
+    let mut seen = BTreeSet::new();
+    for (i, row) in rows.iter().enumerate() {
+        let index = i + 1;
+        let prepared = merge_row(&content, row).and_then(|doc| {
+            let output = expand_output(&args.output, &doc, ext, &[("index", index.to_string())])?;
+            if !seen.insert(output.clone()) {
+                return Err(Error::Merge(format!(
+                    "output {} is used by more than one row",
+                    output.display()
+                )));
+            }
+            Ok((doc, output))
+        });
+        match prepared {
+            Ok((doc, output)) => jobs.push((index, doc, output)),
+            Err(e) => documents.push(MergeEntry {
+                row: index,
+                output: None,
+                status: "error".to_string(),
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+
+    if ctx.common.dry_run {
+        for (index, _, output) in &jobs {
+            log::info!(
+                "dry-run: would compile row {} to {}",
+                index,
+                output.display()
+            );
+        }
+        log::info!("dry-run: would write manifest {}", manifest_path.display());
+        return Ok(());
+    }
+
+    let compile_one = |doc: &ContentFile, output: &Path| -> Result<()> {
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let options = CompileOptions {
+            output: output.to_path_buf(),
+            format,
+            brand_data: brand_data.clone(),
+            brand_font_paths: brand_font_paths.clone(),
+            lang: None,
+            with_positions: false,
+            experimental_html: args.experimental_html,
+            check_only: false,
+            ppi: Some(ctx.config.output.png_ppi),
+        };
+        compiler.compile(doc, &options).map(|_| ())
+    };
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(jobs.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..args.jobs.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| {
+                while let Some((index, doc, output)) =
+                    jobs.get(next.fetch_add(1, Ordering::Relaxed))
+                {
+                    let entry = match compile_one(doc, output) {
+                        Ok(()) => {
+                            log::debug!("row {}: compiled to {}", index, output.display());
+                            MergeEntry {
+                                row: *index,
+                                output: Some(output.clone()),
+                                status: "ok".to_string(),
+                                error: None,
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("row {}: {}", index, e);
+                            MergeEntry {
+                                row: *index,
+                                output: Some(output.clone()),
+                                status: "error".to_string(),
+                                error: Some(e.to_string()),
+                            }
+                        }
+                    };
+                    results
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push(entry);
+                }
+            });
+        }
+    });
+    documents.extend(
+        results
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+
+    let manifest = MergeManifest::new(args.content.clone(), args.data.clone(), documents);
+    if let Some(parent) = manifest_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+
+    if manifest.failed > 0 {
+        if !ctx.common.json {
+            for doc in manifest.documents.iter().filter(|d| d.status != "ok") {
+                eprintln!(
+                    "row {}: {}",
+                    doc.row,
+                    doc.error.as_deref().unwrap_or("failed")
+                );
+            }
+        }
+        return Err(Error::Merge(format!(
+            "{} of {} documents failed (see {})",
+            manifest.failed,
+            manifest.total,
+            manifest_path.display()
+        ))
+        .with_details(serde_json::to_value(&manifest)?));
+    }
+
+    ctx.output(
+        &manifest,
+        &format!(
+            "Merged {} of {} documents (manifest: {})",
+            manifest.succeeded,
+            manifest.total,
+            manifest_path.display()
+        ),
+    )
+}
+
 /// Load brand data for compilation
 fn load_brand_for_compile(
     ctx: &Context,
@@ -430,24 +755,19 @@ fn load_brand_for_compile(
     // Extract font paths from brand
     let mut font_paths = Vec::new();
 
-    // Add brand root directory for relative font paths
-    font_paths.push(brand.source.root_dir.clone());
-
-    // Add fonts directory if it exists
-    let fonts_dir = brand.source.root_dir.join("fonts");
-    if fonts_dir.exists() {
-        font_paths.push(fonts_dir);
+    // Add the root and fonts directory of the brand and each parent, most
+    // specific first
+    for layer in brand.layers.iter().rev() {
+        let fonts_dir = layer.root_dir.join("fonts");
+        for dir in [layer.root_dir.clone(), fonts_dir] {
+            if dir.exists() && !font_paths.contains(&dir) {
+                font_paths.push(dir);
+            }
+        }
     }
 
     // Add specific font file directories
-    for font_face in [
-        &brand.typography.body,
-        &brand.typography.heading,
-        &brand.typography.mono,
-    ]
-    .into_iter()
-    .flatten()
-    {
+    for (_, font_face) in brand.typography.faces() {
         for file in &font_face.files {
             if let Some(parent) = file.parent() {
                 if parent.exists() && !font_paths.contains(&parent.to_path_buf()) {
@@ -457,41 +777,8 @@ fn load_brand_for_compile(
         }
     }
 
-    // Build brand data JSON for injection
-    let brand_data = serde_json::json!({
-        "id": brand.id,
-        "name": brand.name_for(None),
-        "default_language": brand.default_language,
-        "languages": brand.languages,
-        "colors": {
-            "primary": brand.colors.primary,
-            "secondary": brand.colors.secondary,
-            "accent": brand.colors.accent,
-            "background": brand.colors.background,
-            "text": brand.colors.text,
-            "palette": brand.colors.palette
-        },
-        "logos": {
-            "primary": brand.logos.primary.as_ref().map(|p| p.resolved.to_string_lossy()),
-            "secondary": brand.logos.secondary.as_ref().map(|p| p.resolved.to_string_lossy()),
-            "monochrome": brand.logos.monochrome.as_ref().map(|p| p.resolved.to_string_lossy()),
-            "favicon": brand.logos.favicon.as_ref().map(|p| p.resolved.to_string_lossy())
-        },
-        "logo": brand.logos.primary.as_ref().map(|p| p.resolved.to_string_lossy()),
-        "fonts": {
-            "body": brand.typography.body.as_ref().map(|f| &f.family),
-            "heading": brand.typography.heading.as_ref().map(|f| &f.family),
-            "mono": brand.typography.mono.as_ref().map(|f| &f.family)
-        },
-        "contact": brand.contact.as_ref().map(|c| serde_json::json!({
-            "company": c.company.as_ref().and_then(|t| t.resolve(None, brand.default_language.as_deref())),
-            "address": c.address.as_ref().and_then(|t| t.resolve(None, brand.default_language.as_deref())),
-            "phone": c.phone,
-            "email": c.email,
-            "website": c.website
-        })),
-        "root": brand.source.root_dir.to_string_lossy()
-    });
+    // Localized text is resolved with the document language in `prepare_data`
+    let brand_data = brand.to_data()?;
 
     Ok((Some(brand_data), font_paths))
 }
@@ -504,9 +791,38 @@ pub fn handle_get(ctx: &mut Context, args: GetArgs) -> Result<()> {
     // Update cache
     ctx.cache.update(&content)?;
 
+    // Computed fields are evaluated on the same data the template receives
+    if let Some(name) = args.path_or_title.strip_prefix("computed.") {
+        let (brand_data, _) = load_brand_for_compile(ctx, args.brand.as_deref())?;
+        let data = resolve_data(&content, brand_data.as_ref(), args.lang.as_deref())?;
+        let value = data
+            .get("computed")
+            .and_then(|computed| computed.get(name))
+            .ok_or_else(|| Error::PathNotFound {
+                path: args.path_or_title.clone(),
+            })?;
+        let text = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        return ctx.output(
+            &serde_json::json!({
+                "id": args.path_or_title,
+                "path": args.path_or_title,
+                "type": "computed",
+                "content": value
+            }),
+            &text,
+        );
+    }
+
     // Resolve path or title
     let path = content.resolve_path(&args.path_or_title)?;
-    let value = content.get_content(&path)?;
+    let data = content.block_data(&path)?;
+    let value = match data {
+        Some(ref data) => serde_json::to_string_pretty(data)?,
+        None => content.get_content(&path)?,
+    };
 
     if ctx.common.json {
         let info = content.get_block_info(&path);
@@ -516,7 +832,8 @@ pub fn handle_get(ctx: &mut Context, args: GetArgs) -> Result<()> {
             "title": info.and_then(|i| i.title.clone()),
             "format": info.and_then(|i| i.format.clone()),
             "type": info.map(|i| i.kind.as_str()),
-            "content": value
+            "block_type": info.and_then(|i| i.block_type.clone()),
+            "content": data.unwrap_or(serde_json::Value::String(value))
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
@@ -644,8 +961,16 @@ fn set_value_at_path(doc: &mut toml_edit::DocumentMut, path: &str, value: &str)
             // Last part - set the value
             if let Some(table) = current.as_table_mut() {
                 // Check if this is a block with content field
-                if let Some(block) = table.get_mut(*part) {
+                if let Some(block) = table.get_mut(part) {
                     if let Some(block_table) = block.as_table_mut() {
+                        if let Some(name) = block_table.get("type").and_then(|t| t.as_str()) {
+                            let block_type = BlockType::from_str(name).ok_or_else(|| {
+                                Error::Content(format!("{}: unknown block type '{}'", path, name))
+                            })?;
+                            if block_type != BlockType::Text {
+                                return set_block_data(block_table, block_type, path, value);
+                            }
+                        }
                         if block_table.contains_key("content") {
                             block_table["content"] = toml_edit::value(value);
                             return Ok(());
@@ -662,7 +987,7 @@ fn set_value_at_path(doc: &mut toml_edit::DocumentMut, path: &str, value: &str)
         } else {
             // Navigate deeper
             if let Some(table) = current.as_table_mut() {
-                if !table.contains_key(*part) {
+                if !table.contains_key(part) {
                     table[*part] = toml_edit::Item::Table(toml_edit::Table::new());
                 }
                 current = &mut table[*part];
@@ -677,6 +1002,28 @@ fn set_value_at_path(doc: &mut toml_edit::DocumentMut, path: &str, value: &str)
     Ok(())
 }
 
+/// Replace the data of a structured block with `value`
+///
+/// `value` is JSON; anything else is taken as a string (an image path, or
+/// list items one per line).
+fn set_block_data(
+    table: &mut toml_edit::Table,
+    block_type: BlockType,
+    path: &str,
+    value: &str,
+) -> Result<()> {
+    let json = serde_json::from_str(value)
+        .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+    let data = block_type.data_from_json(json).map_err(|e| match e {
+        Error::Content(msg) => Error::Content(format!("{}: {}", path, msg)),
+        e => e,
+    })?;
+    let data =
+        toml::Table::try_from(data).map_err(|e| Error::Content(format!("{}: {}", path, e)))?;
+    write_block_data(table, &data);
+    Ok(())
+}
+
 /// Handle blocks command
 pub fn handle_blocks(ctx: &mut Context, args: BlocksArgs) -> Result<()> {
     let file_path = resolve_file(&ctx.cache, args.file, args.from.as_deref())?;
@@ -693,8 +1040,10 @@ pub fn handle_blocks(ctx: &mut Context, args: BlocksArgs) -> Result<()> {
     } else {
         for block in blocks {
             let title = block.title.as_deref().unwrap_or("-");
-            let kind = block.kind.as_str();
-            println!("{} ({}) - {}", block.path, kind, title);
+            match block.block_type.as_deref() {
+                Some(block_type) => println!("{} (block: {}) - {}", block.path, block_type, title),
+                None => println!("{} ({}) - {}", block.path, block.kind.as_str(), title),
+            }
         }
     }
 
@@ -703,106 +1052,302 @@ pub fn handle_blocks(ctx: &mut Context, args: BlocksArgs) -> Result<()> {
 
 /// Handle validate command
 pub fn handle_validate(ctx: &Context, args: ValidateArgs) -> Result<()> {
-    let content = ContentFile::load(&args.content)?;
+    let source = fs::read_to_string(&args.content).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            Error::FileNotFound {
+                path: args.content.clone(),
+            }
+        } else {
+            Error::Io(e)
+        }
+    })?;
+    let content = ContentFile::parse(args.content.clone(), &source)?;
 
-    // Basic validation - check required fields
     let mut errors = Vec::new();
 
     // Check meta section
     if content.meta.template.is_empty() {
-        errors.push("meta.template is required".to_string());
+        errors.push(SchemaViolation::at(
+            &source,
+            &["meta".to_string(), "template".to_string()],
+            "meta.template is required",
+        ));
     }
 
-    // Check blocks have valid format
+    // Check blocks have a known type and a registered format
+    let formats = ConverterRegistry::builtin();
     if let Some(blocks) = content.as_toml().get("blocks").and_then(|v| v.as_table()) {
         for (name, block) in blocks {
+            if let Some(block_type) = block.get("type").and_then(|v| v.as_str()) {
+                if BlockType::from_str(block_type).is_none() {
+                    let types: Vec<&str> = BlockType::all().iter().map(|t| t.as_str()).collect();
+                    errors.push(SchemaViolation::at(
+                        &source,
+                        &["blocks".to_string(), name.clone(), "type".to_string()],
+                        format!(
+                            "invalid value '{}' (expected one of {})",
+                            block_type,
+                            types.join(", ")
+                        ),
+                    ));
+                }
+            }
+            if let Some(width) = block.get("width").and_then(|v| v.as_str()) {
+                if !is_image_width(width) {
+                    errors.push(SchemaViolation::at(
+                        &source,
+                        &["blocks".to_string(), name.clone(), "width".to_string()],
+                        format!(
+                            "invalid width '{}' (expected a length like \"8cm\" or \"50%\")",
+                            width
+                        ),
+                    ));
+                }
+            }
             if let Some(format) = block.get("format").and_then(|v| v.as_str()) {
-                if !["markdown", "typst", "plain"].contains(&format) {
-                    errors.push(format!(
-                        "blocks.{}.format: invalid value '{}'",
-                        name, format
+                if formats.get(format).is_none() {
+                    errors.push(SchemaViolation::at(
+                        &source,
+                        &["blocks".to_string(), name.clone(), "format".to_string()],
+                        format!(
+                            "invalid value '{}' (expected one of {})",
+                            format,
+                            formats.names().join(", ")
+                        ),
                     ));
                 }
             }
         }
     }
 
+    // Check computed fields evaluate on the same data the template receives
+    // A brand that fails to load is reported like any other problem
+    let brand_data = match load_brand_for_compile(ctx, args.brand.as_deref()) {
+        Ok((brand_data, _)) => brand_data,
+        Err(e) => {
+            errors.push(SchemaViolation::at(
+                &source,
+                &["brand".to_string()],
+                format!("brand could not be loaded: {}", e),
+            ));
+            None
+        }
+    };
+    if let Err(e) = resolve_data(&content, brand_data.as_ref(), args.lang.as_deref()) {
+        let (fallback, message) = match e {
+            Error::Validation(message) => ("computed", message),
+            Error::Content(message) => ("", message),
+            other => ("", other.to_string()),
+        };
+        let (path, message) = match message.split_once(": ") {
+            Some((path, rest)) if path.starts_with("computed.") || path.starts_with("blocks.") => {
+                (path, rest)
+            }
+            _ => (fallback, message.as_str()),
+        };
+        let segments: Vec<String> = path
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        let violation = SchemaViolation::at(&source, &segments, message);
+        // Unknown formats already failed the block check above
+        let reported = |e: &SchemaViolation| {
+            e.path == violation.path || e.path.starts_with(&format!("{}.", violation.path))
+        };
+        if !errors.iter().any(reported) {
+            errors.push(violation);
+        }
+    }
+
+    // Validate against the JSON schema
+    let (schema, schema_source) = resolve_validation_schema(ctx, &args, &content)?;
+    if let Some(ref schema) = schema {
+        let data = toml_to_json(content.as_toml())?;
+        let basic_paths: Vec<String> = errors.iter().map(|e| e.path.clone()).collect();
+        for violation in validate_against_schema(&source, &data, schema)? {
+            // Skip schema errors already covered by the basic checks above
+            if !basic_paths.contains(&violation.path) {
+                errors.push(violation);
+            }
+        }
+    }
+
     if errors.is_empty() {
         ctx.output(
             &serde_json::json!({
                 "status": "ok",
-                "file": args.content
+                "file": args.content,
+                "schema": schema_source
             }),
             &format!("{}: valid", args.content.display()),
         )
     } else {
-        if ctx.common.json {
-            let output = serde_json::json!({
-                "status": "error",
-                "kind": "validation_error",
-                "errors": errors
-            });
-            println!("{}", serde_json::to_string_pretty(&output)?);
-        } else {
+        if !ctx.common.json {
             eprintln!("{}: validation failed", args.content.display());
             for error in &errors {
-                eprintln!("  - {}", error);
+                match error.line {
+                    Some(line) => {
+                        eprintln!("  - {} (line {}): {}", error.path, line, error.message)
+                    }
+                    None => eprintln!("  - {}: {}", error.path, error.message),
+                }
             }
         }
-        Err(Error::Validation(format!("{} errors", errors.len())))
+        let report = serde_json::json!({
+            "file": args.content,
+            "schema": schema_source,
+            "errors": errors
+        });
+        Err(Error::Validation(format!("{} errors", errors.len())).with_details(report))
+    }
+}
+
+/// Resolve the schema used by `validate`
+///
+/// Order: `--schema`, the content file's `"$schema"` key, the template's schema
+/// in `schemas_dir`, then a schema generated from the template on the fly.
+fn resolve_validation_schema(
+    ctx: &Context,
+    args: &ValidateArgs,
+    content: &ContentFile,
+) -> Result<(Option<serde_json::Value>, serde_json::Value)> {
+    if let Some(ref path) = args.schema {
+        let schema = load_schema(path)?;
+        return Ok((
+            Some(schema),
+            serde_json::json!({ "source": "argument", "path": path }),
+        ));
+    }
+
+    if let Some(reference) = content.get("$schema").and_then(|v| v.as_str()) {
+        if reference.starts_with("http://") || reference.starts_with("https://") {
+            log::warn!("ignoring remote $schema '{}'", reference);
+        } else {
+            let content_dir = content.path.parent().unwrap_or(Path::new("."));
+            let path = content_dir.join(reference);
+            if path.exists() {
+                let schema = load_schema(&path)?;
+                return Ok((
+                    Some(schema),
+                    serde_json::json!({ "source": "content", "path": path }),
+                ));
+            }
+            log::warn!("$schema '{}' not found, falling back", path.display());
+        }
+    }
+
+    let template_id = content.meta.template_id.clone().or_else(|| {
+        Path::new(&content.meta.template)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+    });
+    if let Some(id) = template_id {
+        let path = ctx.paths.schemas_dir.join(format!("{}.schema.json", id));
+        if path.exists() {
+            let schema = load_schema(&path)?;
+            return Ok((
+                Some(schema),
+                serde_json::json!({ "source": "schemas_dir", "path": path }),
+            ));
+        }
+    }
+
+    match TemplateInfo::parse(content.template_path()) {
+        Ok(template) => Ok((
+            Some(template.generate_schema()),
+            serde_json::json!({ "source": "generated", "path": template.path }),
+        )),
+        Err(e) => {
+            log::warn!("no schema available, only basic checks performed: {}", e);
+            Ok((None, serde_json::Value::Null))
+        }
     }
 }
 
 /// Handle watch command
 pub fn handle_watch(ctx: &mut Context, args: WatchArgs) -> Result<()> {
-    use notify::RecursiveMode;
     use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
     use std::sync::mpsc;
     use std::time::Duration;
 
     let debounce_ms = args.debounce.unwrap_or(ctx.config.output.watch_debounce_ms);
 
-    let output = args.output.unwrap_or_else(|| {
-        let stem = args
-            .content
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output");
-        PathBuf::from(format!("{}.pdf", stem))
-    });
+    // The preview server renders SVG pages into a temp dir unless told otherwise
+    let preview_dir = if args.serve.is_some() && args.output.is_none() {
+        Some(tempfile::tempdir().map_err(Error::Io)?)
+    } else {
+        None
+    };
+
+    let content = ContentFile::load(&args.content)?;
+
+    // Output names are resolved once; later content edits do not rename them
+    let targets = match preview_dir {
+        Some(ref dir) if args.format.is_none() => vec![OutputTarget {
+            format: OutputFormat::Svg,
+            output: dir.path().join("page-{p}.svg"),
+        }],
+        _ => output_targets(
+            ctx,
+            &content,
+            args.output.as_deref(),
+            args.format.as_deref(),
+        )?,
+    };
+    let output = targets[0].output.clone();
 
-    let format = args.format.as_deref().and_then(OutputFormat::from_str);
+    let server = args
+        .serve
+        .as_deref()
+        .map(PreviewServer::start)
+        .transpose()?;
 
     // Load brand if specified
     let (brand_data, brand_font_paths) = load_brand_for_compile(ctx, args.brand.as_deref())?;
+    let mut brand_roots = brand_roots(ctx, args.brand.as_deref());
 
-    let options = CompileOptions {
+    let mut options = CompileOptions {
         output: output.clone(),
-        format,
+        format: Some(targets[0].format),
         brand_data,
         brand_font_paths,
+        lang: args.lang.clone(),
         with_positions: false,
         experimental_html: args.experimental_html,
         check_only: false,
+        ppi: Some(args.ppi.unwrap_or(ctx.config.output.png_ppi)),
     };
 
     // Initial compile
-    let content = ContentFile::load(&args.content)?;
     let compiler = TypstCompiler::from_config(&ctx.config)?;
 
-    match compiler.compile(&content, &options) {
-        Ok(_) => {
-            println!("Compiled to {}", output.display());
-
-            // Open in default viewer if requested
-            if args.open {
-                match open_file(&output) {
-                    Ok(_) => println!("Opened {} in default viewer", output.display()),
-                    Err(e) => eprintln!("Warning: could not open file: {}", e),
-                }
+    match compiler.compile_all(&content, &options, &targets) {
+        Ok(results) => {
+            print_diagnostics(ctx, &results);
+            for result in &results {
+                println!("Compiled to {}", describe_result(result));
             }
+            publish_preview(server.as_ref(), &results);
         }
-        Err(e) => eprintln!("Compilation error: {}", e),
+        Err(e) => report_watch_error(server.as_ref(), e),
+    }
+
+    // Open in default viewer (or the browser when serving) if requested
+    if args.open {
+        let target = match server {
+            Some(ref server) => PathBuf::from(server.url()),
+            None => output.clone(),
+        };
+        match open_file(&target) {
+            Ok(_) => println!("Opened {} in default viewer", target.display()),
+            Err(e) => eprintln!("Warning: could not open file: {}", e),
+        }
+    }
+
+    if let Some(ref server) = server {
+        println!("Serving live preview at {}", server.url());
     }
 
     // Set up file watcher
@@ -810,27 +1355,89 @@ pub fn handle_watch(ctx: &mut Context, args: WatchArgs) -> Result<()> {
     let mut debouncer = new_debouncer(Duration::from_millis(debounce_ms), tx)
         .map_err(|e| Error::Watch(format!("creating watcher: {}", e)))?;
 
-    debouncer
-        .watcher()
-        .watch(&args.content, RecursiveMode::NonRecursive)
-        .map_err(|e| Error::Watch(format!("watching file: {}", e)))?;
+    let mut watch_set = WatchSet::collect(&content, &brand_roots);
+    let mut watched = BTreeSet::new();
+    sync_watches(debouncer.watcher(), &watch_set, &mut watched)?;
 
-    println!("Watching {} for changes...", args.content.display());
+    println!(
+        "Watching {} and {} dependencies for changes...",
+        args.content.display(),
+        watch_set.files.len() + watch_set.dirs.len() - 1
+    );
+    for path in watch_set.files.iter().chain(&watch_set.dirs) {
+        log::debug!("watching {}", path.display());
+    }
 
     // Watch loop
     loop {
         match rx.recv() {
             Ok(Ok(events)) => {
-                for event in events {
-                    if matches!(event.kind, DebouncedEventKind::Any) {
-                        match ContentFile::load(&args.content) {
-                            Ok(content) => match compiler.compile(&content, &options) {
-                                Ok(_) => println!("Recompiled to {}", output.display()),
-                                Err(e) => eprintln!("Compilation error: {}", e),
-                            },
-                            Err(e) => eprintln!("Error loading content: {}", e),
+                let changed: BTreeSet<PathBuf> = events
+                    .into_iter()
+                    .filter(|event| matches!(event.kind, DebouncedEventKind::Any))
+                    .map(|event| event.path)
+                    .filter(|path| watch_set.contains(path))
+                    .collect();
+                let Some(trigger) = changed.iter().next() else {
+                    continue;
+                };
+
+                if changed.len() > 1 {
+                    println!(
+                        "Changed: {} (and {} more)",
+                        trigger.display(),
+                        changed.len() - 1
+                    );
+                } else {
+                    println!("Changed: {}", trigger.display());
+                }
+
+                // Brand files changed: reload brand data and fonts
+                if changed
+                    .iter()
+                    .any(|path| brand_roots.iter().any(|root| is_within(path, root)))
+                {
+                    match load_brand_for_compile(ctx, args.brand.as_deref()) {
+                        Ok((data, fonts)) => {
+                            options.brand_data = data;
+                            options.brand_font_paths = fonts;
                         }
+                        Err(e) => eprintln!("Error loading brand: {}", e),
+                    }
+                    // `extends` may point somewhere else now
+                    brand_roots = self::brand_roots(ctx, args.brand.as_deref());
+                }
+
+                let content = match ContentFile::load(&args.content) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        eprintln!("Error loading content: {}", e);
+                        if let Some(ref server) = server {
+                            server.publish_error(&format!("Error loading content: {}", e));
+                        }
+                        continue;
+                    }
+                };
+
+                match compiler.compile_all(&content, &options, &targets) {
+                    Ok(results) => {
+                        print_diagnostics(ctx, &results);
+                        for result in &results {
+                            println!("Recompiled to {}", describe_result(result));
+                        }
+                        publish_preview(server.as_ref(), &results);
+                    }
+                    Err(e) => report_watch_error(server.as_ref(), e),
+                }
+
+                // Dependencies may have changed (new imports, new asset paths)
+                let updated = WatchSet::collect(&content, &brand_roots);
+                if updated != watch_set {
+                    for path in updated.files.difference(&watch_set.files) {
+                        println!("Now watching {}", path.display());
                     }
+                    sync_watches(debouncer.watcher(), &updated, &mut watched)?;
+                    watch_set = updated;
                 }
             }
             Ok(Err(e)) => {
@@ -843,6 +1450,81 @@ pub fn handle_watch(ctx: &mut Context, args: WatchArgs) -> Result<()> {
     }
 }
 
+/// Show the best artifact for a browser preview (paged images first)
+fn publish_preview(server: Option<&PreviewServer>, results: &[CompileResult]) {
+    let preview = results
+        .iter()
+        .find(|r| r.pages.is_some())
+        .or_else(|| results.first());
+    if let (Some(server), Some(result)) = (server, preview) {
+        server.publish(result);
+    }
+}
+
+/// Print a watch-mode compile error and show it in the preview
+fn report_watch_error(server: Option<&PreviewServer>, err: Error) {
+    eprintln!("Compilation error: {}", err);
+    if let Some(server) = server {
+        let error = ErrorReport::from(err);
+        let message = match error.details.as_ref().and_then(|d| d.as_str()) {
+            Some(details) => format!("{}\n\n{}", error.message, details),
+            None => error.message,
+        };
+        server.publish_error(&message);
+    }
+}
+
+/// Root directories of the brand used for compiling and its parents
+fn brand_roots(ctx: &Context, brand_id: Option<&str>) -> Vec<PathBuf> {
+    let Some(brand_id) = brand_id.or(ctx.config.brand.default.as_deref()) else {
+        return Vec::new();
+    };
+    let registry = BrandRegistry::new(vec![ctx.paths.brands_dir.clone()]);
+    let Ok(brand) = registry.load(brand_id) else {
+        return Vec::new();
+    };
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for layer in brand.layers {
+        if !roots.contains(&layer.root_dir) {
+            roots.push(layer.root_dir);
+        }
+    }
+    roots
+}
+
+/// Register new watch targets and drop ones that are no longer needed
+fn sync_watches(
+    watcher: &mut dyn notify::Watcher,
+    watch_set: &WatchSet,
+    watched: &mut BTreeSet<(PathBuf, bool)>,
+) -> Result<()> {
+    use notify::RecursiveMode;
+
+    let targets = watch_set.watch_targets();
+
+    for (dir, recursive) in watched.difference(&targets) {
+        log::debug!("unwatching {} (recursive: {})", dir.display(), recursive);
+        let _ = watcher.unwatch(dir);
+    }
+
+    for (dir, recursive) in targets.difference(watched) {
+        let mode = if *recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        if let Err(e) = watcher.watch(dir, mode) {
+            if dir.exists() {
+                return Err(Error::Watch(format!("watching {}: {}", dir.display(), e)));
+            }
+            log::warn!("not watching missing directory {}", dir.display());
+        }
+    }
+
+    *watched = targets;
+    Ok(())
+}
+
 /// Handle templates command
 pub fn handle_templates(ctx: &Context, args: TemplatesArgs) -> Result<()> {
     let search_paths = if let Some(path) = args.path {
@@ -936,6 +1618,40 @@ pub fn handle_config(ctx: &Context, command: ConfigCommand) -> Result<()> {
     }
 }
 
+/// Handle cache command
+pub fn handle_cache(ctx: &Context, command: CacheCommand) -> Result<()> {
+    match command {
+        CacheCommand::Prune(args) => {
+            let cache = BuildCache::new(&ctx.paths.cache_dir);
+            let older_than = args
+                .older_than
+                .map(|days| std::time::Duration::from_secs(days * 24 * 60 * 60));
+
+            if ctx.common.dry_run {
+                log::info!("dry-run: would prune {}", cache.dir().display());
+                return Ok(());
+            }
+
+            let report = cache.prune(older_than)?;
+            ctx.output(
+                &serde_json::json!({
+                    "status": "ok",
+                    "dir": cache.dir(),
+                    "removed": report.removed,
+                    "freed_bytes": report.freed_bytes,
+                    "remaining": report.remaining
+                }),
+                &format!(
+                    "Removed {} cached builds ({} KiB), {} remaining",
+                    report.removed,
+                    report.freed_bytes / 1024,
+                    report.remaining
+                ),
+            )
+        }
+    }
+}
+
 /// Handle add command
 pub fn handle_add(ctx: &Context, command: AddCommand) -> Result<()> {
     match command {
@@ -1218,7 +1934,7 @@ fn open_file(path: &std::path::Path) -> Result<()> {
         std::process::Command::new("open")
             .arg(path)
             .spawn()
-            .map_err(|e| Error::Io(e))?;
+            .map_err(Error::Io)?;
     }
 
     #[cfg(target_os = "linux")]
@@ -1226,7 +1942,7 @@ fn open_file(path: &std::path::Path) -> Result<()> {
         std::process::Command::new("xdg-open")
             .arg(path)
             .spawn()
-            .map_err(|e| Error::Io(e))?;
+            .map_err(Error::Io)?;
     }
 
     #[cfg(target_os = "windows")]
@@ -1235,7 +1951,7 @@ fn open_file(path: &std::path::Path) -> Result<()> {
             .args(["/c", "start", ""])
             .arg(path)
             .spawn()
-            .map_err(|e| Error::Io(e))?;
+            .map_err(Error::Io)?;
     }
 
     Ok(())
@@ -1336,12 +2052,30 @@ fn handle_brands_show(ctx: &Context, args: BrandsShowArgs) -> Result<()> {
                 "email": c.email,
                 "website": c.website
             })),
-            "path": brand.source.root_dir
+            "path": brand.source.root_dir,
+            "variant": brand.variant,
+            "variants": brand.variants,
+            "layers": brand.layers.iter().map(|l| &l.name).collect::<Vec<_>>(),
+            "origins": brand.origins
         });
         let json = serde_json::to_string_pretty(&output)?;
         println!("{}", json);
     } else {
+        // Inherited brands and variants show the layer behind each value
+        let layered = brand.layers.len() > 1;
+        let from = |key: &str| match brand.origin(key) {
+            Some(layer) if layered => format!("  ({})", layer),
+            _ => String::new(),
+        };
+
         println!("Brand: {}", brand.id);
+        if layered {
+            let names: Vec<&str> = brand.layers.iter().map(|l| l.name.as_str()).collect();
+            println!("Layers: {}", names.join(" -> "));
+        }
+        if !brand.variants.is_empty() {
+            println!("Variants: {}", brand.variants.join(", "));
+        }
         println!("Name: {}", name);
         if let Some(desc) = description {
             println!("Description: {}", desc);
@@ -1350,49 +2084,65 @@ fn handle_brands_show(ctx: &Context, args: BrandsShowArgs) -> Result<()> {
 
         println!("\nColors:");
         if let Some(ref c) = brand.colors.primary {
-            println!("  primary: {}", c);
+            println!("  primary: {}{}", c, from("colors.primary"));
         }
         if let Some(ref c) = brand.colors.secondary {
-            println!("  secondary: {}", c);
+            println!("  secondary: {}{}", c, from("colors.secondary"));
         }
         if let Some(ref c) = brand.colors.accent {
-            println!("  accent: {}", c);
+            println!("  accent: {}{}", c, from("colors.accent"));
         }
 
         println!("\nLogos:");
         if let Some(ref logo) = brand.logos.primary {
-            println!("  primary: {}", logo.resolved.display());
+            println!(
+                "  primary: {}{}",
+                logo.resolved.display(),
+                from("logos.primary")
+            );
         }
         if let Some(ref logo) = brand.logos.secondary {
-            println!("  secondary: {}", logo.resolved.display());
+            println!(
+                "  secondary: {}{}",
+                logo.resolved.display(),
+                from("logos.secondary")
+            );
         }
         if let Some(ref logo) = brand.logos.monochrome {
-            println!("  monochrome: {}", logo.resolved.display());
+            println!(
+                "  monochrome: {}{}",
+                logo.resolved.display(),
+                from("logos.monochrome")
+            );
         }
 
         println!("\nTypography:");
         if let Some(ref font) = brand.typography.body {
-            println!("  body: {}", font.family);
+            println!("  body: {}{}", font.family, from("typography.body.family"));
         }
         if let Some(ref font) = brand.typography.heading {
-            println!("  heading: {}", font.family);
+            println!(
+                "  heading: {}{}",
+                font.family,
+                from("typography.heading.family")
+            );
         }
         if let Some(ref font) = brand.typography.mono {
-            println!("  mono: {}", font.family);
+            println!("  mono: {}{}", font.family, from("typography.mono.family"));
         }
 
         if let Some(ref contact) = brand.contact {
             println!("\nContact:");
             if let Some(ref company) = contact.company {
                 if let Some(c) = company.resolve(lang, brand.default_language.as_deref()) {
-                    println!("  company: {}", c);
+                    println!("  company: {}{}", c, from("contact.company"));
                 }
             }
             if let Some(ref email) = contact.email {
-                println!("  email: {}", email);
+                println!("  email: {}{}", email, from("contact.email"));
             }
             if let Some(ref website) = contact.website {
-                println!("  website: {}", website);
+                println!("  website: {}{}", website, from("contact.website"));
             }
         }
 
@@ -1508,19 +2258,16 @@ fn handle_brands_validate(ctx: &Context, args: BrandsValidateArgs) -> Result<()>
     let brand = match registry.load(&args.brand) {
         Ok(b) => b,
         Err(e) => {
-            if ctx.common.json {
-                let output = serde_json::json!({
-                    "status": "error",
-                    "valid": false,
-                    "brand": args.brand,
-                    "errors": [e.to_string()]
-                });
-                println!("{}", serde_json::to_string_pretty(&output)?);
-            } else {
+            if !ctx.common.json {
                 eprintln!("{}: validation failed", args.brand);
                 eprintln!("  - {}", e);
             }
-            return Err(e);
+            let report = serde_json::json!({
+                "valid": false,
+                "brand": args.brand,
+                "errors": [e.to_string()]
+            });
+            return Err(e.with_details(report));
         }
     };
 
@@ -1536,21 +2283,17 @@ fn handle_brands_validate(ctx: &Context, args: BrandsValidateArgs) -> Result<()>
         errors.push("name is required".to_string());
     }
 
-    // Validate colors (hex format)
-    let color_regex =
-        regex::Regex::new(r"^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$").unwrap();
-    for (name, color) in [
-        ("primary", &brand.colors.primary),
-        ("secondary", &brand.colors.secondary),
-        ("accent", &brand.colors.accent),
-        ("background", &brand.colors.background),
-        ("text", &brand.colors.text),
-    ] {
-        if let Some(c) = color {
-            if !color_regex.is_match(c) {
-                warnings.push(format!("colors.{}: '{}' is not a valid hex color", name, c));
-            }
-        }
+    // Validate color values and readability
+    errors.extend(brand.colors.problems());
+    let contrast = brand.colors.contrast_checks();
+    if brand.colors.background.is_none() && !contrast.is_empty() {
+        warnings.push("colors.background is not set; contrast checked against white".to_string());
+    }
+    for check in contrast.iter().filter(|c| !c.passes()) {
+        errors.push(format!(
+            "colors.{} on colors.{}: contrast {:.2}:1 is below the WCAG AA minimum of {}:1",
+            check.foreground, check.background, check.ratio, check.required
+        ));
     }
 
     // Check referenced files if --check-files
@@ -1595,43 +2338,51 @@ fn handle_brands_validate(ctx: &Context, args: BrandsValidateArgs) -> Result<()>
 
     // Build result
     let valid = errors.is_empty();
+    let report = serde_json::json!({
+        "valid": valid,
+        "brand": brand.id,
+        "path": brand.source.file,
+        "errors": errors,
+        "warnings": warnings,
+        "contrast": contrast.iter().map(|check| serde_json::json!({
+            "foreground": check.foreground,
+            "background": check.background,
+            "ratio": (check.ratio * 100.0).round() / 100.0,
+            "required": check.required,
+            "passes": check.passes()
+        })).collect::<Vec<_>>()
+    });
 
     if ctx.common.json {
-        let output = serde_json::json!({
-            "status": if valid { "ok" } else { "error" },
-            "valid": valid,
-            "brand": brand.id,
-            "path": brand.source.file,
-            "errors": errors,
-            "warnings": warnings
-        });
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    } else {
         if valid {
-            println!(
-                "{}: valid (id: {}, languages: {})",
-                brand.source.file.display(),
-                brand.id,
-                brand.languages.join(", ")
-            );
-            for warning in &warnings {
-                println!("  warning: {}", warning);
-            }
-        } else {
-            eprintln!("{}: validation failed", brand.source.file.display());
-            for error in &errors {
-                eprintln!("  - {}", error);
-            }
-            for warning in &warnings {
-                eprintln!("  warning: {}", warning);
-            }
+            let mut output = report.clone();
+            output["status"] = "ok".into();
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+    } else if valid {
+        println!(
+            "{}: valid (id: {}, languages: {})",
+            brand.source.file.display(),
+            brand.id,
+            brand.languages.join(", ")
+        );
+        for warning in &warnings {
+            println!("  warning: {}", warning);
+        }
+    } else {
+        eprintln!("{}: validation failed", brand.source.file.display());
+        for error in &errors {
+            eprintln!("  - {}", error);
+        }
+        for warning in &warnings {
+            eprintln!("  warning: {}", warning);
         }
     }
 
     if valid {
         Ok(())
     } else {
-        Err(Error::Validation(format!("{} errors", errors.len())))
+        Err(Error::Validation(format!("{} errors", errors.len())).with_details(report))
     }
 }
 
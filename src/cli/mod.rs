@@ -82,9 +82,12 @@ pub enum Command {
     /// Generate a self-contained example template + content pair
     Example(ExampleArgs),
 
-    /// Compile to PDF/SVG/HTML
+    /// Compile to PDF/SVG/PNG/HTML
     Compile(CompileArgs),
 
+    /// Generate one document per row of a CSV/JSON data file
+    Merge(MergeArgs),
+
     /// Get block value(s) by path or title
     Get(GetArgs),
 
@@ -124,6 +127,12 @@ pub enum Command {
         command: ConfigCommand,
     },
 
+    /// Manage the compile cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+
     /// Generate shell completions
     Completions {
         #[arg(value_enum)]
@@ -178,18 +187,27 @@ pub struct CompileArgs {
     /// Content file to compile
     pub content: PathBuf,
 
-    /// Output file path
+    /// Output file path; may use placeholders like {quote.number}, {date:%Y%m%d}, {ext}.
+    /// Braces that aren't a known placeholder are kept as-is; write {{ and }} to escape
     #[arg(short, long, value_name = "PATH")]
     pub output: Option<PathBuf>,
 
-    /// Output format (pdf, svg, html)
+    /// Output format(s), comma-separated (pdf, svg, png, html)
     #[arg(long, value_name = "FORMAT")]
     pub format: Option<String>,
 
-    /// Brand ID or path to use (overrides content file brand)
+    /// Resolution for PNG output in pixels per inch
+    #[arg(long, value_name = "PPI")]
+    pub ppi: Option<u32>,
+
+    /// Brand ID or path to use, optionally `id:variant` (overrides content file brand)
     #[arg(long, short = 'b', value_name = "BRAND")]
     pub brand: Option<String>,
 
+    /// Document language for localized content (overrides `meta.lang`)
+    #[arg(long, short = 'l', value_name = "LANG")]
+    pub lang: Option<String>,
+
     /// Include position information in output
     #[arg(long)]
     pub with_positions: bool,
@@ -201,6 +219,49 @@ pub struct CompileArgs {
     /// Validate template + content compatibility without generating output
     #[arg(long)]
     pub check: bool,
+
+    /// Always run Typst, ignoring the build cache
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+/// Arguments for the merge command
+#[derive(Debug, Clone, Args)]
+pub struct MergeArgs {
+    /// Content file used as the base for every document
+    pub content: PathBuf,
+
+    /// Rows to merge (.csv with dotted column names, or .json array of objects)
+    #[arg(long, value_name = "PATH")]
+    pub data: PathBuf,
+
+    /// Output path pattern, e.g. "out/{recipient.name}.pdf" ({index} = row number)
+    #[arg(short, long, value_name = "PATTERN")]
+    pub output: String,
+
+    /// Output format (pdf, svg, png, html)
+    #[arg(long, value_name = "FORMAT")]
+    pub format: Option<String>,
+
+    /// Brand ID or path to use, optionally `id:variant` (overrides content file brand)
+    #[arg(long, short = 'b', value_name = "BRAND")]
+    pub brand: Option<String>,
+
+    /// Number of documents to compile in parallel
+    #[arg(long, short = 'j', value_name = "N", default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Where to write the summary manifest (default: next to the outputs)
+    #[arg(long, value_name = "PATH")]
+    pub manifest: Option<PathBuf>,
+
+    /// Always run Typst, ignoring the build cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Enable experimental HTML output
+    #[arg(long)]
+    pub experimental_html: bool,
 }
 
 /// Arguments for the get command
@@ -216,6 +277,14 @@ pub struct GetArgs {
     /// Use selector instead of file path
     #[arg(long, value_name = "SELECTOR", conflicts_with = "file")]
     pub from: Option<String>,
+
+    /// Brand ID or path used for `computed.*` fields, optionally `id:variant`
+    #[arg(long, short = 'b', value_name = "BRAND")]
+    pub brand: Option<String>,
+
+    /// Document language used for `computed.*` fields (overrides `meta.lang`)
+    #[arg(long, short = 'l', value_name = "LANG")]
+    pub lang: Option<String>,
 }
 
 /// Arguments for the set command
@@ -266,6 +335,14 @@ pub struct ValidateArgs {
     /// JSON schema file (optional, uses template-specific schema if not provided)
     #[arg(long, value_name = "PATH")]
     pub schema: Option<PathBuf>,
+
+    /// Brand ID or path used for `computed.*` fields, optionally `id:variant`
+    #[arg(long, short = 'b', value_name = "BRAND")]
+    pub brand: Option<String>,
+
+    /// Document language used for `computed.*` fields (overrides `meta.lang`)
+    #[arg(long, short = 'l', value_name = "LANG")]
+    pub lang: Option<String>,
 }
 
 /// Arguments for the watch command
@@ -274,18 +351,27 @@ pub struct WatchArgs {
     /// Content file to watch
     pub content: PathBuf,
 
-    /// Output file path
+    /// Output file path; may use placeholders like {quote.number}, {date:%Y%m%d}, {ext}.
+    /// Braces that aren't a known placeholder are kept as-is; write {{ and }} to escape
     #[arg(short, long, value_name = "PATH")]
     pub output: Option<PathBuf>,
 
-    /// Output format (pdf, svg, html)
+    /// Output format(s), comma-separated (pdf, svg, png, html)
     #[arg(long, value_name = "FORMAT")]
     pub format: Option<String>,
 
-    /// Brand ID or path to use (overrides content file brand)
+    /// Resolution for PNG output in pixels per inch
+    #[arg(long, value_name = "PPI")]
+    pub ppi: Option<u32>,
+
+    /// Brand ID or path to use, optionally `id:variant` (overrides content file brand)
     #[arg(long, short = 'b', value_name = "BRAND")]
     pub brand: Option<String>,
 
+    /// Document language for localized content (overrides `meta.lang`)
+    #[arg(long, short = 'l', value_name = "LANG")]
+    pub lang: Option<String>,
+
     /// Enable experimental HTML output
     #[arg(long)]
     pub experimental_html: bool,
@@ -297,6 +383,10 @@ pub struct WatchArgs {
     /// Open output in default PDF viewer after initial compile
     #[arg(long)]
     pub open: bool,
+
+    /// Serve a live preview in the browser (default address: 127.0.0.1:3000)
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = crate::serve::DEFAULT_ADDR)]
+    pub serve: Option<String>,
 }
 
 /// Arguments for the templates command
@@ -328,6 +418,21 @@ pub enum ConfigCommand {
     Reset,
 }
 
+/// Cache subcommands
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Remove compiled outputs from the build cache
+    Prune(CachePruneArgs),
+}
+
+/// Arguments for cache prune
+#[derive(Debug, Clone, Args)]
+pub struct CachePruneArgs {
+    /// Only remove entries not used for this many days
+    #[arg(long, value_name = "DAYS")]
+    pub older_than: Option<u64>,
+}
+
 /// Brands subcommands
 #[derive(Debug, Subcommand)]
 pub enum BrandsCommand {
@@ -355,7 +460,7 @@ pub struct BrandsListArgs {
 /// Arguments for brands show command
 #[derive(Debug, Clone, Args)]
 pub struct BrandsShowArgs {
-    /// Brand ID or path to brand directory
+    /// Brand ID or path to brand directory, optionally `id:variant`
     pub brand: String,
 
     /// Language code for localized content
@@ -389,7 +494,7 @@ pub struct BrandsNewArgs {
 /// Arguments for brands validate command
 #[derive(Debug, Clone, Args)]
 pub struct BrandsValidateArgs {
-    /// Brand ID or path to brand directory/file
+    /// Brand ID or path to brand directory/file, optionally `id:variant`
     pub brand: String,
 
     /// Check that all referenced files exist
@@ -0,0 +1,198 @@
+//! Color parsing and contrast
+//!
+//! Brand colors may be written as hex (`#0f172a`, `#fff`, `#0f172acc`) or as
+//! `rgb(15, 23, 42)` / `rgba(15, 23, 42, 0.5)`. They are normalized to
+//! lowercase hex, which Typst's `rgb()` accepts. Contrast ratios follow
+//! WCAG 2.x.
+
+/// WCAG AA minimum contrast for body text
+pub const MIN_TEXT_CONTRAST: f64 = 4.5;
+
+/// WCAG AA minimum contrast for large text such as headings
+pub const MIN_LARGE_TEXT_CONTRAST: f64 = 3.0;
+
+/// An sRGB color with alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    /// Parse a hex or `rgb()`/`rgba()` color.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(hex) = value.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        let lower = value.to_ascii_lowercase();
+        let args = lower
+            .strip_prefix("rgba(")
+            .or_else(|| lower.strip_prefix("rgb("))?
+            .strip_suffix(')')?;
+        let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        let channel = |part: &str| -> Option<u8> {
+            match part.strip_suffix('%') {
+                Some(pct) => percent(pct).map(|p| (p * 255.0).round() as u8),
+                None => part.parse().ok(),
+            }
+        };
+        match parts.as_slice() {
+            [r, g, b] => Some(Self::rgb(channel(r)?, channel(g)?, channel(b)?)),
+            [r, g, b, a] => {
+                let alpha = match a.strip_suffix('%') {
+                    Some(pct) => percent(pct)?,
+                    None => a.parse::<f64>().ok().filter(|a| (0.0..=1.0).contains(a))?,
+                };
+                Some(Self {
+                    a: (alpha * 255.0).round() as u8,
+                    ..Self::rgb(channel(r)?, channel(g)?, channel(b)?)
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Lowercase `#rrggbb`, or `#rrggbbaa` when not opaque
+    pub fn to_hex(&self) -> String {
+        if self.a == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// This color composited over an opaque `background`
+    pub fn over(&self, background: Color) -> Color {
+        let alpha = f64::from(self.a) / 255.0;
+        let blend =
+            |fg: u8, bg: u8| (f64::from(fg) * alpha + f64::from(bg) * (1.0 - alpha)).round() as u8;
+        Color::rgb(
+            blend(self.r, background.r),
+            blend(self.g, background.g),
+            blend(self.b, background.b),
+        )
+    }
+
+    /// Mix `amount` (0.0 to 1.0) of `other` into this color
+    pub fn mix(&self, other: Color, amount: f64) -> Color {
+        let blend =
+            |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * amount).round() as u8;
+        Color {
+            r: blend(self.r, other.r),
+            g: blend(self.g, other.g),
+            b: blend(self.b, other.b),
+            a: self.a,
+        }
+    }
+
+    /// Relative luminance as defined by WCAG
+    pub fn luminance(&self) -> f64 {
+        let linear = |channel: u8| {
+            let c = f64::from(channel) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Contrast ratio (1 to 21) of this color drawn on `background`
+    pub fn contrast(&self, background: Color) -> f64 {
+        let background = background.over(Color::WHITE);
+        let foreground = self.over(background);
+        let (l1, l2) = (foreground.luminance(), background.luminance());
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        // #rgb and #rgba: each digit is doubled
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    Some(Color {
+        r: digits[0],
+        g: digits[1],
+        b: digits[2],
+        a: digits.get(3).copied().unwrap_or(255),
+    })
+}
+
+fn percent(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|p| (0.0..=100.0).contains(p))
+        .map(|p| p / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_normalize() {
+        assert_eq!(Color::parse("#FFF").unwrap().to_hex(), "#ffffff");
+        assert_eq!(Color::parse("#0F172A").unwrap().to_hex(), "#0f172a");
+        assert_eq!(Color::parse("#0f172a80").unwrap().to_hex(), "#0f172a80");
+        assert_eq!(Color::parse("rgb(15, 23, 42)").unwrap().to_hex(), "#0f172a");
+        assert_eq!(
+            Color::parse("rgb(100%, 0%, 0%)").unwrap().to_hex(),
+            "#ff0000"
+        );
+        assert_eq!(
+            Color::parse("rgba(0, 0, 0, 0.5)").unwrap().to_hex(),
+            "#00000080"
+        );
+        for invalid in ["blue", "#12", "#gggggg", "rgb(256, 0, 0)", "rgb(1, 2)", ""] {
+            assert_eq!(Color::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_contrast() {
+        let ratio = Color::BLACK.contrast(Color::WHITE);
+        assert!((ratio - 21.0).abs() < 1e-9);
+        assert!((Color::WHITE.contrast(Color::WHITE) - 1.0).abs() < 1e-9);
+
+        // #767676 on white is the classic 4.54:1 AA threshold
+        let grey = Color::parse("#767676").unwrap().contrast(Color::WHITE);
+        assert!((grey - 4.54).abs() < 0.01, "{}", grey);
+
+        // Half-transparent black on white is mid grey
+        let faded = Color::parse("#00000080").unwrap().contrast(Color::WHITE);
+        assert!(faded < MIN_TEXT_CONTRAST);
+    }
+
+    #[test]
+    fn test_mix() {
+        let primary = Color::parse("#0f172a").unwrap();
+        assert_eq!(primary.mix(Color::WHITE, 0.5).to_hex(), "#878b95");
+        assert_eq!(primary.mix(Color::BLACK, 1.0).to_hex(), "#000000");
+        assert_eq!(primary.mix(Color::WHITE, 0.0), primary);
+    }
+}
@@ -0,0 +1,809 @@
+//! Computed fields
+//!
+//! Derived values such as net, VAT and gross totals are declared in the
+//! content file's `[computed]` table or in the template with
+//! `// @computed: name = expression` comments, and evaluated before the data
+//! is passed to Typst. Each result is stored as `computed.<name>`.
+//!
+//! Expressions use numbers, `+ - * /`, parentheses, data paths and a few
+//! functions. A path with `[*]` yields one value per array element, and
+//! arithmetic on such lists works element by element:
+//!
+//! ```toml
+//! [computed]
+//! net = "sum(quote.items[*].price * quote.items[*].qty)"
+//!
+//! [computed.vat]
+//! expr = "computed.net * 0.19"
+//! currency = "EUR"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use regex::Regex;
+
+use crate::content::ContentFile;
+use crate::error::{Error, Result};
+use crate::locale::{currency_decimals, Locale};
+
+/// Functions available in expressions
+const FUNCTIONS: &[&str] = &["sum", "min", "max", "avg", "count", "round", "abs"];
+
+/// Options allowed in a `[computed.<name>]` table
+const OPTIONS: &[&str] = &["expr", "round", "currency"];
+
+/// A computed field declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedField {
+    /// Name under `computed`
+    pub name: String,
+    /// Expression source
+    pub expr: String,
+    /// Decimal places to round the result to
+    pub round: Option<u32>,
+    /// ISO currency code; implies rounding to the currency's minor unit
+    pub currency: Option<String>,
+}
+
+impl ComputedField {
+    /// Decimal places the result is rounded to, if any
+    pub fn decimals(&self) -> Option<u32> {
+        self.round
+            .or_else(|| self.currency.as_deref().map(currency_decimals))
+    }
+}
+
+/// Computed fields declared in a content file's `[computed]` table
+pub fn content_declarations(data: &toml::Value) -> Result<Vec<ComputedField>> {
+    let Some(table) = data.get("computed").and_then(|v| v.as_table()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut fields = Vec::new();
+    for (name, value) in table {
+        let invalid = |msg: &str| Error::Validation(format!("computed.{}: {}", name, msg));
+        let field = match value {
+            toml::Value::String(expr) => ComputedField {
+                name: name.clone(),
+                expr: expr.clone(),
+                round: None,
+                currency: None,
+            },
+            toml::Value::Table(options) => {
+                if let Some(key) = options.keys().find(|k| !OPTIONS.contains(&k.as_str())) {
+                    return Err(invalid(&format!(
+                        "unknown option '{}' (expected one of {})",
+                        key,
+                        OPTIONS.join(", ")
+                    )));
+                }
+                let expr = options
+                    .get("expr")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| invalid("missing expr"))?;
+                let round = match options.get("round") {
+                    Some(v) => Some(
+                        v.as_integer()
+                            .and_then(|n| u32::try_from(n).ok())
+                            .ok_or_else(|| invalid("round must be a non-negative integer"))?,
+                    ),
+                    None => None,
+                };
+                let currency = match options.get("currency") {
+                    Some(v) => Some(
+                        v.as_str()
+                            .ok_or_else(|| invalid("currency must be a string"))?
+                            .to_string(),
+                    ),
+                    None => None,
+                };
+                ComputedField {
+                    name: name.clone(),
+                    expr: expr.to_string(),
+                    round,
+                    currency,
+                }
+            }
+            _ => return Err(invalid("expected an expression or a table with expr")),
+        };
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// Computed fields declared in template comments (`// @computed: name = expr`)
+pub fn template_declarations(source: &str) -> Vec<ComputedField> {
+    let re =
+        Regex::new(r"//\s*@computed:\s*([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(.+)").expect("valid regex");
+    re.captures_iter(source)
+        .map(|cap| ComputedField {
+            name: cap[1].to_string(),
+            expr: cap[2].trim().to_string(),
+            round: None,
+            currency: None,
+        })
+        .collect()
+}
+
+/// Evaluate the computed fields of `content` and store them in `data`
+pub fn evaluate_into(content: &ContentFile, data: &mut serde_json::Value) -> Result<()> {
+    let mut fields = match std::fs::read_to_string(content.template_path()) {
+        Ok(source) => template_declarations(&source),
+        Err(e) => {
+            log::debug!(
+                "not reading computed fields from {}: {}",
+                content.template_path().display(),
+                e
+            );
+            Vec::new()
+        }
+    };
+    for field in content_declarations(content.as_toml())? {
+        fields.retain(|f| f.name != field.name);
+        fields.push(field);
+    }
+    evaluate(data, &fields)
+}
+
+/// Evaluate `fields` against `data`, storing each result as `computed.<name>`
+///
+/// Fields may refer to each other as `computed.<name>`; they are evaluated in
+/// dependency order. Cycles, missing paths and non-numeric operands are
+/// reported as [`Error::Validation`].
+pub fn evaluate(data: &mut serde_json::Value, fields: &[ComputedField]) -> Result<()> {
+    if fields.is_empty() {
+        return Ok(());
+    }
+
+    let mut parsed = BTreeMap::new();
+    for field in fields {
+        let expr = Parser::parse(&field.expr)
+            .map_err(|e| Error::Validation(format!("computed.{}: {}", field.name, e)))?;
+        parsed.insert(field.name.as_str(), (field, expr));
+    }
+
+    let order = evaluation_order(&parsed)?;
+    let locale = Locale::for_data(data);
+
+    // Expressions are replaced by their values as they are evaluated
+    let Some(root) = data.as_object_mut() else {
+        return Ok(());
+    };
+    root.insert(
+        "computed".to_string(),
+        serde_json::Value::Object(serde_json::Map::new()),
+    );
+
+    for name in order {
+        let (field, expr) = &parsed[name];
+        let fail = |msg: String| Error::Validation(format!("computed.{}: {}", name, msg));
+        let value = match expr.eval(data).map_err(fail)? {
+            Value::Number(n) => n,
+            Value::List(_) => {
+                return Err(fail(
+                    "evaluates to a list; aggregate it with sum(), min(), max() or count()"
+                        .to_string(),
+                ))
+            }
+        };
+        if !value.is_finite() {
+            return Err(fail("result is not a finite number".to_string()));
+        }
+        let value = match field.decimals() {
+            Some(decimals) => round(value, decimals),
+            None => value,
+        };
+
+        let computed = data["computed"].as_object_mut().expect("computed table");
+        computed.insert(name.to_string(), number(value));
+        if let Some(ref currency) = field.currency {
+            computed.insert(
+                format!("{}_formatted", name),
+                serde_json::Value::String(locale.format_currency(value, currency)),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Names in an order where every field comes after the fields it uses
+fn evaluation_order<'a>(
+    fields: &BTreeMap<&'a str, (&ComputedField, Expr)>,
+) -> Result<Vec<&'a str>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Visiting,
+        Done,
+    }
+
+    fn visit<'a>(
+        name: &'a str,
+        fields: &BTreeMap<&'a str, (&ComputedField, Expr)>,
+        state: &mut HashMap<&'a str, State>,
+        stack: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> Result<()> {
+        match state.get(name) {
+            Some(State::Done) => return Ok(()),
+            Some(State::Visiting) => {
+                let start = stack.iter().position(|n| *n == name).unwrap_or(0);
+                let mut cycle: Vec<&str> = stack[start..].to_vec();
+                cycle.push(name);
+                return Err(Error::Validation(format!(
+                    "cycle in computed fields: {}",
+                    cycle
+                        .iter()
+                        .map(|n| format!("computed.{}", n))
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )));
+            }
+            None => {}
+        }
+
+        state.insert(name, State::Visiting);
+        stack.push(name);
+        let (_, expr) = &fields[name];
+        for dependency in expr.computed_references() {
+            if let Some((&key, _)) = fields.get_key_value(dependency.as_str()) {
+                visit(key, fields, state, stack, order)?;
+            }
+        }
+        stack.pop();
+        state.insert(name, State::Done);
+        order.push(name);
+        Ok(())
+    }
+
+    let mut state = HashMap::new();
+    let mut order = Vec::new();
+    for &name in fields.keys() {
+        visit(name, fields, &mut state, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Round half away from zero to `decimals` places
+///
+/// The scaled value is nudged by a few ulps so that decimal halves stored
+/// slightly below their value (12.345 is 12.3449999...) still round up.
+fn round(value: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (value * factor * (1.0 + 8.0 * f64::EPSILON)).round() / factor
+}
+
+/// JSON number, as an integer when the value has no fraction
+fn number(value: f64) -> serde_json::Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        serde_json::Value::from(value as i64)
+    } else {
+        serde_json::Value::from(value)
+    }
+}
+
+/// Result of evaluating an expression
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    List(Vec<f64>),
+}
+
+/// One step of a data path
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Path(Vec<Segment>),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, char, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, data: &serde_json::Value) -> std::result::Result<Value, String> {
+        match self {
+            Expr::Number(n) => Ok(Value::Number(*n)),
+            Expr::Path(segments) => resolve(data, segments),
+            Expr::Negate(expr) => match expr.eval(data)? {
+                Value::Number(n) => Ok(Value::Number(-n)),
+                Value::List(list) => Ok(Value::List(list.into_iter().map(|n| -n).collect())),
+            },
+            Expr::Binary(left, op, right) => {
+                let apply = |a: f64, b: f64| -> std::result::Result<f64, String> {
+                    match op {
+                        '+' => Ok(a + b),
+                        '-' => Ok(a - b),
+                        '*' => Ok(a * b),
+                        _ if b == 0.0 => Err("division by zero".to_string()),
+                        _ => Ok(a / b),
+                    }
+                };
+                match (left.eval(data)?, right.eval(data)?) {
+                    (Value::Number(a), Value::Number(b)) => apply(a, b).map(Value::Number),
+                    (Value::List(a), Value::Number(b)) => a
+                        .into_iter()
+                        .map(|a| apply(a, b))
+                        .collect::<std::result::Result<_, _>>()
+                        .map(Value::List),
+                    (Value::Number(a), Value::List(b)) => b
+                        .into_iter()
+                        .map(|b| apply(a, b))
+                        .collect::<std::result::Result<_, _>>()
+                        .map(Value::List),
+                    (Value::List(a), Value::List(b)) if a.len() == b.len() => a
+                        .into_iter()
+                        .zip(b)
+                        .map(|(a, b)| apply(a, b))
+                        .collect::<std::result::Result<_, _>>()
+                        .map(Value::List),
+                    (Value::List(a), Value::List(b)) => Err(format!(
+                        "cannot combine lists of {} and {} values with '{}'",
+                        a.len(),
+                        b.len(),
+                        op
+                    )),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(data))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                call(name, args)
+            }
+        }
+    }
+
+    /// Names of computed fields this expression reads (`computed.<name>`)
+    fn computed_references(&self) -> Vec<String> {
+        match self {
+            Expr::Number(_) => Vec::new(),
+            Expr::Path(segments) => match segments.as_slice() {
+                [Segment::Key(root), Segment::Key(name), ..] if root == "computed" => {
+                    vec![name.clone()]
+                }
+                _ => Vec::new(),
+            },
+            Expr::Negate(expr) => expr.computed_references(),
+            Expr::Binary(left, _, right) => {
+                let mut refs = left.computed_references();
+                refs.extend(right.computed_references());
+                refs
+            }
+            Expr::Call(_, args) => args.iter().flat_map(Expr::computed_references).collect(),
+        }
+    }
+}
+
+fn call(name: &str, args: Vec<Value>) -> std::result::Result<Value, String> {
+    let values = || -> Vec<f64> {
+        args.iter()
+            .flat_map(|arg| match arg {
+                Value::Number(n) => vec![*n],
+                Value::List(list) => list.clone(),
+            })
+            .collect()
+    };
+    let non_empty = |values: Vec<f64>| {
+        if values.is_empty() {
+            Err(format!("{}() of no values", name))
+        } else {
+            Ok(values)
+        }
+    };
+
+    match name {
+        "sum" => Ok(Value::Number(values().iter().sum())),
+        "count" => Ok(Value::Number(values().len() as f64)),
+        "min" => Ok(Value::Number(
+            non_empty(values())?
+                .into_iter()
+                .fold(f64::INFINITY, f64::min),
+        )),
+        "max" => Ok(Value::Number(
+            non_empty(values())?
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max),
+        )),
+        "avg" => {
+            let values = non_empty(values())?;
+            Ok(Value::Number(
+                values.iter().sum::<f64>() / values.len() as f64,
+            ))
+        }
+        "abs" => match args.as_slice() {
+            [Value::Number(n)] => Ok(Value::Number(n.abs())),
+            [Value::List(list)] => Ok(Value::List(list.iter().map(|n| n.abs()).collect())),
+            _ => Err("abs() takes one argument".to_string()),
+        },
+        "round" => {
+            let (value, decimals) = match args.as_slice() {
+                [value] => (value, 0.0),
+                [value, Value::Number(d)] if *d >= 0.0 && d.fract() == 0.0 => (value, *d),
+                _ => return Err("round() takes a value and a number of decimals".to_string()),
+            };
+            let decimals = decimals as u32;
+            Ok(match value {
+                Value::Number(n) => Value::Number(round(*n, decimals)),
+                Value::List(list) => {
+                    Value::List(list.iter().map(|n| round(*n, decimals)).collect())
+                }
+            })
+        }
+        _ => Err(format!(
+            "unknown function '{}' (expected one of {})",
+            name,
+            FUNCTIONS.join(", ")
+        )),
+    }
+}
+
+/// Look up a path, expanding `[*]` over array elements
+fn resolve(data: &serde_json::Value, segments: &[Segment]) -> std::result::Result<Value, String> {
+    let mut current = vec![(String::new(), data)];
+    let mut is_list = false;
+
+    for segment in segments {
+        let mut next = Vec::new();
+        for (path, value) in current {
+            match segment {
+                Segment::Key(key) => {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    let child = value
+                        .get(key)
+                        .ok_or_else(|| format!("unknown path '{}'", path))?;
+                    next.push((path, child));
+                }
+                Segment::Index(index) => {
+                    let path = format!("{}[{}]", path, index);
+                    let array = value
+                        .as_array()
+                        .ok_or_else(|| format!("'{}' is not an array", path))?;
+                    let child = array
+                        .get(*index)
+                        .ok_or_else(|| format!("unknown path '{}'", path))?;
+                    next.push((path, child));
+                }
+                Segment::All => {
+                    let array = value
+                        .as_array()
+                        .ok_or_else(|| format!("'{}' is not an array", path))?;
+                    for (i, child) in array.iter().enumerate() {
+                        next.push((format!("{}[{}]", path, i), child));
+                    }
+                    is_list = true;
+                }
+            }
+        }
+        current = next;
+    }
+
+    let numbers = current
+        .into_iter()
+        .map(|(path, value)| {
+            value
+                .as_f64()
+                .ok_or_else(|| format!("'{}' is not a number (found {})", path, type_name(value)))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    if is_list {
+        Ok(Value::List(numbers))
+    } else {
+        Ok(Value::Number(numbers[0]))
+    }
+}
+
+fn type_name(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "nothing".to_string(),
+        serde_json::Value::Bool(b) => format!("boolean {}", b),
+        serde_json::Value::Number(n) => format!("number {}", n),
+        serde_json::Value::String(s) => format!("string \"{}\"", s),
+        serde_json::Value::Array(_) => "an array".to_string(),
+        serde_json::Value::Object(_) => "a table".to_string(),
+    }
+}
+
+/// Recursive descent parser for expressions
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(source: &'a str) -> std::result::Result<Expr, String> {
+        let mut parser = Parser { source, pos: 0 };
+        let expr = parser.sum()?;
+        parser.skip_space();
+        if parser.pos < source.len() {
+            return Err(format!(
+                "unexpected '{}' at column {}",
+                parser.rest().chars().next().unwrap_or_default(),
+                parser.pos + 1
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn skip_space(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.source.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> std::result::Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at column {}", c, self.pos + 1))
+        }
+    }
+
+    fn sum(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                '+'
+            } else if self.eat('-') {
+                '-'
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                '*'
+            } else if self.eat('/') {
+                '/'
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> std::result::Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> std::result::Result<Expr, String> {
+        self.skip_space();
+        if self.eat('(') {
+            let expr = self.sum()?;
+            self.expect(')')?;
+            return Ok(expr);
+        }
+
+        let rest = self.rest();
+        if rest.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", &rest[..len]))?;
+            self.pos += len;
+            return Ok(Expr::Number(number));
+        }
+
+        let name = self
+            .ident()
+            .ok_or_else(|| format!("expected a value at column {}", self.pos + 1))?;
+
+        if self.rest().starts_with('(') {
+            self.pos += 1;
+            let mut args = Vec::new();
+            if !self.eat(')') {
+                loop {
+                    args.push(self.sum()?);
+                    if self.eat(')') {
+                        break;
+                    }
+                    self.expect(',')?;
+                }
+            }
+            return Ok(Expr::Call(name, args));
+        }
+
+        let mut segments = vec![Segment::Key(name)];
+        loop {
+            if self.rest().starts_with('.') {
+                self.pos += 1;
+                let key = self
+                    .ident()
+                    .ok_or_else(|| format!("expected a key at column {}", self.pos + 1))?;
+                segments.push(Segment::Key(key));
+            } else if self.rest().starts_with('[') {
+                self.pos += 1;
+                let rest = self.rest();
+                let len = rest.find(']').ok_or("unclosed '['")?;
+                let segment = match rest[..len].trim() {
+                    "*" => Segment::All,
+                    index => Segment::Index(
+                        index
+                            .parse()
+                            .map_err(|_| format!("invalid index '{}'", index))?,
+                    ),
+                };
+                self.pos += len + 1;
+                segments.push(segment);
+            } else {
+                return Ok(Expr::Path(segments));
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        self.pos += len;
+        Some(rest[..len].to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote() -> serde_json::Value {
+        serde_json::json!({
+            "quote": {
+                "vat_rate": 0.19,
+                "items": [
+                    { "price": 100.0, "qty": 2 },
+                    { "price": 12.345, "qty": 1 }
+                ]
+            }
+        })
+    }
+
+    fn field(name: &str, expr: &str) -> ComputedField {
+        ComputedField {
+            name: name.to_string(),
+            expr: expr.to_string(),
+            round: None,
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn test_totals() {
+        let mut data = quote();
+        let fields = vec![
+            field("gross", "computed.net + computed.vat"),
+            ComputedField {
+                currency: Some("EUR".to_string()),
+                ..field("vat", "computed.net * quote.vat_rate")
+            },
+            ComputedField {
+                round: Some(2),
+                ..field("net", "sum(quote.items[*].price * quote.items[*].qty)")
+            },
+        ];
+        evaluate(&mut data, &fields).unwrap();
+
+        let computed = &data["computed"];
+        assert_eq!(computed["net"], 212.35);
+        assert_eq!(computed["vat"], 40.35);
+        assert_eq!(computed["vat_formatted"], "€40.35");
+        assert_eq!(computed["gross"], 252.7);
+    }
+
+    #[test]
+    fn test_functions_and_precedence() {
+        let eval = |expr: &str| {
+            let mut data = quote();
+            evaluate(&mut data, &[field("x", expr)]).map(|_| data["computed"]["x"].clone())
+        };
+        assert_eq!(eval("1 + 2 * 3").unwrap(), 7);
+        assert_eq!(eval("-(1 + 2) * 3").unwrap(), -9);
+        assert_eq!(eval("count(quote.items[*].qty)").unwrap(), 2);
+        assert_eq!(eval("max(quote.items[*].price)").unwrap(), 100);
+        assert_eq!(eval("round(quote.items[1].price, 2)").unwrap(), 12.35);
+        assert!(eval("quote.items[*].qty").is_err());
+        assert!(eval("frobnicate(1)").is_err());
+        assert!(eval("1 / 0").is_err());
+        assert!(eval("sum(quote.items[*].price").is_err());
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let mut data = quote();
+        data["quote"]["items"][1]["price"] = serde_json::json!("free");
+        let err = evaluate(
+            &mut data,
+            &[field(
+                "net",
+                "sum(quote.items[*].price * quote.items[*].qty)",
+            )],
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        assert!(err
+            .to_string()
+            .contains("'quote.items[1].price' is not a number (found string \"free\")"));
+
+        let err = evaluate(&mut quote(), &[field("x", "quote.missing + 1")]).unwrap_err();
+        assert!(err.to_string().contains("unknown path 'quote.missing'"));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let err = evaluate(
+            &mut quote(),
+            &[
+                field("a", "computed.b + 1"),
+                field("b", "computed.c * 2"),
+                field("c", "computed.a"),
+            ],
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        assert!(err
+            .to_string()
+            .contains("computed.a -> computed.b -> computed.c -> computed.a"));
+    }
+
+    #[test]
+    fn test_declarations() {
+        let data: toml::Value = toml::from_str(
+            r#"
+[computed]
+net = "sum(quote.items[*].price)"
+
+[computed.vat]
+expr = "computed.net * 0.19"
+currency = "JPY"
+"#,
+        )
+        .unwrap();
+        let fields = content_declarations(&data).unwrap();
+        assert_eq!(fields[0], field("net", "sum(quote.items[*].price)"));
+        assert_eq!(fields[1].decimals(), Some(0));
+
+        let invalid: toml::Value =
+            toml::from_str("[computed.x]\nexpr = \"1\"\nunit = \"m\"").unwrap();
+        assert!(content_declarations(&invalid).is_err());
+
+        let fields = template_declarations("// @computed: total = sum(a[*].b)\n#let x = 1");
+        assert_eq!(fields, vec![field("total", "sum(a[*].b)")]);
+    }
+}
@@ -13,7 +13,7 @@ use crate::error::{Error, Result};
 const APP_NAME: &str = "tmpltr";
 
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppConfig {
     /// Path configuration
@@ -28,18 +28,6 @@ pub struct AppConfig {
     pub experimental: ExperimentalConfig,
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            paths: PathsConfig::default(),
-            brand: BrandConfig::default(),
-            typst: TypstConfig::default(),
-            output: OutputConfig::default(),
-            experimental: ExperimentalConfig::default(),
-        }
-    }
-}
-
 /// Path configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -55,7 +43,7 @@ pub struct PathsConfig {
 }
 
 /// Brand configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct BrandConfig {
     /// Default brand ID to use when --brand is not specified
@@ -73,12 +61,6 @@ impl Default for PathsConfig {
     }
 }
 
-impl Default for BrandConfig {
-    fn default() -> Self {
-        Self { default: None }
-    }
-}
-
 /// Typst-related configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -123,35 +105,35 @@ impl Default for TypstConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct OutputConfig {
-    /// Default output format (pdf, svg, html)
+    /// Default output format (pdf, svg, png, html)
     pub format: String,
+    /// Resolution for PNG output in pixels per inch
+    pub png_ppi: u32,
     /// Watch debounce in milliseconds
     pub watch_debounce_ms: u64,
+    /// Default output filename pattern, e.g. "{quote.number}-{date:%Y%m%d}.{ext}"
+    pub filename_pattern: String,
 }
 
 impl Default for OutputConfig {
     fn default() -> Self {
         Self {
             format: "pdf".to_string(),
+            png_ppi: 144,
             watch_debounce_ms: 300,
+            filename_pattern: crate::output::DEFAULT_FILENAME_PATTERN.to_string(),
         }
     }
 }
 
 /// Experimental features configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ExperimentalConfig {
     /// Enable experimental HTML output
     pub html: bool,
 }
 
-impl Default for ExperimentalConfig {
-    fn default() -> Self {
-        Self { html: false }
-    }
-}
-
 /// Resolved application paths
 #[derive(Debug, Clone)]
 pub struct ResolvedPaths {
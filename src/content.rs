@@ -25,6 +25,8 @@ pub struct ContentMeta {
     pub template_version: Option<String>,
     /// When the content file was generated
     pub generated_at: Option<DateTime<Utc>>,
+    /// Output filename pattern (overrides the configured pattern)
+    pub output: Option<String>,
 }
 
 /// Block format type
@@ -38,14 +40,36 @@ pub enum BlockFormat {
     Typst,
     /// Plain text (escaped for Typst)
     Plain,
+    /// Djot markup (converted to Typst)
+    Djot,
+    /// Safe HTML subset (converted to Typst)
+    Html,
+    /// AsciiDoc markup (converted to Typst)
+    Asciidoc,
 }
 
 impl BlockFormat {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "markdown" => Some(BlockFormat::Markdown),
+            "typst" => Some(BlockFormat::Typst),
+            "plain" => Some(BlockFormat::Plain),
+            "djot" => Some(BlockFormat::Djot),
+            "html" => Some(BlockFormat::Html),
+            "asciidoc" => Some(BlockFormat::Asciidoc),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             BlockFormat::Markdown => "markdown",
             BlockFormat::Typst => "typst",
             BlockFormat::Plain => "plain",
+            BlockFormat::Djot => "djot",
+            BlockFormat::Html => "html",
+            BlockFormat::Asciidoc => "asciidoc",
         }
     }
 }
@@ -59,19 +83,240 @@ pub enum BlockType {
     Text,
     /// Table with columns and rows
     Table,
+    /// Image with path, caption and width
+    Image,
+    /// Bullet list of items
+    List,
+    /// Ordered key/value entries (e.g. payment terms)
+    KeyValue,
+    /// Repeated records with named fields (e.g. line items)
+    Records,
 }
 
 impl BlockType {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "text" => Some(BlockType::Text),
+            "table" => Some(BlockType::Table),
+            "image" => Some(BlockType::Image),
+            "list" => Some(BlockType::List),
+            "keyvalue" => Some(BlockType::KeyValue),
+            "records" => Some(BlockType::Records),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             BlockType::Text => "text",
             BlockType::Table => "table",
+            BlockType::Image => "image",
+            BlockType::List => "list",
+            BlockType::KeyValue => "keyvalue",
+            BlockType::Records => "records",
+        }
+    }
+
+    /// All block types
+    pub fn all() -> &'static [BlockType] {
+        &[
+            BlockType::Text,
+            BlockType::Table,
+            BlockType::Image,
+            BlockType::List,
+            BlockType::KeyValue,
+            BlockType::Records,
+        ]
+    }
+
+    /// Keys holding the block's data (besides `title` and `type`)
+    pub fn data_keys(&self) -> &'static [&'static str] {
+        match self {
+            BlockType::Text => &["content"],
+            BlockType::Table => &["columns", "rows"],
+            BlockType::Image => &["path", "caption", "width"],
+            BlockType::List => &["items"],
+            BlockType::KeyValue => &["entries"],
+            BlockType::Records => &["fields", "records"],
+        }
+    }
+
+    /// Normalize a value given to `set` into the block's data keys.
+    ///
+    /// Besides the full object (`{"items": [...]}`), each type accepts a
+    /// shorthand: an image path, list items as an array or one per line,
+    /// key/value entries as `[key, value]` pairs, table rows and records as
+    /// an array.
+    pub fn data_from_json(
+        &self,
+        value: serde_json::Value,
+    ) -> Result<serde_json::Map<String, serde_json::Value>> {
+        use serde_json::Value;
+
+        let invalid = |expected: &str| {
+            Error::Content(format!(
+                "invalid {} block data (expected {})",
+                self.as_str(),
+                expected
+            ))
+        };
+
+        let mut data = match value {
+            Value::Object(object) if self.data_keys().iter().any(|k| object.contains_key(*k)) => {
+                if let Some(key) = object
+                    .keys()
+                    .find(|k| !self.data_keys().contains(&k.as_str()))
+                {
+                    return Err(Error::Content(format!(
+                        "unknown key '{}' for {} block (expected one of {})",
+                        key,
+                        self.as_str(),
+                        self.data_keys().join(", ")
+                    )));
+                }
+                object
+            }
+            value => {
+                let (key, value) = match (self, value) {
+                    (BlockType::Text, Value::String(s)) => ("content", Value::String(s)),
+                    (BlockType::Table, rows @ Value::Array(_)) => ("rows", rows),
+                    (BlockType::Image, Value::String(path)) => ("path", Value::String(path)),
+                    (BlockType::List, items @ Value::Array(_)) => ("items", items),
+                    (BlockType::List, Value::String(text)) => (
+                        "items",
+                        text.lines()
+                            .map(|line| line.trim().trim_start_matches("- "))
+                            .filter(|line| !line.is_empty())
+                            .map(|line| Value::String(line.to_string()))
+                            .collect(),
+                    ),
+                    (BlockType::KeyValue, entries @ Value::Array(_)) => ("entries", entries),
+                    (BlockType::Records, records @ Value::Array(_)) => ("records", records),
+                    _ => return Err(invalid(self.shorthand())),
+                };
+                let mut data = serde_json::Map::new();
+                data.insert(key.to_string(), value);
+                data
+            }
+        };
+
+        // Entries are stored as an array of {key, value} tables. Only arrays
+        // are accepted: JSON objects do not keep their key order.
+        if let Some(entries) = data.get_mut("entries") {
+            let pairs: Vec<(Value, Value)> = match entries.take() {
+                Value::Array(array) => array
+                    .into_iter()
+                    .map(|entry| match entry {
+                        Value::Array(mut pair) if pair.len() == 2 => {
+                            let value = pair.pop().unwrap_or_default();
+                            Some((pair.pop().unwrap_or_default(), value))
+                        }
+                        Value::Object(mut object) => {
+                            let value = object.remove("value").unwrap_or_default();
+                            Some((object.remove("key")?, value))
+                        }
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| invalid("{key, value} objects or [key, value] pairs"))?,
+                _ => return Err(invalid("an array of entries")),
+            };
+            *entries = pairs
+                .into_iter()
+                .map(|(key, value)| {
+                    let text = |v: Value| match v {
+                        Value::String(s) => Some(s),
+                        Value::Number(n) => Some(n.to_string()),
+                        Value::Bool(b) => Some(b.to_string()),
+                        _ => None,
+                    };
+                    match (text(key), text(value)) {
+                        (Some(key), Some(value)) => {
+                            Ok(serde_json::json!({ "key": key, "value": value }))
+                        }
+                        _ => Err(invalid("entries with text keys and values")),
+                    }
+                })
+                .collect::<Result<_>>()?;
         }
+
+        let check = |key: &str, valid: fn(&Value) -> bool, expected: &str| match data.get(key) {
+            Some(value) if !valid(value) => Err(invalid(&format!("{} to be {}", key, expected))),
+            _ => Ok(()),
+        };
+        check("content", Value::is_string, "a string")?;
+        check("path", Value::is_string, "a string")?;
+        check("caption", Value::is_string, "a string")?;
+        check(
+            "width",
+            |v| v.as_str().is_some_and(is_image_width),
+            "a length like \"8cm\" or \"50%\"",
+        )?;
+        check("columns", is_string_array, "an array of strings")?;
+        check("fields", is_string_array, "an array of strings")?;
+        check("items", is_string_array, "an array of strings")?;
+        check(
+            "rows",
+            |v| {
+                v.as_array()
+                    .is_some_and(|rows| rows.iter().all(is_scalar_array))
+            },
+            "an array of arrays",
+        )?;
+        check(
+            "records",
+            |v| v.as_array().is_some_and(|r| r.iter().all(Value::is_object)),
+            "an array of objects",
+        )?;
+        Ok(data)
     }
+
+    /// Shorthand accepted by [`BlockType::data_from_json`]
+    fn shorthand(&self) -> &'static str {
+        match self {
+            BlockType::Text => "a string",
+            BlockType::Table => "an array of rows",
+            BlockType::Image => "an image path or {path, caption, width}",
+            BlockType::List => "an array of items or one item per line",
+            BlockType::KeyValue => "an array of [key, value] pairs",
+            BlockType::Records => "an array of objects",
+        }
+    }
+}
+
+/// Image block widths: a Typst length or ratio such as `8cm` or `50%`
+pub const IMAGE_WIDTH_PATTERN: &str = r"^\d+(\.\d+)?(pt|mm|cm|in|em|%)$";
+
+/// Whether `value` is a valid image block width
+pub fn is_image_width(value: &str) -> bool {
+    regex::Regex::new(IMAGE_WIDTH_PATTERN)
+        .expect("valid regex")
+        .is_match(value)
+}
+
+fn is_string_array(value: &serde_json::Value) -> bool {
+    value
+        .as_array()
+        .is_some_and(|a| a.iter().all(serde_json::Value::is_string))
+}
+
+fn is_scalar_array(value: &serde_json::Value) -> bool {
+    value.as_array().is_some_and(|a| {
+        a.iter()
+            .all(|v| !v.is_array() && !v.is_object() && !v.is_null())
+    })
+}
+
+/// One entry of a key/value block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyValueEntry {
+    pub key: String,
+    pub value: String,
 }
 
 /// A content block
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ContentBlock {
     /// Human-readable title
     pub title: Option<String>,
@@ -87,6 +332,20 @@ pub struct ContentBlock {
     pub columns: Option<Vec<String>>,
     /// Table rows (for table blocks)
     pub rows: Option<Vec<Vec<String>>>,
+    /// Image path (for image blocks)
+    pub path: Option<String>,
+    /// Image caption (for image blocks)
+    pub caption: Option<String>,
+    /// Image width as a Typst length, e.g. "8cm" (for image blocks)
+    pub width: Option<String>,
+    /// List items (for list blocks)
+    pub items: Option<Vec<String>>,
+    /// Entries (for key/value blocks)
+    pub entries: Option<Vec<KeyValueEntry>>,
+    /// Field names in display order (for records blocks)
+    pub fields: Option<Vec<String>>,
+    /// Records (for records blocks)
+    pub records: Option<Vec<toml::Table>>,
 }
 
 impl ContentBlock {
@@ -95,10 +354,8 @@ impl ContentBlock {
         Self {
             title: Some(title.into()),
             format: BlockFormat::Markdown,
-            block_type: BlockType::Text,
             content: Some(content.into()),
-            columns: None,
-            rows: None,
+            ..Default::default()
         }
     }
 
@@ -108,13 +365,151 @@ impl ContentBlock {
             title: Some(title.into()),
             format: BlockFormat::Plain,
             block_type: BlockType::Table,
-            content: None,
             columns: Some(columns),
             rows: Some(rows),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new image block
+    pub fn image(
+        title: impl Into<String>,
+        path: impl Into<String>,
+        caption: Option<String>,
+        width: Option<String>,
+    ) -> Self {
+        Self {
+            title: Some(title.into()),
+            block_type: BlockType::Image,
+            path: Some(path.into()),
+            caption,
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Create a new list block
+    pub fn list(title: impl Into<String>, items: Vec<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            block_type: BlockType::List,
+            items: Some(items),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new key/value block
+    pub fn key_value(title: impl Into<String>, entries: Vec<KeyValueEntry>) -> Self {
+        Self {
+            title: Some(title.into()),
+            block_type: BlockType::KeyValue,
+            entries: Some(entries),
+            ..Default::default()
+        }
+    }
+
+    /// Create an empty block of `block_type` for a template's editable-block()
+    ///
+    /// `fields` become table columns or record fields; a default body is
+    /// used as text content, image path or list items (one per line).
+    pub fn skeleton(
+        title: impl Into<String>,
+        block_type: BlockType,
+        fields: &[String],
+        default: Option<&str>,
+    ) -> Self {
+        let default = default.unwrap_or_default();
+        match block_type {
+            BlockType::Text => Self::text(title, default),
+            BlockType::Table => Self::table(title, fields.to_vec(), Vec::new()),
+            BlockType::Image => Self::image(title, default, None, None),
+            BlockType::List => Self::list(
+                title,
+                default
+                    .lines()
+                    .map(|line| line.trim().trim_start_matches("- ").to_string())
+                    .filter(|line| !line.is_empty())
+                    .collect(),
+            ),
+            BlockType::KeyValue => Self::key_value(title, Vec::new()),
+            BlockType::Records => Self::records(title, fields.to_vec(), Vec::new()),
+        }
+    }
+
+    /// Data keys of this block's type as a TOML table
+    pub fn data(&self) -> toml::Table {
+        let mut data = toml::Table::new();
+        if let Ok(toml::Value::Table(values)) = toml::Value::try_from(self) {
+            for key in self.block_type.data_keys() {
+                if let Some(value) = values.get(*key) {
+                    data.insert(key.to_string(), value.clone());
+                }
+            }
+        }
+        data
+    }
+
+    /// Create a new records block
+    pub fn records(
+        title: impl Into<String>,
+        fields: Vec<String>,
+        records: Vec<toml::Table>,
+    ) -> Self {
+        Self {
+            title: Some(title.into()),
+            block_type: BlockType::Records,
+            fields: Some(fields),
+            records: Some(records),
+            ..Default::default()
         }
     }
 }
 
+/// Write block data into an editable block table.
+///
+/// Keys in `data` replace those in the table, other keys are kept; arrays of
+/// tables (records, entries) are written as `[[...]]` sections.
+pub(crate) fn write_block_data(table: &mut toml_edit::Table, data: &toml::Table) {
+    for (key, value) in data {
+        table.remove(key);
+        let item = match value {
+            toml::Value::Array(array)
+                if !array.is_empty() && array.iter().all(toml::Value::is_table) =>
+            {
+                let mut tables = toml_edit::ArrayOfTables::new();
+                for value in array {
+                    if let toml_edit::Value::InlineTable(inline) = edit_value(value) {
+                        tables.push(inline.into_table());
+                    }
+                }
+                toml_edit::Item::ArrayOfTables(tables)
+            }
+            value => toml_edit::Item::Value(edit_value(value)),
+        };
+        table.insert(key, item);
+    }
+}
+
+/// Convert a TOML value into its editable counterpart
+fn edit_value(value: &toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::String(s) => s.as_str().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(d) => (*d).into(),
+        toml::Value::Array(array) => {
+            toml_edit::Value::Array(array.iter().map(edit_value).collect())
+        }
+        toml::Value::Table(table) => toml_edit::Value::InlineTable(
+            table
+                .iter()
+                .map(|(k, v)| (k.as_str(), edit_value(v)))
+                .collect(),
+        ),
+    }
+}
+
 /// A parsed content file
 #[derive(Debug, Clone)]
 pub struct ContentFile {
@@ -243,12 +638,18 @@ impl ContentFile {
             .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
             .map(|dt| dt.with_timezone(&Utc));
 
+        let output = meta_table
+            .get("output")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
         Ok(ContentMeta {
             template,
             resolved_template: None,
             template_id,
             template_version,
             generated_at,
+            output,
         })
     }
 
@@ -271,7 +672,8 @@ impl ContentFile {
                 let block_type = value
                     .get("type")
                     .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
+                    .unwrap_or(BlockType::Text.as_str())
+                    .to_string();
 
                 self.blocks_index.insert(
                     path.clone(),
@@ -281,7 +683,7 @@ impl ContentFile {
                         title,
                         kind: BlockKind::Block,
                         format,
-                        block_type,
+                        block_type: Some(block_type),
                     },
                 );
             }
@@ -358,6 +760,37 @@ impl ContentFile {
         }
     }
 
+    /// Type of the block at `path`, if it is a block
+    pub fn block_type(&self, path: &str) -> Result<Option<BlockType>> {
+        let Some(info) = self.blocks_index.get(path) else {
+            return Ok(None);
+        };
+        match info.block_type.as_deref() {
+            None => Ok(None),
+            Some(name) => BlockType::from_str(name)
+                .map(Some)
+                .ok_or_else(|| Error::Content(format!("{}: unknown block type '{}'", path, name))),
+        }
+    }
+
+    /// Data of a structured (non-text) block as JSON, e.g. `{"items": [...]}`
+    pub fn block_data(&self, path: &str) -> Result<Option<serde_json::Value>> {
+        let block_type = match self.block_type(path)? {
+            None | Some(BlockType::Text) => return Ok(None),
+            Some(block_type) => block_type,
+        };
+        let Some(block) = self.get(path).and_then(|v| v.as_table()) else {
+            return Ok(None);
+        };
+        let mut data = serde_json::Map::new();
+        for key in block_type.data_keys() {
+            if let Some(value) = block.get(*key) {
+                data.insert(key.to_string(), serde_json::to_value(value)?);
+            }
+        }
+        Ok(Some(serde_json::Value::Object(data)))
+    }
+
     /// Get block info by path
     pub fn get_block_info(&self, path: &str) -> Option<&BlockInfo> {
         self.blocks_index.get(path)
@@ -376,10 +809,14 @@ impl ContentFile {
                 title: title.to_string(),
             }),
             1 => Ok(matches[0]),
-            _ => Err(Error::AmbiguousTitle {
-                title: title.to_string(),
-                matches: matches.iter().map(|b| b.path.clone()).collect(),
-            }),
+            _ => {
+                let mut paths: Vec<String> = matches.iter().map(|b| b.path.clone()).collect();
+                paths.sort();
+                Err(Error::AmbiguousTitle {
+                    title: title.to_string(),
+                    matches: paths,
+                })
+            }
         }
     }
 
@@ -409,8 +846,7 @@ impl ContentFile {
     pub fn template_path(&self) -> &Path {
         self.meta
             .resolved_template
-            .as_ref()
-            .map(|p| p.as_path())
+            .as_deref()
             .unwrap_or(Path::new(&self.meta.template))
     }
 }
@@ -506,6 +942,60 @@ impl ContentBuilder {
         self
     }
 
+    /// Add an image block
+    pub fn image_block(
+        self,
+        name: &str,
+        title: impl Into<String>,
+        path: impl Into<String>,
+        caption: Option<String>,
+        width: Option<String>,
+    ) -> Self {
+        self.typed_block(name, ContentBlock::image(title, path, caption, width))
+    }
+
+    /// Add a list block
+    pub fn list_block(self, name: &str, title: impl Into<String>, items: Vec<String>) -> Self {
+        self.typed_block(name, ContentBlock::list(title, items))
+    }
+
+    /// Add a key/value block
+    pub fn key_value_block(
+        self,
+        name: &str,
+        title: impl Into<String>,
+        entries: Vec<KeyValueEntry>,
+    ) -> Self {
+        self.typed_block(name, ContentBlock::key_value(title, entries))
+    }
+
+    /// Add a records block; `fields` gives the column order
+    pub fn records_block(
+        self,
+        name: &str,
+        title: impl Into<String>,
+        fields: Vec<String>,
+        records: Vec<toml::Table>,
+    ) -> Self {
+        self.typed_block(name, ContentBlock::records(title, fields, records))
+    }
+
+    /// Add a structured block, writing only the keys of its type
+    pub fn typed_block(mut self, name: &str, block: ContentBlock) -> Self {
+        let mut table = toml::map::Map::new();
+        if let Some(title) = block.title.clone() {
+            table.insert("title".to_string(), toml::Value::String(title));
+        }
+        table.insert(
+            "type".to_string(),
+            toml::Value::String(block.block_type.as_str().to_string()),
+        );
+        table.extend(block.data());
+        self.blocks
+            .insert(name.to_string(), toml::Value::Table(table));
+        self
+    }
+
     /// Insert a value at a nested path
     fn insert_nested(
         map: &mut toml::map::Map<String, toml::Value>,
@@ -633,4 +1123,105 @@ content = "This is the **introduction**."
         assert!(content.contains("template = \"test-template\""));
         assert!(content.contains("Introduction"));
     }
+
+    #[test]
+    fn test_structured_blocks() {
+        let mut item = toml::Table::new();
+        item.insert("description".into(), "Design".into());
+        item.insert("qty".into(), 3.into());
+        let content = ContentBuilder::new("quote.typ")
+            .image_block("photo", "Photo", "site.png", None, Some("8cm".into()))
+            .list_block("scope", "Scope", vec!["Design".into(), "Build".into()])
+            .key_value_block(
+                "payment",
+                "Payment",
+                vec![KeyValueEntry {
+                    key: "Net".into(),
+                    value: "30 days".into(),
+                }],
+            )
+            .records_block("items", "Items", vec!["description".into()], vec![item])
+            .build()
+            .unwrap();
+        let file = ContentFile::parse(PathBuf::from("q.toml"), &content).unwrap();
+
+        assert_eq!(
+            file.block_type("blocks.items").unwrap(),
+            Some(BlockType::Records)
+        );
+        assert_eq!(
+            file.block_data("blocks.photo").unwrap(),
+            Some(serde_json::json!({ "path": "site.png", "width": "8cm" }))
+        );
+        assert_eq!(
+            file.block_data("blocks.scope").unwrap(),
+            Some(serde_json::json!({ "items": ["Design", "Build"] }))
+        );
+        assert_eq!(
+            file.block_data("blocks.payment").unwrap(),
+            Some(serde_json::json!({ "entries": [{ "key": "Net", "value": "30 days" }] }))
+        );
+        assert_eq!(
+            file.block_data("blocks.items").unwrap().unwrap()["records"][0]["qty"],
+            3
+        );
+    }
+
+    #[test]
+    fn test_block_data_from_json() {
+        use serde_json::json;
+
+        let data = BlockType::List
+            .data_from_json(json!("- one\n\ntwo"))
+            .unwrap();
+        assert_eq!(data["items"], json!(["one", "two"]));
+
+        let data = BlockType::Image.data_from_json(json!("logo.png")).unwrap();
+        assert_eq!(data["path"], "logo.png");
+
+        let data = BlockType::KeyValue
+            .data_from_json(json!([["Net", 30], { "key": "Due", "value": "on receipt" }]))
+            .unwrap();
+        assert_eq!(
+            data["entries"],
+            json!([
+                { "key": "Net", "value": "30" },
+                { "key": "Due", "value": "on receipt" }
+            ])
+        );
+
+        assert!(BlockType::KeyValue
+            .data_from_json(json!({ "Net": 30, "Due": "on receipt" }))
+            .is_err());
+        assert!(BlockType::KeyValue
+            .data_from_json(json!({ "entries": { "Net": 30 } }))
+            .is_err());
+
+        let data = BlockType::Records
+            .data_from_json(json!([{ "description": "Build", "qty": 1 }]))
+            .unwrap();
+        assert_eq!(data["records"][0]["qty"], 1);
+
+        assert!(BlockType::Records.data_from_json(json!("x")).is_err());
+        assert!(BlockType::Image
+            .data_from_json(json!({ "path": 1 }))
+            .is_err());
+        assert!(BlockType::List
+            .data_from_json(json!({ "items": [], "rows": [] }))
+            .is_err());
+
+        let data = BlockType::Image
+            .data_from_json(json!({ "path": "a.png", "width": "12.5mm" }))
+            .unwrap();
+        assert_eq!(data["width"], "12.5mm");
+        for width in ["8 cm", "80mm)", "1cm + 2cm", "50"] {
+            assert!(
+                BlockType::Image
+                    .data_from_json(json!({ "path": "a.png", "width": width }))
+                    .is_err(),
+                "{}",
+                width
+            );
+        }
+    }
 }
@@ -0,0 +1,325 @@
+//! Block format conversion
+//!
+//! Text blocks are written in one of several formats (`format = "..."`) and
+//! converted to Typst markup before compiling. Each format is handled by a
+//! [`BlockConverter`]; the [`ConverterRegistry`] maps format names to
+//! converters and is what compilation, validation and schema generation
+//! consult for the available formats.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::asciidoc::asciidoc_to_typst;
+use crate::djot::djot_to_typst;
+use crate::error::{Error, Result};
+use crate::html::html_to_typst;
+use crate::markdown::{
+    escape_line_start, escape_typst, heading_labels, markdown_to_typst_with_labels,
+};
+
+/// Format assumed for blocks without a `format` key
+pub const DEFAULT_FORMAT: &str = "markdown";
+
+/// Information shared by all blocks of a document during conversion
+#[derive(Debug, Clone, Default)]
+pub struct ConvertContext {
+    /// Labels declared by any block, which references may point to
+    pub labels: HashSet<String>,
+}
+
+/// Converts the source of a text block to Typst markup
+pub trait BlockConverter: Send + Sync {
+    /// Format name, as written in `format = "..."`
+    fn name(&self) -> &str;
+
+    /// Convert block source to Typst markup
+    fn convert(&self, source: &str, context: &ConvertContext) -> Result<String>;
+
+    /// Labels declared in the source that other blocks may reference
+    fn labels(&self, _source: &str) -> HashSet<String> {
+        HashSet::new()
+    }
+}
+
+/// Markdown (CommonMark with GFM extensions and math)
+pub struct MarkdownConverter;
+
+impl BlockConverter for MarkdownConverter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn convert(&self, source: &str, context: &ConvertContext) -> Result<String> {
+        markdown_to_typst_with_labels(source, &context.labels)
+    }
+
+    fn labels(&self, source: &str) -> HashSet<String> {
+        heading_labels(source)
+    }
+}
+
+/// Typst markup, passed through unchanged
+pub struct TypstConverter;
+
+impl BlockConverter for TypstConverter {
+    fn name(&self) -> &str {
+        "typst"
+    }
+
+    fn convert(&self, source: &str, _context: &ConvertContext) -> Result<String> {
+        Ok(source.to_string())
+    }
+}
+
+/// Plain text, escaped so that it renders literally
+pub struct PlainConverter;
+
+impl BlockConverter for PlainConverter {
+    fn name(&self) -> &str {
+        "plain"
+    }
+
+    fn convert(&self, source: &str, _context: &ConvertContext) -> Result<String> {
+        Ok(escape_plain(source))
+    }
+}
+
+/// Djot markup
+pub struct DjotConverter;
+
+impl BlockConverter for DjotConverter {
+    fn name(&self) -> &str {
+        "djot"
+    }
+
+    fn convert(&self, source: &str, context: &ConvertContext) -> Result<String> {
+        Ok(djot_to_typst(source, &context.labels))
+    }
+
+    fn labels(&self, source: &str) -> HashSet<String> {
+        crate::djot::heading_labels(source)
+    }
+}
+
+/// AsciiDoc markup
+pub struct AsciidocConverter;
+
+impl BlockConverter for AsciidocConverter {
+    fn name(&self) -> &str {
+        "asciidoc"
+    }
+
+    fn convert(&self, source: &str, context: &ConvertContext) -> Result<String> {
+        Ok(asciidoc_to_typst(source, &context.labels))
+    }
+
+    fn labels(&self, source: &str) -> HashSet<String> {
+        crate::asciidoc::heading_labels(source)
+    }
+}
+
+/// A safe subset of HTML; other elements are dropped
+pub struct HtmlConverter;
+
+impl BlockConverter for HtmlConverter {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn convert(&self, source: &str, context: &ConvertContext) -> Result<String> {
+        Ok(html_to_typst(source, &context.labels))
+    }
+}
+
+/// Block converters by format name
+pub struct ConverterRegistry {
+    converters: BTreeMap<String, Box<dyn BlockConverter>>,
+}
+
+impl Default for ConverterRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ConverterRegistry {
+    /// A registry without any converters
+    pub fn empty() -> Self {
+        Self {
+            converters: BTreeMap::new(),
+        }
+    }
+
+    /// A registry with the built-in formats
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        registry.register(MarkdownConverter);
+        registry.register(TypstConverter);
+        registry.register(PlainConverter);
+        registry.register(DjotConverter);
+        registry.register(AsciidocConverter);
+        registry.register(HtmlConverter);
+        registry
+    }
+
+    /// Add a converter, replacing any registered under the same name
+    pub fn register(&mut self, converter: impl BlockConverter + 'static) {
+        self.converters
+            .insert(converter.name().to_string(), Box::new(converter));
+    }
+
+    pub fn get(&self, format: &str) -> Option<&dyn BlockConverter> {
+        self.converters.get(format).map(|c| c.as_ref())
+    }
+
+    /// Registered format names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.converters.keys().map(String::as_str).collect()
+    }
+
+    /// Convert the `content` of every text block under `blocks` to Typst.
+    ///
+    /// The original text is kept in the block's `source` key.
+    pub fn convert_blocks(&self, data: &mut serde_json::Value) -> Result<()> {
+        let Some(blocks) = data.get_mut("blocks").and_then(|v| v.as_object_mut()) else {
+            return Ok(());
+        };
+
+        // Labels of any block may be referenced from every other block
+        let mut context = ConvertContext::default();
+        for (name, block) in blocks.iter() {
+            if let Some((converter, source)) = self.block_source(name, block)? {
+                context.labels.extend(converter.labels(source));
+            }
+        }
+
+        for (name, block) in blocks.iter_mut() {
+            let Some((converter, source)) = self.block_source(name, block)? else {
+                continue;
+            };
+            let source = source.to_string();
+            let typst = converter.convert(&source, &context)?;
+            if let Some(block) = block.as_object_mut() {
+                block.insert("source".to_string(), serde_json::Value::String(source));
+                block.insert("content".to_string(), serde_json::Value::String(typst));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converter and text of a block with string content
+    fn block_source<'a>(
+        &self,
+        name: &str,
+        block: &'a serde_json::Value,
+    ) -> Result<Option<(&dyn BlockConverter, &'a str)>> {
+        let Some(source) = block.get("content").and_then(|v| v.as_str()) else {
+            return Ok(None);
+        };
+        let format = block
+            .get("format")
+            .and_then(|v| v.as_str())
+            .unwrap_or(DEFAULT_FORMAT);
+        let converter = self.get(format).ok_or_else(|| {
+            Error::Content(format!(
+                "blocks.{}: unknown format '{}' (expected one of {})",
+                name,
+                format,
+                self.names().join(", ")
+            ))
+        })?;
+        Ok(Some((converter, source)))
+    }
+}
+
+/// Escape text to be appended to `output`
+///
+/// Besides escaping special characters, text must not start a list or
+/// heading at the start of a line, nor continue a function call that ends
+/// `output` (`#link(..)[..](` or `#raw(..).x` would be read as code).
+pub(crate) fn escape_text(output: &str, text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (n, line) in escape_typst(text).split('\n').enumerate() {
+        if n > 0 {
+            escaped.push('\n');
+        }
+        if n > 0 || output.is_empty() || output.ends_with('\n') {
+            escaped.push_str(&escape_line_start(line));
+        } else {
+            escaped.push_str(line);
+        }
+    }
+
+    if output.ends_with([')', ']']) {
+        let mut chars = escaped.chars();
+        match (chars.next(), chars.next()) {
+            (Some('('), _) => escaped.insert(0, '\\'),
+            (Some('.'), Some(c)) if c.is_alphanumeric() => escaped.insert(0, '\\'),
+            _ => {}
+        }
+    }
+    escaped
+}
+
+/// Escape plain text so that Typst renders it literally
+pub fn escape_plain(text: &str) -> String {
+    text.lines()
+        .map(|line| escape_line_start(&escape_typst(line)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_formats() {
+        let registry = ConverterRegistry::builtin();
+        assert_eq!(
+            registry.names(),
+            vec!["asciidoc", "djot", "html", "markdown", "plain", "typst"]
+        );
+    }
+
+    #[test]
+    fn test_escape_plain() {
+        assert_eq!(
+            escape_plain("Price: $5 #1\n- not a list\n= not a heading"),
+            "Price: \\$5 \\#1\n\\- not a list\n\\= not a heading"
+        );
+    }
+
+    #[test]
+    fn test_convert_blocks() {
+        let mut data = serde_json::json!({
+            "blocks": {
+                "intro": { "content": "See @terms and *this*." },
+                "terms": { "format": "djot", "content": "{#terms}\n# Terms" },
+                "note": { "format": "plain", "content": "50% off *today*" },
+                "table": { "type": "table", "columns": ["A"], "rows": [["1"]] }
+            }
+        });
+        ConverterRegistry::builtin()
+            .convert_blocks(&mut data)
+            .unwrap();
+
+        let blocks = &data["blocks"];
+        assert_eq!(blocks["intro"]["content"], "See @terms and _this_.");
+        assert_eq!(blocks["intro"]["source"], "See @terms and *this*.");
+        assert_eq!(blocks["terms"]["content"], "= Terms <terms>");
+        assert_eq!(blocks["note"]["content"], "50% off \\*today\\*");
+        assert!(blocks["table"].get("source").is_none());
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let mut data = serde_json::json!({
+            "blocks": { "intro": { "format": "rst", "content": "Text" } }
+        });
+        let err = ConverterRegistry::builtin()
+            .convert_blocks(&mut data)
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown format 'rst'"));
+    }
+}
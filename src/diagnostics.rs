@@ -0,0 +1,542 @@
+//! Structured Typst diagnostics
+//!
+//! Parses the errors and warnings `typst` prints to stderr into records and
+//! traces diagnostics raised while evaluating block content (converted
+//! markdown and other formats, or raw Typst) back to the
+//! `blocks.<name>.content` value and its line in the content file.
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use typst_syntax::{LinkedNode, SyntaxKind};
+
+use crate::content::ContentFile;
+use crate::convert::{ConvertContext, ConverterRegistry, DEFAULT_FORMAT};
+use crate::schema::locate_line;
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A single Typst error or warning
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// File as named by Typst (root-relative, or `@namespace/package:version/file`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 1-based line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<String>,
+    /// Calls that led to the diagnostic, innermost first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trace: Vec<TracePoint>,
+    /// Content value the diagnostic originates from, if it could be traced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<ContentLocation>,
+    /// Source line Typst printed for the location
+    #[serde(skip)]
+    snippet: Option<String>,
+}
+
+/// One step of a diagnostic's call trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracePoint {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip)]
+    snippet: Option<String>,
+}
+
+/// Location of a content value in the content file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentLocation {
+    /// Dotted path of the value (e.g., "blocks.intro.content")
+    pub path: String,
+    /// Content file
+    pub file: PathBuf,
+    /// 1-based line in the content file (if it could be located)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic without location
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            file: None,
+            line: None,
+            column: None,
+            hints: Vec::new(),
+            trace: Vec::new(),
+            content: None,
+            snippet: None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Whether the diagnostic was raised while `md()` evaluated a string
+    fn raised_in_block_eval(&self) -> bool {
+        self.file
+            .as_deref()
+            .is_some_and(|file| file.contains("tmpltr-lib"))
+            && (self.snippet.as_deref().is_some_and(|s| s.contains("eval("))
+                || self.trace.iter().any(|t| t.message.contains("`md`")))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity.as_str(), self.message)?;
+        if let Some(content) = &self.content {
+            write!(f, "\n  --> {}", content.path)?;
+            match content.line {
+                Some(line) => write!(f, " ({}:{})", content.file.display(), line)?,
+                None => write!(f, " ({})", content.file.display())?,
+            }
+        } else if let Some(file) = &self.file {
+            write!(f, "\n  --> {}", file)?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+                if let Some(column) = self.column {
+                    write!(f, ":{}", column)?;
+                }
+            }
+        }
+        for hint in &self.hints {
+            write!(f, "\n  = hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the human-readable diagnostics printed by `typst compile`.
+///
+/// `help:` entries following an error are Typst's call trace and are attached
+/// to it; `= hint:` notes become hints.
+pub fn parse(stderr: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for raw in stderr.lines() {
+        let line = strip_ansi(raw);
+        let trimmed = line.trim();
+
+        if let Some((severity, message)) = header(&line) {
+            match severity {
+                "error" => diagnostics.push(Diagnostic::new(Severity::Error, message)),
+                "warning" => diagnostics.push(Diagnostic::new(Severity::Warning, message)),
+                _ => {
+                    if let Some(last) = diagnostics.last_mut() {
+                        last.trace.push(TracePoint {
+                            message: message.to_string(),
+                            file: None,
+                            line: None,
+                            column: None,
+                            snippet: None,
+                        });
+                    }
+                }
+            }
+            continue;
+        }
+
+        let Some(diagnostic) = diagnostics.last_mut() else {
+            continue;
+        };
+        let (file, line_no, column, snippet) = match diagnostic.trace.last_mut() {
+            Some(t) => (&mut t.file, &mut t.line, &mut t.column, &mut t.snippet),
+            None => (
+                &mut diagnostic.file,
+                &mut diagnostic.line,
+                &mut diagnostic.column,
+                &mut diagnostic.snippet,
+            ),
+        };
+
+        if let Some(location) = trimmed
+            .strip_prefix("┌─")
+            .or_else(|| trimmed.strip_prefix("-->"))
+        {
+            if file.is_none() {
+                (*file, *line_no, *column) = parse_location(location.trim());
+            }
+        } else if let Some(note) = trimmed.strip_prefix("= ") {
+            let note = note.strip_prefix("hint: ").unwrap_or(note);
+            diagnostic.hints.push(note.to_string());
+        } else if let Some((number, text)) = source_line(&line) {
+            if snippet.is_none() && *line_no == Some(number) {
+                *snippet = Some(text.to_string());
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// `error: message`, `warning[code]: message` or `help: message`
+fn header(line: &str) -> Option<(&str, &str)> {
+    let (label, message) = line.split_once(": ")?;
+    let label = label.split('[').next().unwrap_or(label);
+    matches!(label, "error" | "warning" | "help").then(|| (label, message.trim()))
+}
+
+/// `main.typ:3:7` into file, line and column
+fn parse_location(location: &str) -> (Option<String>, Option<usize>, Option<usize>) {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next().and_then(|c| c.trim().parse().ok());
+    let line = parts.next().and_then(|l| l.trim().parse().ok());
+    match (parts.next(), line, column) {
+        (Some(file), Some(line), Some(column)) => {
+            (Some(file.to_string()), Some(line), Some(column))
+        }
+        _ => (Some(location.to_string()), None, None),
+    }
+}
+
+/// A numbered source line of a snippet, e.g. `12 │ #md(...)`
+fn source_line(line: &str) -> Option<(usize, &str)> {
+    let (number, text) = line.split_once('│').or_else(|| line.split_once('|'))?;
+    let number = number.trim().parse().ok()?;
+    Some((number, text.strip_prefix(' ').unwrap_or(text)))
+}
+
+/// Remove ANSI escape sequences (colored output)
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// A block whose content Typst evaluates as markup
+struct EvaluatedBlock {
+    name: String,
+    /// Content as written in the content file
+    source: String,
+    /// Markup handed to Typst
+    typst: String,
+}
+
+/// Trace diagnostics raised inside evaluated block content back to the
+/// `blocks.<name>.content` value and line in the content file.
+///
+/// Typst reports such errors at the `eval` call in the helper library, so the
+/// block is identified from the template line in the call trace, falling back
+/// to the only block with a syntax error or mentioning the offending name.
+pub fn locate_in_content(
+    diagnostics: &mut [Diagnostic],
+    content: &ContentFile,
+    converters: &ConverterRegistry,
+) {
+    let blocks = evaluated_blocks(content, converters);
+    if blocks.is_empty() {
+        return;
+    }
+    let source = fs::read_to_string(&content.path).ok();
+
+    for diagnostic in diagnostics
+        .iter_mut()
+        .filter(|d| d.content.is_none() && d.raised_in_block_eval())
+    {
+        let Some(block) = find_block(diagnostic, &blocks) else {
+            continue;
+        };
+        let line = source
+            .as_deref()
+            .and_then(|s| content_line(s, &block.name, block_line(diagnostic, block)));
+        diagnostic.content = Some(ContentLocation {
+            path: format!("blocks.{}.content", block.name),
+            file: content.path.clone(),
+            line,
+        });
+    }
+}
+
+fn evaluated_blocks(content: &ContentFile, converters: &ConverterRegistry) -> Vec<EvaluatedBlock> {
+    let Some(blocks) = content.get("blocks").and_then(|b| b.as_table()) else {
+        return Vec::new();
+    };
+
+    blocks
+        .iter()
+        .filter_map(|(name, block)| {
+            let source = block.get("content")?.as_str()?;
+            let format = block
+                .get("format")
+                .and_then(|f| f.as_str())
+                .unwrap_or(DEFAULT_FORMAT);
+            let typst = converters
+                .get(format)?
+                .convert(source, &ConvertContext::default())
+                .ok()?;
+            Some(EvaluatedBlock {
+                name: name.clone(),
+                source: source.to_string(),
+                typst,
+            })
+        })
+        .collect()
+}
+
+fn find_block<'a>(
+    diagnostic: &Diagnostic,
+    blocks: &'a [EvaluatedBlock],
+) -> Option<&'a EvaluatedBlock> {
+    // The template line calling `md`, e.g. `#md(get(data, "blocks.intro.content"))`
+    for point in &diagnostic.trace {
+        let Some(snippet) = &point.snippet else {
+            continue;
+        };
+        for (start, _) in snippet.match_indices("blocks.") {
+            let rest = &snippet[start + "blocks.".len()..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            if let Some(block) = blocks.iter().find(|b| b.name == rest[..end]) {
+                return Some(block);
+            }
+        }
+    }
+
+    let only = |matches: Vec<&'a EvaluatedBlock>| (matches.len() == 1).then(|| matches[0]);
+
+    only(
+        blocks
+            .iter()
+            .filter(|b| first_syntax_error(&b.typst).is_some())
+            .collect(),
+    )
+    .or_else(|| {
+        let token = message_token(&diagnostic.message)?;
+        only(blocks.iter().filter(|b| b.typst.contains(token)).collect())
+    })
+    .or_else(|| only(blocks.iter().collect()))
+}
+
+/// 1-based line within the block's content
+fn block_line(diagnostic: &Diagnostic, block: &EvaluatedBlock) -> Option<usize> {
+    if let Some(offset) = first_syntax_error(&block.typst) {
+        let line = block.typst[..offset].matches('\n').count() + 1;
+        if block.source == block.typst {
+            return Some(line);
+        }
+        // Find the markdown line that produced the erroneous markup line
+        let markup = block.typst.lines().nth(line - 1)?;
+        let word = markup
+            .split(|c: char| !c.is_alphanumeric())
+            .max_by_key(|w| w.chars().count())
+            .filter(|w| !w.is_empty())?;
+        return block
+            .source
+            .lines()
+            .position(|l| l.contains(word))
+            .map(|i| i + 1);
+    }
+
+    let token = message_token(&diagnostic.message)?;
+    block
+        .source
+        .lines()
+        .position(|l| l.contains(token))
+        .map(|i| i + 1)
+}
+
+/// The name a message is about, e.g. `foo` in "unknown variable: foo"
+fn message_token(message: &str) -> Option<&str> {
+    let (_, token) = message.rsplit_once(": ")?;
+    let token = token.trim().trim_matches(|c| c == '`' || c == '"');
+    (!token.is_empty() && !token.contains(' ')).then_some(token)
+}
+
+/// Byte offset of the first syntax error in markup
+fn first_syntax_error(markup: &str) -> Option<usize> {
+    let root = typst_syntax::parse(markup);
+    if !root.erroneous() {
+        return None;
+    }
+    first_error_node(&LinkedNode::new(&root))
+}
+
+fn first_error_node(node: &LinkedNode) -> Option<usize> {
+    if node.kind() == SyntaxKind::Error {
+        return Some(node.offset());
+    }
+    node.children()
+        .filter(|child| child.erroneous())
+        .find_map(|child| first_error_node(&child))
+}
+
+/// Line in the TOML source for line `line` of `blocks.<name>.content`
+fn content_line(source: &str, name: &str, line: Option<usize>) -> Option<usize> {
+    let segments = [
+        "blocks".to_string(),
+        name.to_string(),
+        "content".to_string(),
+    ];
+    let key_line = locate_line(source, &segments)?;
+    let Some(line) = line else {
+        return Some(key_line);
+    };
+
+    // A newline right after an opening `"""` is not part of the string
+    let value = source
+        .lines()
+        .nth(key_line - 1)
+        .and_then(|l| l.split_once('='))
+        .map(|(_, v)| v.trim())
+        .unwrap_or("");
+    let first = if (value.starts_with("\"\"\"") || value.starts_with("'''")) && value.len() == 3 {
+        key_line + 1
+    } else {
+        key_line
+    };
+    Some(first + line - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STDERR: &str = "\
+warning: unknown font family: fira sans
+   ┌─ templates/quote.typ:2:18
+   │
+ 2 │ #set text(font: \"Fira Sans\")
+   │                  ^^^^^^^^^^^
+
+error: unknown variable: foo
+   ┌─ @local/tmpltr-lib:1.0.0/lib.typ:181:9
+   │
+181 │     eval(content, mode: \"markup\")
+   │          ^^^^^^^
+   │
+   = hint: if you meant to display a hash, escape it
+
+help: error occurred in this call of function `md`
+   ┌─ templates/quote.typ:14:2
+   │
+14 │ #md(get(data, \"blocks.notes.content\"))
+   │  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+";
+
+    const CONTENT: &str = r#"[meta]
+template = "quote.typ"
+
+[blocks.intro]
+content = "Hello **world**"
+
+[blocks.notes]
+title = "Notes"
+format = "typst"
+content = """
+First paragraph.
+
+Call #foo here.
+"""
+"#;
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let diagnostics = parse(STDERR);
+        assert_eq!(diagnostics.len(), 2);
+
+        let warning = &diagnostics[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.file.as_deref(), Some("templates/quote.typ"));
+        assert_eq!((warning.line, warning.column), (Some(2), Some(18)));
+
+        let error = &diagnostics[1];
+        assert!(error.is_error());
+        assert_eq!(error.message, "unknown variable: foo");
+        assert_eq!(error.line, Some(181));
+        assert_eq!(
+            error.hints,
+            vec!["if you meant to display a hash, escape it".to_string()]
+        );
+        assert_eq!(error.trace.len(), 1);
+        assert_eq!(error.trace[0].line, Some(14));
+        assert!(error.raised_in_block_eval());
+
+        let colored = "\u{1b}[31merror\u{1b}[0m: input file not found";
+        let diagnostics = parse(colored);
+        assert_eq!(diagnostics[0].message, "input file not found");
+        assert_eq!(diagnostics[0].file, None);
+    }
+
+    #[test]
+    fn test_locate_in_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quote.toml");
+        fs::write(&path, CONTENT).unwrap();
+        let content = ContentFile::load(&path).unwrap();
+
+        let mut diagnostics = parse(STDERR);
+        locate_in_content(&mut diagnostics, &content, &ConverterRegistry::builtin());
+        assert_eq!(diagnostics[0].content, None);
+        let location = diagnostics[1].content.as_ref().unwrap();
+        assert_eq!(location.path, "blocks.notes.content");
+        assert_eq!(location.line, Some(13));
+        assert!(diagnostics[1].to_string().contains("blocks.notes.content"));
+    }
+
+    #[test]
+    fn test_locate_syntax_error_without_trace() {
+        let source = CONTENT.replace("Call #foo here.", "Call #(1 + here.");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quote.toml");
+        fs::write(&path, source).unwrap();
+        let content = ContentFile::load(&path).unwrap();
+
+        let mut diagnostics = vec![Diagnostic {
+            file: Some("@local/tmpltr-lib:1.0.0/lib.typ".to_string()),
+            line: Some(181),
+            snippet: Some("    eval(content, mode: \"markup\")".to_string()),
+            ..Diagnostic::new(Severity::Error, "unclosed delimiter")
+        }];
+        locate_in_content(&mut diagnostics, &content, &ConverterRegistry::builtin());
+        let location = diagnostics[0].content.as_ref().unwrap();
+        assert_eq!(location.path, "blocks.notes.content");
+        assert_eq!(location.line, Some(13));
+    }
+}
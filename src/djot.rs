@@ -0,0 +1,845 @@
+//! Djot to Typst conversion
+//!
+//! Converts [Djot](https://djot.net) markup to Typst for embedding in
+//! templates. Covers headings, paragraphs, emphasis and the other inline
+//! spans, links, images, code, math, bullet, ordered and task lists, block
+//! quotes, divs, pipe tables and thematic breaks. Raw Typst can be written
+//! with `` `...`{=typst} `` spans and ```` ``` =typst ```` blocks.
+//!
+//! Attributes before a heading (`{#label .unnumbered}`) become a Typst label
+//! and heading options; links to `#label` point to the label.
+
+use std::collections::HashSet;
+
+use crate::convert::escape_text;
+use crate::markdown::{is_label, math_markup, string_literal, TASK_DONE, TASK_OPEN};
+
+/// Convert Djot text to Typst markup
+///
+/// Links to `#label` become links to the Typst label if it is in `labels`.
+pub fn djot_to_typst(djot: &str, labels: &HashSet<String>) -> String {
+    let lines: Vec<&str> = djot.lines().collect();
+    let converter = Converter { labels };
+    let mut output = converter.blocks(&lines, false);
+    while output.ends_with('\n') {
+        output.pop();
+    }
+    output
+}
+
+/// Labels given to headings with an attribute line (`{#label}`)
+pub fn heading_labels(djot: &str) -> HashSet<String> {
+    let lines: Vec<&str> = djot.lines().map(str::trim).collect();
+    lines
+        .windows(2)
+        .filter(|pair| heading_level(pair[1]).is_some())
+        .filter_map(|pair| attributes(pair[0])?.id)
+        .filter(|id| is_label(id))
+        .collect()
+}
+
+/// Attributes in braces, like `{#id .class key=value}`
+#[derive(Debug, Default)]
+struct Attributes {
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+/// Parse a line consisting only of an attribute block
+fn attributes(line: &str) -> Option<Attributes> {
+    let inner = line.strip_prefix('{')?.strip_suffix('}')?;
+    if inner.starts_with(['=', '+', '-']) {
+        return None;
+    }
+
+    let mut attributes = Attributes::default();
+    for part in inner.split_whitespace() {
+        if let Some(id) = part.strip_prefix('#') {
+            attributes.id = Some(id.to_string());
+        } else if let Some(class) = part.strip_prefix('.') {
+            attributes.classes.push(class.to_string());
+        } else if !part.contains('=') {
+            return None;
+        }
+    }
+    Some(attributes)
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' '))).then_some(level)
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let marks = line.chars().filter(|c| !c.is_whitespace()).count();
+    marks >= 3
+        && (line.chars().all(|c| c == '*' || c.is_whitespace())
+            || line.chars().all(|c| c == '-' || c.is_whitespace()))
+}
+
+/// Length of a fence of at least three `fence` characters, and what follows
+fn fence(line: &str, fence: char) -> Option<(usize, &str)> {
+    let len = line.len() - line.trim_start_matches(fence).len();
+    (len >= 3).then(|| (len, line[len..].trim()))
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MarkerKind {
+    Bullet(char),
+    Ordered(char),
+}
+
+/// A list item marker
+#[derive(Debug, Clone, Copy)]
+struct Marker {
+    kind: MarkerKind,
+    number: u64,
+    indent: usize,
+    /// Column where the item's content starts
+    content: usize,
+    task: Option<bool>,
+}
+
+fn list_marker(line: &str) -> Option<Marker> {
+    let indent = indent(line);
+    let rest = &line[indent..];
+    let followed_by_space = |rest: &str| rest.is_empty() || rest.starts_with(' ');
+
+    let (kind, number, width) = match rest.chars().next()? {
+        c @ ('-' | '*' | '+') if followed_by_space(&rest[1..]) => (MarkerKind::Bullet(c), 1, 1),
+        c if c.is_ascii_digit() => {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let delimiter = rest[digits..].chars().next()?;
+            if !matches!(delimiter, '.' | ')') || !followed_by_space(&rest[digits + 1..]) {
+                return None;
+            }
+            let number = rest[..digits].parse().ok()?;
+            (MarkerKind::Ordered(delimiter), number, digits + 1)
+        }
+        _ => return None,
+    };
+
+    let mut content = indent + width + 1;
+    let after = line.get(content..).unwrap_or("");
+    let task = match after.get(..4) {
+        Some("[ ] ") => Some(false),
+        Some("[x] " | "[X] ") => Some(true),
+        _ => None,
+    };
+    if task.is_some() && matches!(kind, MarkerKind::Bullet(_)) {
+        content += 4;
+    }
+
+    Some(Marker {
+        kind,
+        number,
+        indent,
+        content: content.min(line.len()),
+        task: task.filter(|_| matches!(kind, MarkerKind::Bullet(_))),
+    })
+}
+
+struct Converter<'a> {
+    labels: &'a HashSet<String>,
+}
+
+impl Converter<'_> {
+    /// Convert a sequence of block-level lines
+    ///
+    /// Inside list items a list may follow a paragraph line directly.
+    fn blocks(&self, lines: &[&str], in_item: bool) -> String {
+        let mut output = String::new();
+        let mut pending: Option<Attributes> = None;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let trimmed = lines[i].trim();
+            if trimmed.is_empty() {
+                i += 1;
+                continue;
+            }
+            if let Some(attributes) = attributes(trimmed) {
+                pending = Some(attributes);
+                i += 1;
+                continue;
+            }
+            let attributes = pending.take().unwrap_or_default();
+
+            let end = if let Some(level) = heading_level(trimmed) {
+                let end = paragraph_end(lines, i, false);
+                let text: Vec<&str> = lines[i..end]
+                    .iter()
+                    .map(|line| line.trim().trim_start_matches('#').trim())
+                    .collect();
+                output.push_str(&self.heading(level, &text.join(" "), &attributes));
+                end
+            } else if is_thematic_break(trimmed) {
+                output.push_str("#line(length: 100%)\n\n");
+                i + 1
+            } else if let Some((len, info)) = fence(trimmed, '`') {
+                let close = (i + 1..lines.len())
+                    .find(|&j| {
+                        fence(lines[j].trim(), '`')
+                            .is_some_and(|(n, rest)| n >= len && rest.is_empty())
+                    })
+                    .unwrap_or(lines.len());
+                let code = lines[i + 1..close].join("\n");
+                if info == "=typst" {
+                    output.push_str(&code);
+                } else {
+                    output.push_str(&raw_block(&code, info));
+                }
+                output.push_str("\n\n");
+                close + 1
+            } else if trimmed == ">" || trimmed.starts_with("> ") {
+                let end = paragraph_end(lines, i, false);
+                let quoted: Vec<&str> = lines[i..end]
+                    .iter()
+                    .map(|line| {
+                        let line = line.trim_start();
+                        line.strip_prefix("> ")
+                            .or_else(|| line.strip_prefix('>'))
+                            .unwrap_or(line)
+                    })
+                    .collect();
+                output.push_str("#quote(block: true)[\n");
+                output.push_str(self.blocks(&quoted, false).trim_end());
+                output.push_str("\n]\n\n");
+                end
+            } else if let Some((len, _)) = fence(trimmed, ':') {
+                let close = (i + 1..lines.len())
+                    .find(|&j| {
+                        fence(lines[j].trim(), ':')
+                            .is_some_and(|(n, rest)| n >= len && rest.is_empty())
+                    })
+                    .unwrap_or(lines.len());
+                output.push_str(&self.blocks(&lines[i + 1..close], false));
+                close + 1
+            } else if let Some(marker) = list_marker(lines[i]) {
+                self.list(lines, i, marker, &mut output)
+            } else if trimmed.starts_with('|') && table_row(trimmed).is_some() {
+                let end = (i..lines.len())
+                    .find(|&j| table_row(lines[j].trim()).is_none())
+                    .unwrap_or(lines.len());
+                output.push_str(&self.table(&lines[i..end]));
+                end
+            } else {
+                let end = paragraph_end(lines, i, in_item);
+                let text: Vec<&str> = lines[i..end].iter().map(|line| line.trim()).collect();
+                output.push_str(&self.inline(&text.join("\n")));
+                output.push_str("\n\n");
+                end
+            };
+            i = end;
+        }
+
+        output
+    }
+
+    fn heading(&self, level: usize, text: &str, attributes: &Attributes) -> String {
+        let numbered = !attributes.classes.iter().any(|c| c == "unnumbered");
+        let outlined = !attributes.classes.iter().any(|c| c == "unlisted");
+        let body = self.inline(text);
+
+        let mut heading = if numbered && outlined {
+            format!("{} {}", "=".repeat(level), body)
+        } else {
+            let mut call = format!("#heading(level: {}", level);
+            if !numbered {
+                call.push_str(", numbering: none");
+            }
+            if !outlined {
+                call.push_str(", outlined: false");
+            }
+            format!("{})[{}]", call, body)
+        };
+        if let Some(id) = attributes.id.as_deref().filter(|id| is_label(id)) {
+            heading.push_str(&format!(" <{}>", id));
+        }
+        heading.push_str("\n\n");
+        heading
+    }
+
+    /// Convert the list starting at line `start`, returning the line after it
+    fn list(&self, lines: &[&str], start: usize, first: Marker, output: &mut String) -> usize {
+        let mut items: Vec<(Marker, Vec<String>)> = Vec::new();
+        let mut tight = true;
+        let mut i = start;
+
+        while let Some(marker) = lines.get(i).and_then(|line| list_marker(line)) {
+            if marker.kind != first.kind || marker.indent != first.indent {
+                break;
+            }
+            let mut content = vec![lines[i].get(marker.content..).unwrap_or("").to_string()];
+            i += 1;
+
+            while i < lines.len() {
+                let line = lines[i];
+                if line.trim().is_empty() {
+                    // A blank line ends the item unless indented content follows
+                    let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+                    match next {
+                        Some(j) if indent(lines[j]) >= marker.content => {
+                            content.extend(std::iter::repeat_n(String::new(), j - i));
+                            i = j;
+                        }
+                        _ => break,
+                    }
+                } else if indent(line) >= marker.content {
+                    content.push(line[marker.content..].to_string());
+                    i += 1;
+                } else if list_marker(line).is_some()
+                    || content.last().is_some_and(String::is_empty)
+                {
+                    break;
+                } else {
+                    // Lazy continuation of the item's paragraph
+                    content.push(line.trim_start().to_string());
+                    i += 1;
+                }
+            }
+            items.push((marker, content));
+
+            // Items separated by blank lines make a loose list
+            let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+            match next.and_then(|j| Some((j, list_marker(lines[j])?))) {
+                Some((j, next)) if next.kind == first.kind && next.indent == first.indent => {
+                    tight &= j == i;
+                    i = j;
+                }
+                _ => break,
+            }
+        }
+
+        for (index, (marker, content)) in items.iter().enumerate() {
+            let lines: Vec<&str> = content.iter().map(String::as_str).collect();
+            let body = self.blocks(&lines, true);
+            let mut prefix = match marker.kind {
+                MarkerKind::Bullet(_) => "- ".to_string(),
+                // Typst continues numbering from an explicit number
+                MarkerKind::Ordered(_) if index == 0 && marker.number != 1 => {
+                    format!("{}. ", marker.number)
+                }
+                MarkerKind::Ordered(_) => "+ ".to_string(),
+            };
+            match marker.task {
+                Some(true) => prefix.push_str(&format!("{} ", TASK_DONE)),
+                Some(false) => prefix.push_str(&format!("{} ", TASK_OPEN)),
+                None => {}
+            }
+
+            output.push_str(&prefix);
+            let body: Vec<&str> = body.trim_end().lines().collect();
+            for (n, line) in body.iter().enumerate() {
+                // Keep a nested list of a tight list attached to its item
+                let before_list = body
+                    .get(n + 1)
+                    .is_some_and(|next| list_marker(next).is_some());
+                if line.is_empty() && tight && before_list {
+                    continue;
+                }
+                if n > 0 && !line.is_empty() {
+                    output.push_str("  ");
+                }
+                output.push_str(line);
+                output.push('\n');
+            }
+            if !tight {
+                output.push('\n');
+            }
+        }
+        if tight {
+            output.push('\n');
+        }
+        i
+    }
+
+    fn table(&self, lines: &[&str]) -> String {
+        let mut header: Option<Vec<String>> = None;
+        let mut alignments: Vec<&str> = Vec::new();
+        let mut rows: Vec<Vec<String>> = Vec::new();
+
+        for line in lines {
+            let Some(cells) = table_row(line.trim()) else {
+                continue;
+            };
+            if let Some(align) = separator_row(&cells) {
+                if let Some(previous) = rows.pop() {
+                    header = Some(previous);
+                }
+                alignments = align;
+            } else {
+                rows.push(cells.iter().map(|cell| self.inline(cell)).collect());
+            }
+        }
+
+        let columns = rows
+            .iter()
+            .chain(header.iter())
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0);
+        let cells = |row: &[String]| {
+            (0..columns)
+                .map(|n| format!("[{}]", row.get(n).map(String::as_str).unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut table = format!(
+            "#table(\n  columns: ({}),\n",
+            vec!["auto"; columns].join(", ")
+        );
+        if alignments.iter().any(|a| *a != "auto") {
+            alignments.resize(columns, "auto");
+            table.push_str(&format!("  align: ({}),\n", alignments.join(", ")));
+        }
+        if let Some(header) = header {
+            table.push_str(&format!("  table.header({}),\n", cells(&header)));
+        }
+        for row in &rows {
+            table.push_str(&format!("  {},\n", cells(row)));
+        }
+        table.push_str(")\n\n");
+        table
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let mut parser = Inline {
+            chars: text.chars().collect(),
+            pos: 0,
+            labels: self.labels,
+        };
+        parser.parse(&[]).0
+    }
+}
+
+/// Index of the first blank line after `start`, or of a list marker when
+/// lists may interrupt the paragraph
+fn paragraph_end(lines: &[&str], start: usize, lists: bool) -> usize {
+    (start + 1..lines.len())
+        .find(|&j| lines[j].trim().is_empty() || (lists && list_marker(lines[j]).is_some()))
+        .unwrap_or(lines.len())
+}
+
+/// Cells of a `| a | b |` table row
+fn table_row(line: &str) -> Option<Vec<String>> {
+    let inner = line.strip_prefix('|')?.strip_suffix('|')?;
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                cell.push(c);
+                if let Some(next) = chars.next() {
+                    cell.push(next);
+                }
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    Some(cells)
+}
+
+/// Column alignments if every cell is like `---`, `:--`, `--:` or `:-:`
+fn separator_row(cells: &[String]) -> Option<Vec<&'static str>> {
+    cells
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => "center",
+                (true, false) => "left",
+                (false, true) => "right",
+                (false, false) => "auto",
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn raw_block(code: &str, lang: &str) -> String {
+    let mut raw = format!("#raw({}, block: true", string_literal(code));
+    if !lang.is_empty() {
+        raw.push_str(&format!(", lang: {}", string_literal(lang)));
+    }
+    raw.push(')');
+    raw
+}
+
+/// Inline parser over one paragraph
+struct Inline<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    labels: &'a HashSet<String>,
+}
+
+impl Inline<'_> {
+    /// Parse until the last of `closers` closes the span, returning the
+    /// output and whether it was closed. Stops without closing when an outer
+    /// closer is reached.
+    fn parse(&mut self, closers: &[&str]) -> (String, bool) {
+        let mut output = String::new();
+        let mut text = String::new();
+
+        while self.pos < self.chars.len() {
+            if let Some((own, outer)) = closers.split_last() {
+                if self.closes(own) {
+                    flush(&mut output, &mut text);
+                    self.pos += own.chars().count();
+                    return (output, true);
+                }
+                if outer.iter().any(|closer| self.closes(closer)) {
+                    break;
+                }
+            }
+
+            let c = self.chars[self.pos];
+            let next = self.chars.get(self.pos + 1).copied();
+            match c {
+                '\\' => {
+                    self.pos += 2;
+                    match next {
+                        Some('\n') => {
+                            flush(&mut output, &mut text);
+                            output.push_str("\\\n");
+                        }
+                        Some(' ') => {
+                            flush(&mut output, &mut text);
+                            output.push('~');
+                        }
+                        Some(c) if c.is_ascii_punctuation() => text.push(c),
+                        _ => {
+                            text.push('\\');
+                            self.pos -= 1;
+                        }
+                    }
+                }
+                '`' => {
+                    flush(&mut output, &mut text);
+                    let call = self.code();
+                    output.push_str(&call);
+                }
+                '$' if self.at("$$`") || self.at("$`") => {
+                    flush(&mut output, &mut text);
+                    let display = self.at("$$");
+                    self.pos += if display { 2 } else { 1 };
+                    let latex = self.code_span();
+                    output.push_str(&math_markup(&latex, display));
+                }
+                '*' | '_' | '^' | '~' if self.opens(c) => {
+                    let call = match c {
+                        '*' => "#strong",
+                        '_' => "#emph",
+                        '^' => "#super",
+                        _ => "#sub",
+                    };
+                    self.pos += 1;
+                    self.span(&mut output, &mut text, closers, &c.to_string(), call);
+                }
+                '{' if matches!(next, Some('=' | '+' | '-')) => {
+                    let mark = next.unwrap_or('=');
+                    let call = match mark {
+                        '=' => "#highlight",
+                        '+' => "#underline",
+                        _ => "#strike",
+                    };
+                    self.pos += 2;
+                    self.span(
+                        &mut output,
+                        &mut text,
+                        closers,
+                        &format!("{}}}", mark),
+                        call,
+                    );
+                }
+                '!' if next == Some('[') => {
+                    self.pos += 2;
+                    let (alt, closed) = self.parse_plain(']');
+                    match self.destination().filter(|_| closed) {
+                        Some(src) => {
+                            flush(&mut output, &mut text);
+                            let mut call = format!("#image({}", string_literal(&src));
+                            if !alt.is_empty() {
+                                call.push_str(&format!(", alt: {}", string_literal(&alt)));
+                            }
+                            call.push(')');
+                            output.push_str(&call);
+                        }
+                        None => {
+                            text.push_str("![");
+                            text.push_str(&alt);
+                            if closed {
+                                text.push(']');
+                            }
+                        }
+                    }
+                }
+                '[' => {
+                    self.pos += 1;
+                    flush(&mut output, &mut text);
+                    let mut inner_closers = closers.to_vec();
+                    inner_closers.push("]");
+                    let (inner, closed) = self.parse(&inner_closers);
+                    match self.destination().filter(|_| closed) {
+                        Some(url) => {
+                            let target = match url.strip_prefix('#') {
+                                Some(label) if self.labels.contains(label) => {
+                                    format!("<{}>", label)
+                                }
+                                _ => string_literal(&url),
+                            };
+                            output.push_str(&format!("#link({})[{}]", target, inner));
+                        }
+                        None => {
+                            output.push_str("\\[");
+                            output.push_str(&inner);
+                            if closed {
+                                output.push_str("\\]");
+                            }
+                        }
+                    }
+                }
+                '<' => match self.autolink() {
+                    Some(url) => {
+                        flush(&mut output, &mut text);
+                        let target = if url.contains('@') && !url.contains(':') {
+                            format!("mailto:{}", url)
+                        } else {
+                            url
+                        };
+                        output.push_str(&format!("#link({})", string_literal(&target)));
+                    }
+                    None => {
+                        text.push('<');
+                        self.pos += 1;
+                    }
+                },
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        flush(&mut output, &mut text);
+        (output, closers.is_empty())
+    }
+
+    /// Parse a span opened by a delimiter just consumed
+    fn span(
+        &mut self,
+        output: &mut String,
+        text: &mut String,
+        closers: &[&str],
+        closer: &str,
+        call: &str,
+    ) {
+        let opener_len = if closer.len() > 1 { 2 } else { 1 };
+        let opener: String = self.chars[self.pos - opener_len..self.pos].iter().collect();
+        flush(output, text);
+
+        let mut inner_closers = closers.to_vec();
+        inner_closers.push(closer);
+        let (inner, closed) = self.parse(&inner_closers);
+        if closed {
+            output.push_str(&format!("{}[{}]", call, inner));
+        } else {
+            text.push_str(&opener);
+            flush(output, text);
+            output.push_str(&inner);
+        }
+    }
+
+    /// Plain text up to `close`, for image descriptions
+    fn parse_plain(&mut self, close: char) -> (String, bool) {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            self.pos += 1;
+            if c == close {
+                return (text, true);
+            }
+            text.push(c);
+        }
+        (text, false)
+    }
+
+    /// `(url)` after a link or image
+    fn destination(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'(') {
+            return None;
+        }
+        let end = self.chars[self.pos..].iter().position(|&c| c == ')')?;
+        let url: String = self.chars[self.pos + 1..self.pos + end]
+            .iter()
+            .filter(|c| **c != '\n')
+            .collect();
+        self.pos += end + 1;
+        Some(url.trim().to_string())
+    }
+
+    /// `<https://...>` or `<name@example.com>`
+    fn autolink(&mut self) -> Option<String> {
+        let end = self.chars[self.pos..].iter().position(|&c| c == '>')?;
+        let url: String = self.chars[self.pos + 1..self.pos + end].iter().collect();
+        if url.is_empty()
+            || url.contains(char::is_whitespace)
+            || !(url.contains(':') || url.contains('@'))
+        {
+            return None;
+        }
+        self.pos += end + 1;
+        Some(url)
+    }
+
+    /// A code span, with a following `{=typst}` inserting it verbatim
+    fn code(&mut self) -> String {
+        let code = self.code_span();
+        if self.at("{=typst}") {
+            self.pos += "{=typst}".len();
+            return code;
+        }
+        if code.contains('`') || code.is_empty() {
+            format!("#raw({})", string_literal(&code))
+        } else {
+            format!("`{}`", code)
+        }
+    }
+
+    /// Text of a code span starting at a backtick run
+    fn code_span(&mut self) -> String {
+        let start = self.pos;
+        while self.chars.get(self.pos) == Some(&'`') {
+            self.pos += 1;
+        }
+        let ticks = self.pos - start;
+
+        let mut code = String::new();
+        while self.pos < self.chars.len() {
+            let run = self.chars[self.pos..]
+                .iter()
+                .take_while(|&&c| c == '`')
+                .count();
+            if run == ticks {
+                self.pos += run;
+                break;
+            }
+            if run > 0 {
+                code.extend(std::iter::repeat_n('`', run));
+                self.pos += run;
+            } else {
+                code.push(self.chars[self.pos]);
+                self.pos += 1;
+            }
+        }
+
+        // One space of padding allows code starting or ending with a backtick
+        if code.starts_with(" `") || code.ends_with("` ") {
+            let trimmed = code.strip_prefix(' ').unwrap_or(&code);
+            code = trimmed.strip_suffix(' ').unwrap_or(trimmed).to_string();
+        }
+        code.replace('\n', " ")
+    }
+
+    fn at(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(n, c)| self.chars.get(self.pos + n) == Some(&c))
+    }
+
+    /// Whether the delimiter at the cursor can open a span
+    fn opens(&self, c: char) -> bool {
+        let next = self.chars.get(self.pos + 1);
+        next.is_some_and(|n| !n.is_whitespace() && *n != c)
+    }
+
+    /// Whether `closer` closes a span at the cursor
+    fn closes(&self, closer: &str) -> bool {
+        if !self.at(closer) {
+            return false;
+        }
+        match closer {
+            "*" | "_" | "^" | "~" => self.pos > 0 && !self.chars[self.pos - 1].is_whitespace(),
+            _ => true,
+        }
+    }
+}
+
+/// Append escaped text to the output
+fn flush(output: &mut String, text: &mut String) {
+    if !text.is_empty() {
+        let escaped = escape_text(output, text);
+        output.push_str(&escaped);
+        text.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(djot: &str) -> String {
+        djot_to_typst(djot, &HashSet::new())
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            convert("Some *strong* and _emphasized_ text with `code`."),
+            "Some #strong[strong] and #emph[emphasized] text with `code`."
+        );
+        assert_eq!(
+            convert("H~2~O, x^2^, {=marked=}, {+new+} and {-old-}"),
+            "H#sub[2]O, x#super[2], #highlight[marked], #underline[new] and #strike[old]"
+        );
+        assert_eq!(
+            convert("A [link](https://example.com) and <mail@example.com>"),
+            "A #link(\"https://example.com\")[link] and #link(\"mailto:mail@example.com\")"
+        );
+        assert_eq!(
+            convert("Break `#pagebreak()`{=typst} here, pay $5 * 2 #1"),
+            "Break #pagebreak() here, pay \\$5 \\* 2 \\#1"
+        );
+        assert_eq!(convert("Area $`\\pi r^2`"), "Area $pi r^2$");
+        assert_eq!(convert("Price `` `€``"), "Price #raw(\"`€\")");
+    }
+
+    #[test]
+    fn test_unclosed_delimiters() {
+        assert_eq!(convert("2 * 3 and a_b"), "2 \\* 3 and a\\_b");
+        assert_eq!(convert("*open"), "\\*open");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let djot = "{#intro .unnumbered}\n# Intro\n\n- one\n- [x] two\n\n3. three\n4. four\n\n> quoted\n\n``` rust\nfn main() {}\n```\n\n| A | B |\n|:--|--:|\n| 1 | 2 |";
+        let expected = "#heading(level: 1, numbering: none)[Intro] <intro>\n\n\
+                        - one\n- ☑ two\n\n\
+                        3. three\n+ four\n\n\
+                        #quote(block: true)[\nquoted\n]\n\n\
+                        #raw(\"fn main() {}\", block: true, lang: \"rust\")\n\n\
+                        #table(\n  columns: (auto, auto),\n  align: (left, right),\n  table.header([A], [B]),\n  [1], [2],\n)";
+        assert_eq!(convert(djot), expected);
+    }
+
+    #[test]
+    fn test_labels() {
+        let djot = "{#terms}\n## Terms\n\nSee [the terms](#terms).";
+        let labels = heading_labels(djot);
+        assert!(labels.contains("terms"));
+        assert_eq!(
+            djot_to_typst(djot, &labels),
+            "== Terms <terms>\n\nSee #link(<terms>)[the terms]."
+        );
+    }
+}
@@ -1,8 +1,12 @@
 //! Error types for tmpltr
 
 use std::path::PathBuf;
+
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::diagnostics::Diagnostic;
+
 /// Result type alias using tmpltr's Error type
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -42,6 +46,8 @@ pub enum Error {
     TypstCompilation {
         message: String,
         details: Option<String>,
+        /// Structured errors and warnings reported by Typst
+        diagnostics: Vec<Diagnostic>,
     },
 
     /// File not found
@@ -80,6 +86,17 @@ pub enum Error {
     #[error("watch error: {0}")]
     Watch(String),
 
+    /// Batch / mail-merge error
+    #[error("merge error: {0}")]
+    Merge(String),
+
+    /// Error with a structured report (e.g. validation errors) for `--json`
+    #[error("{error}")]
+    Detailed {
+        error: Box<Error>,
+        details: serde_json::Value,
+    },
+
     /// Generic error wrapper
     #[error(transparent)]
     Other(#[from] anyhow::Error),
@@ -104,7 +121,9 @@ impl Error {
             | Error::Template(_)
             | Error::Cache(_)
             | Error::NoRecentDocument
-            | Error::Watch(_) => 1,
+            | Error::Watch(_)
+            | Error::Merge(_) => 1,
+            Error::Detailed { error, .. } => error.exit_code(),
             Error::Other(_) => 10,
         }
     }
@@ -129,7 +148,122 @@ impl Error {
             Error::NoRecentDocument => "no_recent_document",
             Error::Validation(_) => "validation_error",
             Error::Watch(_) => "watch_error",
+            Error::Merge(_) => "merge_error",
+            Error::Detailed { error, .. } => error.kind(),
             Error::Other(_) => "internal_error",
         }
     }
+
+    /// Attach a structured report, reported as `details` in `--json` mode
+    pub fn with_details(self, details: serde_json::Value) -> Self {
+        Error::Detailed {
+            error: Box::new(self),
+            details,
+        }
+    }
+}
+
+/// Error envelope printed to stdout for failures in `--json` mode
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    /// Always "error"
+    pub status: String,
+    /// Error kind (see [`Error::kind`])
+    pub kind: String,
+    pub message: String,
+    /// Typst output or a command-specific report
+    pub details: Option<serde_json::Value>,
+    /// Offending content path or file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Offending block title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Block paths matching an ambiguous title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<Vec<String>>,
+    /// Structured Typst diagnostics
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    pub exit_code: i32,
+}
+
+impl ErrorReport {
+    /// Envelope for an error without a matching [`Error`] variant
+    pub fn new(kind: impl Into<String>, message: impl Into<String>, exit_code: i32) -> Self {
+        Self {
+            status: "error".to_string(),
+            kind: kind.into(),
+            message: message.into(),
+            details: None,
+            path: None,
+            title: None,
+            candidates: None,
+            diagnostics: Vec::new(),
+            exit_code,
+        }
+    }
+}
+
+impl From<&Error> for ErrorReport {
+    fn from(err: &Error) -> Self {
+        let mut report = Self::new(err.kind(), err.to_string(), err.exit_code());
+        let mut current = err;
+        if let Error::Detailed { error, details } = err {
+            report.details = Some(details.clone());
+            current = error;
+        }
+
+        match current {
+            Error::TypstCompilation {
+                details,
+                diagnostics,
+                ..
+            } => {
+                report.details = details.clone().map(serde_json::Value::String);
+                report.diagnostics = diagnostics.clone();
+            }
+            Error::PathNotFound { path } => report.path = Some(path.clone()),
+            Error::FileNotFound { path } => report.path = Some(path.display().to_string()),
+            Error::TitleNotFound { title } => report.title = Some(title.clone()),
+            Error::AmbiguousTitle { title, matches } => {
+                report.title = Some(title.clone());
+                report.candidates = Some(matches.clone());
+            }
+            _ => {}
+        }
+        report
+    }
+}
+
+impl From<Error> for ErrorReport {
+    fn from(err: Error) -> Self {
+        Self::from(&err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_report_fields() {
+        let err = Error::AmbiguousTitle {
+            title: "Intro".to_string(),
+            matches: vec!["blocks.a".to_string(), "blocks.b".to_string()],
+        };
+        let json = serde_json::to_value(ErrorReport::from(&err)).unwrap();
+        assert_eq!(json["kind"], "ambiguous_title");
+        assert_eq!(json["title"], "Intro");
+        assert_eq!(json["candidates"][1], "blocks.b");
+        assert_eq!(json["details"], serde_json::Value::Null);
+        assert_eq!(json["exit_code"], 1);
+
+        let err = Error::Validation("2 errors".to_string())
+            .with_details(serde_json::json!({"errors": ["a", "b"]}));
+        let report = ErrorReport::from(&err);
+        assert_eq!(report.kind, "validation_error");
+        assert_eq!(report.message, "validation error: 2 errors");
+        assert_eq!(report.details.unwrap()["errors"][0], "a");
+    }
 }
@@ -0,0 +1,819 @@
+//! HTML to Typst conversion
+//!
+//! Converts a safe subset of HTML to Typst for embedding in templates:
+//! paragraphs, headings, line breaks, inline formatting, links, images,
+//! code, lists, block quotes and tables. Other elements are dropped but
+//! their text is kept, except for scripts, styles and embedded content,
+//! which are removed entirely. Only `href`, `src`, `alt`, `start` and a
+//! `language-*` class on `<code>` are read; everything is escaped, so HTML
+//! cannot inject Typst code.
+
+use std::collections::HashSet;
+
+use crate::convert::escape_text;
+use crate::markdown::string_literal;
+
+/// Elements removed together with their content
+const DROPPED: &[&str] = &[
+    "script", "style", "head", "title", "template", "noscript", "iframe", "object", "embed", "svg",
+    "math", "canvas", "video", "audio", "form", "select", "textarea", "button",
+];
+
+/// Elements without content or end tag
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements separated from their surroundings like paragraphs
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "aside",
+    "header",
+    "footer",
+    "main",
+    "nav",
+    "figure",
+    "figcaption",
+    "address",
+    "details",
+    "summary",
+    "dl",
+    "dt",
+    "dd",
+];
+
+/// Convert HTML to Typst markup
+///
+/// Links to `#label` become links to the Typst label if it is in `labels`.
+pub fn html_to_typst(html: &str, labels: &HashSet<String>) -> String {
+    let mut converter = Converter {
+        labels,
+        output: String::new(),
+        saved: Vec::new(),
+        open: Vec::new(),
+    };
+    let mut tokens = Tokenizer { html, pos: 0 };
+
+    while let Some(token) = tokens.next_token() {
+        match token {
+            Token::Text(text) => converter.text(&text),
+            Token::Open { name, .. } if DROPPED.contains(&name.as_str()) => {
+                if !VOID.contains(&name.as_str()) {
+                    tokens.skip_element(&name);
+                }
+            }
+            Token::Open { name, attrs } => converter.open(&name, &attrs),
+            Token::Close(name) => converter.close(&name),
+        }
+    }
+
+    converter.finish()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text(String),
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    Close(String),
+}
+
+struct Tokenizer<'a> {
+    html: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.html[self.pos..]
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return None;
+            }
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment.find("-->").map(|i| i + 3).unwrap_or(comment.len());
+                self.pos += 4 + end;
+                continue;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                self.pos += rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+                continue;
+            }
+            if let Some(tag) = rest.strip_prefix("</") {
+                if tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                    let end = tag.find('>').unwrap_or(tag.len());
+                    let name = tag[..end].trim().to_ascii_lowercase();
+                    self.pos += 2 + (end + 1).min(tag.len());
+                    return Some(Token::Close(name));
+                }
+            }
+            if let Some(tag) = rest.strip_prefix('<') {
+                if tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                    return Some(self.open_tag());
+                }
+            }
+
+            // Text up to the next tag
+            let first = rest.chars().next().map_or(0, char::len_utf8);
+            let end = rest[first..]
+                .find('<')
+                .map(|i| i + first)
+                .unwrap_or(rest.len());
+            self.pos += end;
+            return Some(Token::Text(decode_entities(&rest[..end])));
+        }
+    }
+
+    fn open_tag(&mut self) -> Token {
+        let tag = &self.rest()[1..];
+        let name_len = tag
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(tag.len());
+        let name = tag[..name_len].to_ascii_lowercase();
+        let mut chars = tag[name_len..].char_indices().peekable();
+        let mut attrs = Vec::new();
+        let mut end = tag.len();
+
+        while let Some(&(i, c)) = chars.peek() {
+            if c == '>' {
+                end = name_len + i + 1;
+                break;
+            }
+            if c.is_whitespace() || c == '/' {
+                chars.next();
+                continue;
+            }
+
+            let mut key = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() || matches!(c, '=' | '>' | '/') {
+                    break;
+                }
+                key.push(c.to_ascii_lowercase());
+                chars.next();
+            }
+            let mut value = String::new();
+            if chars.peek().map(|&(_, c)| c) == Some('=') {
+                chars.next();
+                match chars.peek().map(|&(_, c)| c) {
+                    Some(quote @ ('"' | '\'')) => {
+                        chars.next();
+                        for (_, c) in chars.by_ref() {
+                            if c == quote {
+                                break;
+                            }
+                            value.push(c);
+                        }
+                    }
+                    _ => {
+                        while let Some(&(_, c)) = chars.peek() {
+                            if c.is_whitespace() || c == '>' {
+                                break;
+                            }
+                            value.push(c);
+                            chars.next();
+                        }
+                    }
+                }
+            }
+            attrs.push((key, decode_entities(&value)));
+        }
+
+        self.pos += 1 + end;
+        Token::Open { name, attrs }
+    }
+
+    /// Skip past the end tag of an element whose start tag was just read
+    fn skip_element(&mut self, name: &str) {
+        let mut depth = 1;
+        while let Some(token) = self.next_token() {
+            match token {
+                Token::Open { name: open, .. } if open == name => depth += 1,
+                Token::Close(close) if close == name => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Replace character references like `&amp;` and `&#8364;`
+fn decode_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let c = match entity {
+                    "amp" => '&',
+                    "lt" => '<',
+                    "gt" => '>',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "nbsp" => '\u{a0}',
+                    "ndash" => '–',
+                    "mdash" => '—',
+                    "hellip" => '…',
+                    "euro" => '€',
+                    "copy" => '©',
+                    "reg" => '®',
+                    "shy" => '\u{ad}',
+                    _ => {
+                        let code = match entity.strip_prefix('#')? {
+                            hex if hex.starts_with(['x', 'X']) => {
+                                u32::from_str_radix(&hex[1..], 16)
+                            }
+                            dec => dec.parse(),
+                        };
+                        char::from_u32(code.ok()?)?
+                    }
+                };
+                Some((c, end + 2))
+            });
+        match decoded {
+            Some((c, len)) => {
+                result.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// A table being collected
+#[derive(Default)]
+struct Table {
+    rows: Vec<Row>,
+    in_head: bool,
+}
+
+struct Row {
+    cells: Vec<String>,
+    /// Inside `<thead>`
+    in_head: bool,
+    /// Only `<th>` cells so far
+    all_th: bool,
+}
+
+impl Row {
+    fn new(in_head: bool) -> Self {
+        Self {
+            cells: Vec::new(),
+            in_head,
+            all_th: true,
+        }
+    }
+
+    fn is_header(&self) -> bool {
+        self.in_head || (self.all_th && !self.cells.is_empty())
+    }
+}
+
+enum Kind {
+    /// Closed by appending a string
+    Inline(&'static str),
+    /// Closed by appending a string and ending the paragraph
+    Block(&'static str),
+    /// `<code>` or `<pre>`, whose text is captured
+    Code {
+        block: bool,
+        lang: Option<String>,
+    },
+    List {
+        items: usize,
+    },
+    Item,
+    Table(Table),
+    TableHead,
+    TableRow,
+    Cell {
+        header: bool,
+    },
+    /// Elements without Typst equivalent, only their content is kept
+    Transparent,
+}
+
+struct Element {
+    name: String,
+    kind: Kind,
+}
+
+struct Converter<'a> {
+    labels: &'a HashSet<String>,
+    output: String,
+    /// Outer output while code or a table cell is captured
+    saved: Vec<String>,
+    open: Vec<Element>,
+}
+
+impl Converter<'_> {
+    fn open(&mut self, name: &str, attrs: &[(String, String)]) {
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+
+        // Elements implicitly closed by their successor
+        let closes_previous = match name {
+            "li" => &["li"][..],
+            "p" => &["p"],
+            "tr" => &["td", "th", "tr"],
+            "td" | "th" => &["td", "th"],
+            _ => &[],
+        };
+        while self
+            .open
+            .last()
+            .is_some_and(|e| closes_previous.contains(&e.name.as_str()))
+        {
+            self.close_top();
+        }
+
+        let in_code = self
+            .open
+            .iter()
+            .any(|e| matches!(e.kind, Kind::Code { .. }));
+        if in_code {
+            // Only the language of `<pre><code class="language-x">` matters
+            if let (
+                Some(Element {
+                    kind: Kind::Code { lang, .. },
+                    ..
+                }),
+                "code",
+            ) = (self.open.last_mut(), name)
+            {
+                if let Some(class) = attr("class") {
+                    *lang = class
+                        .split_whitespace()
+                        .find_map(|c| c.strip_prefix("language-"))
+                        .map(str::to_string);
+                }
+            }
+            if !VOID.contains(&name) {
+                self.open.push(Element {
+                    name: name.to_string(),
+                    kind: Kind::Transparent,
+                });
+            }
+            return;
+        }
+
+        let kind = match name {
+            "br" => {
+                self.trim_trailing_space();
+                self.output.push_str(" \\\n");
+                return;
+            }
+            "hr" => {
+                self.paragraph_break();
+                self.output.push_str("#line(length: 100%)\n\n");
+                return;
+            }
+            "img" => {
+                if let Some(src) = attr("src").filter(|src| safe_url(src)) {
+                    let mut call = format!("#image({}", string_literal(src));
+                    if let Some(alt) = attr("alt").filter(|alt| !alt.is_empty()) {
+                        call.push_str(&format!(", alt: {}", string_literal(alt)));
+                    }
+                    call.push(')');
+                    self.output.push_str(&call);
+                }
+                return;
+            }
+            _ if VOID.contains(&name) => return,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.paragraph_break();
+                self.output
+                    .push_str(&format!("#heading(level: {})[", &name[1..]));
+                Kind::Block("]")
+            }
+            "strong" | "b" => self.inline("#strong[", "]"),
+            "em" | "i" | "cite" | "dfn" => self.inline("#emph[", "]"),
+            "u" | "ins" => self.inline("#underline[", "]"),
+            "s" | "strike" | "del" => self.inline("#strike[", "]"),
+            "sup" => self.inline("#super[", "]"),
+            "sub" => self.inline("#sub[", "]"),
+            "mark" => self.inline("#highlight[", "]"),
+            "small" => self.inline("#text(size: 0.8em)[", "]"),
+            "q" => self.inline("#quote[", "]"),
+            "a" => match attr("href").filter(|href| safe_url(href)) {
+                Some(href) => {
+                    let target = match href.strip_prefix('#') {
+                        Some(label) if self.labels.contains(label) => format!("<{}>", label),
+                        _ => string_literal(href),
+                    };
+                    self.inline(&format!("#link({})[", target), "]")
+                }
+                None => Kind::Transparent,
+            },
+            "blockquote" => {
+                self.paragraph_break();
+                self.output.push_str("#quote(block: true)[");
+                Kind::Block("]")
+            }
+            "code" | "kbd" | "samp" | "tt" | "pre" => {
+                let block = name == "pre";
+                if block {
+                    self.paragraph_break();
+                }
+                self.begin_capture();
+                Kind::Code { block, lang: None }
+            }
+            "ul" | "ol" => {
+                self.paragraph_break();
+                if name == "ol" {
+                    self.output.push_str("#enum(");
+                    if let Some(start) = attr("start").and_then(|s| s.trim().parse::<u64>().ok()) {
+                        self.output.push_str(&format!("start: {}, ", start));
+                    }
+                } else {
+                    self.output.push_str("#list(");
+                }
+                Kind::List { items: 0 }
+            }
+            "li" => match self.open.last_mut() {
+                Some(Element {
+                    kind: Kind::List { items },
+                    ..
+                }) => {
+                    if *items > 0 {
+                        self.output.push_str(", ");
+                    }
+                    *items += 1;
+                    self.output.push('[');
+                    Kind::Item
+                }
+                _ => Kind::Transparent,
+            },
+            "table" => {
+                self.paragraph_break();
+                // Anything outside the cells, like a caption, is discarded
+                self.begin_capture();
+                Kind::Table(Table::default())
+            }
+            "thead" => {
+                self.set_table_head(true);
+                Kind::TableHead
+            }
+            "tr" => match self.table() {
+                Some(table) => {
+                    let in_head = table.in_head;
+                    table.rows.push(Row::new(in_head));
+                    Kind::TableRow
+                }
+                None => Kind::Transparent,
+            },
+            "td" | "th" if self.table().is_some() => {
+                self.begin_capture();
+                Kind::Cell {
+                    header: name == "th",
+                }
+            }
+            _ if BLOCKS.contains(&name) => {
+                self.paragraph_break();
+                Kind::Block("")
+            }
+            _ => Kind::Transparent,
+        };
+
+        self.open.push(Element {
+            name: name.to_string(),
+            kind,
+        });
+    }
+
+    fn inline(&mut self, open: &str, close: &'static str) -> Kind {
+        self.output.push_str(open);
+        Kind::Inline(close)
+    }
+
+    fn close(&mut self, name: &str) {
+        if !self.open.iter().any(|e| e.name == name) {
+            return;
+        }
+        while let Some(element) = self.open.last() {
+            let done = element.name == name;
+            self.close_top();
+            if done {
+                break;
+            }
+        }
+    }
+
+    fn close_top(&mut self) {
+        let Some(element) = self.open.pop() else {
+            return;
+        };
+
+        match element.kind {
+            Kind::Inline(close) => {
+                self.trim_trailing_space();
+                self.output.push_str(close);
+            }
+            Kind::Block(close) => {
+                if !close.is_empty() {
+                    self.output.truncate(self.output.trim_end().len());
+                }
+                self.output.push_str(close);
+                self.paragraph_break();
+            }
+            Kind::Code { block, lang } => {
+                let code = self.end_capture();
+                let mut call = if block {
+                    let code = code.strip_prefix('\n').unwrap_or(&code);
+                    format!("#raw({}, block: true", string_literal(code.trim_end()))
+                } else {
+                    format!("#raw({}", string_literal(&code))
+                };
+                if let Some(lang) = lang {
+                    call.push_str(&format!(", lang: {}", string_literal(&lang)));
+                }
+                call.push(')');
+                self.output.push_str(&call);
+                if block {
+                    self.paragraph_break();
+                }
+            }
+            Kind::List { .. } => {
+                self.output.push(')');
+                self.paragraph_break();
+            }
+            Kind::Item => {
+                self.trim_trailing_space();
+                while self.output.ends_with('\n') {
+                    self.output.pop();
+                }
+                self.output.push(']');
+            }
+            Kind::Table(table) => {
+                self.end_capture();
+                self.output.push_str(&render_table(&table));
+                self.paragraph_break();
+            }
+            Kind::TableHead => self.set_table_head(false),
+            Kind::Cell { header } => {
+                let cell = self.end_capture();
+                if let Some(table) = self.table() {
+                    if table.rows.is_empty() {
+                        let in_head = table.in_head;
+                        table.rows.push(Row::new(in_head));
+                    }
+                    if let Some(row) = table.rows.last_mut() {
+                        row.all_th &= header;
+                        row.cells.push(cell.trim().to_string());
+                    }
+                }
+            }
+            Kind::TableRow | Kind::Transparent => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let code = self.open.iter().rev().find_map(|e| match e.kind {
+            Kind::Code { block, .. } => Some(block),
+            _ => None,
+        });
+        match code {
+            Some(true) => return self.output.push_str(text),
+            Some(false) => return self.output.push_str(&collapse_whitespace(text)),
+            None => {}
+        }
+        // Whitespace between list items or table rows
+        if let Some(Kind::List { .. } | Kind::Table(_) | Kind::TableHead | Kind::TableRow) =
+            self.open.last().map(|e| &e.kind)
+        {
+            return;
+        }
+
+        let mut text = collapse_whitespace(text);
+        let at_start = self.output.is_empty()
+            || self.output.ends_with(['\n', '['])
+            || self.output.ends_with(' ');
+        if at_start {
+            text = text.trim_start().to_string();
+        }
+        if text.is_empty() {
+            return;
+        }
+        let escaped = escape_text(&self.output, &text);
+        self.output.push_str(&escaped);
+    }
+
+    fn table(&mut self) -> Option<&mut Table> {
+        self.open.iter_mut().rev().find_map(|e| match &mut e.kind {
+            Kind::Table(table) => Some(table),
+            _ => None,
+        })
+    }
+
+    fn set_table_head(&mut self, in_head: bool) {
+        if let Some(table) = self.table() {
+            table.in_head = in_head;
+        }
+    }
+
+    /// End the current paragraph, unless at the start of content
+    fn paragraph_break(&mut self) {
+        self.trim_trailing_space();
+        if self.output.is_empty()
+            || self.output.ends_with(['[', '('])
+            || self.output.ends_with(", ")
+        {
+            return;
+        }
+        while !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+
+    fn trim_trailing_space(&mut self) {
+        let trimmed = self.output.trim_end_matches(' ').len();
+        if !self.output[..trimmed].ends_with('\\') {
+            self.output.truncate(trimmed);
+        }
+    }
+
+    fn begin_capture(&mut self) {
+        self.saved.push(std::mem::take(&mut self.output));
+    }
+
+    fn end_capture(&mut self) -> String {
+        let outer = self.saved.pop().unwrap_or_default();
+        std::mem::replace(&mut self.output, outer)
+    }
+
+    fn finish(mut self) -> String {
+        while !self.open.is_empty() {
+            self.close_top();
+        }
+        self.output.trim_end().to_string()
+    }
+}
+
+fn render_table(table: &Table) -> String {
+    let columns = table.rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+    let cells = |row: &Row| {
+        (0..columns)
+            .map(|n| format!("[{}]", row.cells.get(n).map(String::as_str).unwrap_or("")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let header_rows = table.rows.iter().take_while(|row| row.is_header()).count();
+    let mut result = format!("#table(\n  columns: {},\n", columns);
+    if header_rows > 0 {
+        let header: Vec<String> = table.rows[..header_rows].iter().map(cells).collect();
+        result.push_str(&format!("  table.header({}),\n", header.join(", ")));
+    }
+    for row in &table.rows[header_rows..] {
+        result.push_str(&format!("  {},\n", cells(row)));
+    }
+    result.push_str(")\n");
+    result
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            space = true;
+        } else {
+            if space {
+                result.push(' ');
+                space = false;
+            }
+            result.push(c);
+        }
+    }
+    if space {
+        result.push(' ');
+    }
+    result
+}
+
+/// URLs without a scheme, or with one that cannot run code
+fn safe_url(url: &str) -> bool {
+    let url = url.trim();
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            matches!(
+                scheme.to_ascii_lowercase().as_str(),
+                "http" | "https" | "mailto" | "tel"
+            )
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(html: &str) -> String {
+        html_to_typst(html, &HashSet::new())
+    }
+
+    #[test]
+    fn test_inline_formatting() {
+        assert_eq!(
+            convert("<p>Some <b>bold</b>, <em>emphasis</em> and <code>x &lt; 1</code>.</p>"),
+            "Some #strong[bold], #emph[emphasis] and #raw(\"x < 1\")."
+        );
+        assert_eq!(
+            convert("<p>A <a href=\"https://example.com\">link</a><br>next line</p>"),
+            "A #link(\"https://example.com\")[link] \\\nnext line"
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        let labels = HashSet::from(["terms".to_string()]);
+        assert_eq!(
+            html_to_typst(
+                "<p>See <a href=\"#terms\">the terms</a> and <a href=\"#faq\">the FAQ</a></p>",
+                &labels
+            ),
+            "See #link(<terms>)[the terms] and #link(\"#faq\")[the FAQ]"
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let html = "<h2>Scope</h2>\n<ul>\n  <li>One</li>\n  <li>Two <i>items</i></li>\n</ul>\n<ol start=\"3\"><li>Three</ol>\n<blockquote><p>Quoted</p></blockquote>";
+        assert_eq!(
+            convert(html),
+            "#heading(level: 2)[Scope]\n\n\
+             #list([One], [Two #emph[items]])\n\n\
+             #enum(start: 3, [Three])\n\n\
+             #quote(block: true)[Quoted]"
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_text() {
+        assert_eq!(convert("Äpfel"), "Äpfel");
+        assert_eq!(convert("<p>Äpfel</p>"), "Äpfel");
+        assert_eq!(convert("<p>x</p>€ 5"), "x\n\n€ 5");
+        assert_eq!(convert("„Zitat“"), "„Zitat“");
+    }
+
+    #[test]
+    fn test_table() {
+        let html = "<table><caption>Ignored</caption><tr><th>Item</th><th>Price</th></tr>\
+                    <tr><td>Design</td><td>1.200 €</td></tr></table>";
+        assert_eq!(
+            convert(html),
+            "#table(\n  columns: 2,\n  table.header([Item], [Price]),\n  [Design], [1.200 €],\n)"
+        );
+    }
+
+    #[test]
+    fn test_unsafe_content_is_removed() {
+        let html = "<p onclick=\"x()\">Hi #let x = 1 $5</p><script>alert(1)</script>\
+                    <style>p {}</style><a href=\"javascript:alert(1)\">click</a>";
+        assert_eq!(convert(html), "Hi \\#let x = 1 \\$5\n\nclick");
+    }
+
+    #[test]
+    fn test_code_block() {
+        let html = "<pre><code class=\"language-rust\">fn main() {\n    println!(\"hi\");\n}\n</code></pre>";
+        assert_eq!(
+            convert(html),
+            "#raw(\"fn main() {\\n    println!(\\\"hi\\\");\\n}\", block: true, lang: \"rust\")"
+        );
+    }
+}
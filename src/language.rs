@@ -0,0 +1,241 @@
+//! Document language selection
+//!
+//! Brand text and content values can be localized with tables keyed by
+//! language code. Every localized value is resolved through the same
+//! [`LanguageChain`]: the document language (`--lang`, then `meta.lang`,
+//! then the brand's `default_language`), the brand's default language, the
+//! `default` entry and finally the first entry.
+
+use serde_json::Value;
+
+/// Key holding the language-neutral entry of a localized table
+pub const DEFAULT_LANGUAGE_KEY: &str = "default";
+
+/// Ordered language preferences used to pick localized values
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageChain {
+    requested: Option<String>,
+    default: Option<String>,
+    /// Further languages the document is known to use (`brand.languages`)
+    known: Vec<String>,
+}
+
+impl LanguageChain {
+    pub fn new(requested: Option<&str>, default: Option<&str>) -> Self {
+        let clean = |lang: Option<&str>| {
+            lang.map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            requested: clean(requested),
+            default: clean(default),
+            known: Vec::new(),
+        }
+    }
+
+    /// Also treat `languages` as localized-table keys
+    pub fn with_known<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.known.extend(languages.into_iter().map(Into::into));
+        self
+    }
+
+    /// Chain for prepared data: `explicit` (from `--lang`) > `meta.lang` >
+    /// `brand.default_language`, with the brand default as fallback and
+    /// `brand.languages` as known languages
+    pub fn for_data(explicit: Option<&str>, data: &Value) -> Self {
+        let meta_lang = data.pointer("/meta/lang").and_then(|v| v.as_str());
+        let brand_default = data
+            .pointer("/brand/default_language")
+            .and_then(|v| v.as_str());
+        let brand_languages = data
+            .pointer("/brand/languages")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str());
+        Self::new(explicit.or(meta_lang), brand_default).with_known(brand_languages)
+    }
+
+    /// The document language, if any is known
+    pub fn language(&self) -> Option<&str> {
+        self.requested.as_deref().or(self.default.as_deref())
+    }
+
+    /// Keys tried in order: each language, then its base language
+    /// (`de-AT` -> `de`), then the `default` entry
+    fn preferences(&self) -> Vec<&str> {
+        let mut prefs = Vec::new();
+        for lang in [self.requested.as_deref(), self.default.as_deref()]
+            .into_iter()
+            .flatten()
+        {
+            prefs.push(lang);
+            if let Some((base, _)) = lang.split_once(['-', '_']) {
+                prefs.push(base);
+            }
+        }
+        prefs.push(DEFAULT_LANGUAGE_KEY);
+        prefs
+    }
+
+    /// Choose the key to use from a localized table, falling back to the
+    /// first key when no preference matches
+    pub fn select<'a, I>(&self, keys: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let keys: Vec<&str> = keys.into_iter().collect();
+        self.preferences()
+            .into_iter()
+            .find_map(|pref| keys.iter().find(|k| k.eq_ignore_ascii_case(pref)).copied())
+            .or_else(|| keys.first().copied())
+    }
+
+    /// Replace every localized table in `value` with its resolved string
+    pub fn localize(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                if self.is_localized(map) {
+                    let key = self.select(map.keys().map(String::as_str));
+                    if let Some(text) = key.and_then(|k| map.get(k)).cloned() {
+                        *value = text;
+                    }
+                    return;
+                }
+                for child in map.values_mut() {
+                    self.localize(child);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.localize(item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// A table is localized when all keys are language codes, all values
+    /// strings and at least one key is a language of the chain, a known
+    /// language or `default`. Tables that merely have short keys, such as
+    /// `{ net = "..", vat = ".." }` or `{ bg = "#fff", fg = "#000" }`, are
+    /// left alone.
+    fn is_localized(&self, map: &serde_json::Map<String, Value>) -> bool {
+        if map.is_empty()
+            || !map.values().all(Value::is_string)
+            || !map
+                .keys()
+                .all(|k| k == DEFAULT_LANGUAGE_KEY || is_language_code(k))
+        {
+            return false;
+        }
+        let prefs = self.preferences();
+        map.keys().any(|k| {
+            prefs
+                .iter()
+                .copied()
+                .chain(self.known.iter().map(String::as_str))
+                .any(|lang| k.eq_ignore_ascii_case(lang))
+        })
+    }
+}
+
+/// Whether `code` looks like a language tag (`de`, `en-GB`, `pt_BR`)
+pub fn is_language_code(code: &str) -> bool {
+    let mut parts = code.split(['-', '_']);
+    let language = parts.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_fallbacks() {
+        let keys = ["default", "de", "en"];
+
+        let chain = LanguageChain::new(Some("de"), Some("en"));
+        assert_eq!(chain.select(keys), Some("de"));
+
+        let chain = LanguageChain::new(Some("de-AT"), Some("en"));
+        assert_eq!(chain.select(keys), Some("de"));
+
+        let chain = LanguageChain::new(Some("fr"), Some("en"));
+        assert_eq!(chain.select(keys), Some("en"));
+
+        let chain = LanguageChain::new(Some("fr"), None);
+        assert_eq!(chain.select(keys), Some("default"));
+
+        let chain = LanguageChain::new(Some("fr"), None);
+        assert_eq!(chain.select(["it", "es"]), Some("it"));
+    }
+
+    #[test]
+    fn test_for_data_precedence() {
+        let data = json!({
+            "meta": { "lang": "de" },
+            "brand": { "default_language": "en" }
+        });
+        assert_eq!(
+            LanguageChain::for_data(Some("fr"), &data).language(),
+            Some("fr")
+        );
+        assert_eq!(LanguageChain::for_data(None, &data).language(), Some("de"));
+        assert_eq!(
+            LanguageChain::for_data(None, &json!({ "brand": { "default_language": "en" } }))
+                .language(),
+            Some("en")
+        );
+        assert_eq!(LanguageChain::for_data(None, &json!({})).language(), None);
+    }
+
+    #[test]
+    fn test_localize() {
+        let mut data = json!({
+            "quote": {
+                "title": { "de": "Angebot", "en": "Quote" },
+                "client": { "id": "42" },
+                "note": { "de": "Danke" },
+                "items": [{ "name": { "default": "Beratung", "en": "Consulting" } }]
+            },
+            "brand": { "name": { "default": "Acme" } }
+        });
+        LanguageChain::new(Some("en"), Some("de")).localize(&mut data);
+
+        assert_eq!(data["quote"]["title"], "Quote");
+        assert_eq!(data["quote"]["client"], json!({ "id": "42" }));
+        assert_eq!(data["quote"]["note"], "Danke");
+        assert_eq!(data["quote"]["items"][0]["name"], "Consulting");
+        assert_eq!(data["brand"]["name"], "Acme");
+    }
+
+    #[test]
+    fn test_localize_keeps_short_key_tables() {
+        let mut data = json!({
+            "quote": {
+                "labels": { "net": "Netto", "vat": "MwSt." },
+                "contact": { "tel": "+49 30 1234", "fax": "+49 30 5678" },
+                "palette": { "bg": "#ffffff", "fg": "#0f172a" },
+                "greeting": { "fr": "Bonjour", "it": "Ciao" }
+            },
+            "brand": { "languages": ["fr", "it"] }
+        });
+        let original = data["quote"].clone();
+        LanguageChain::for_data(Some("de"), &data).localize(&mut data);
+
+        assert_eq!(data["quote"]["labels"], original["labels"]);
+        assert_eq!(data["quote"]["contact"], original["contact"]);
+        assert_eq!(data["quote"]["palette"], original["palette"]);
+        // Tables keyed by the brand's languages are still localized
+        assert_eq!(data["quote"]["greeting"], "Bonjour");
+    }
+}
@@ -3,14 +3,32 @@
 //! This library provides the core functionality for the tmpltr CLI tool,
 //! which generates professional documents from structured data using Typst templates.
 
+pub mod asciidoc;
 pub mod brand;
+pub mod build_cache;
 pub mod cache;
 pub mod cli;
+pub mod color;
+pub mod compute;
 pub mod config;
 pub mod content;
+pub mod convert;
+pub mod diagnostics;
+pub mod djot;
 pub mod error;
+pub mod html;
+pub mod language;
+pub mod locale;
 pub mod markdown;
+pub mod math;
+pub mod merge;
+pub mod migrate;
+pub mod output;
+pub mod schema;
+pub mod serve;
+pub mod syntax;
 pub mod template;
 pub mod typst;
+pub mod watch;
 
 pub use error::{Error, Result};
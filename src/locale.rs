@@ -0,0 +1,453 @@
+//! Locale-aware formatting
+//!
+//! Numbers, currency amounts and dates are formatted on the Rust side so that
+//! templates don't have to. The locale comes from `meta.locale`, `meta.lang`
+//! or the brand's `default_language`. Typed template fields get a
+//! `<field>_formatted` sibling in the prepared data, and the locale's
+//! conventions are passed as `data._tmpltr.locale` for the `fmt-*` helpers
+//! in `tmpltr-lib.typ`, so a content file's own `[locale]` table is left
+//! alone.
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::template::{EditableField, FieldType};
+
+/// Locale used when the document doesn't name one
+pub const DEFAULT_LOCALE: Locale = Locale::EnUs;
+
+/// Supported locales
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    /// German (`de`, `de-DE`, `de-AT`, ...)
+    De,
+    /// American English (`en`, `en-US`)
+    EnUs,
+    /// British English (`en-GB`)
+    EnGb,
+    /// French (`fr`, `fr-FR`, ...)
+    Fr,
+}
+
+/// Formatting rules of a locale
+#[derive(Debug, Clone, Copy)]
+struct Conventions {
+    decimal: &'static str,
+    group: &'static str,
+    /// Currency symbol before the amount
+    symbol_first: bool,
+    months: [&'static str; 12],
+    /// Long date pattern with `{day}`, `{month}` and `{year}`
+    date: &'static str,
+    /// Replacement for day 1 (French "1er")
+    first_day: Option<&'static str>,
+    currency: &'static str,
+}
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Currency symbols; other codes are written as-is
+const SYMBOLS: &[(&str, &str)] = &[("EUR", "€"), ("USD", "$"), ("GBP", "£"), ("JPY", "¥")];
+
+/// Currencies without two minor unit digits
+const MINOR_UNITS: &[(&str, u32)] = &[
+    ("JPY", 0),
+    ("KRW", 0),
+    ("ISK", 0),
+    ("CLP", 0),
+    ("VND", 0),
+    ("BHD", 3),
+    ("KWD", 3),
+    ("OMR", 3),
+    ("JOD", 3),
+    ("TND", 3),
+];
+
+/// No-break space between amount and currency
+const NBSP: &str = "\u{a0}";
+
+impl Locale {
+    /// Parse a language tag such as `de`, `de-AT`, `en_GB` or `fr-CA`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().replace('_', "-").to_lowercase();
+        let (language, region) = tag.split_once('-').unwrap_or((&tag, ""));
+        match (language, region) {
+            ("de", _) => Some(Locale::De),
+            ("en", "gb" | "uk" | "ie") => Some(Locale::EnGb),
+            ("en", _) => Some(Locale::EnUs),
+            ("fr", _) => Some(Locale::Fr),
+            _ => None,
+        }
+    }
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::De => "de",
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            Locale::Fr => "fr",
+        }
+    }
+
+    /// Locale of prepared document data
+    ///
+    /// Uses `meta.locale`, then `meta.lang`, then `brand.default_language`;
+    /// unsupported tags fall through to the next source.
+    pub fn for_data(data: &serde_json::Value) -> Self {
+        let candidates = [
+            data.pointer("/meta/locale"),
+            data.pointer("/meta/lang"),
+            data.pointer("/brand/default_language"),
+        ];
+        for tag in candidates.into_iter().flatten().filter_map(|v| v.as_str()) {
+            match Self::from_tag(tag) {
+                Some(locale) => return locale,
+                None => log::warn!("unsupported locale '{}', trying fallbacks", tag),
+            }
+        }
+        DEFAULT_LOCALE
+    }
+
+    fn conventions(&self) -> Conventions {
+        match self {
+            Locale::De => Conventions {
+                decimal: ",",
+                group: ".",
+                symbol_first: false,
+                months: [
+                    "Januar",
+                    "Februar",
+                    "März",
+                    "April",
+                    "Mai",
+                    "Juni",
+                    "Juli",
+                    "August",
+                    "September",
+                    "Oktober",
+                    "November",
+                    "Dezember",
+                ],
+                date: "{day}. {month} {year}",
+                first_day: None,
+                currency: "EUR",
+            },
+            Locale::EnUs => Conventions {
+                decimal: ".",
+                group: ",",
+                symbol_first: true,
+                months: ENGLISH_MONTHS,
+                date: "{month} {day}, {year}",
+                first_day: None,
+                currency: "USD",
+            },
+            Locale::EnGb => Conventions {
+                decimal: ".",
+                group: ",",
+                symbol_first: true,
+                months: ENGLISH_MONTHS,
+                date: "{day} {month} {year}",
+                first_day: None,
+                currency: "GBP",
+            },
+            Locale::Fr => Conventions {
+                decimal: ",",
+                // Narrow no-break space
+                group: "\u{202f}",
+                symbol_first: false,
+                months: [
+                    "janvier",
+                    "février",
+                    "mars",
+                    "avril",
+                    "mai",
+                    "juin",
+                    "juillet",
+                    "août",
+                    "septembre",
+                    "octobre",
+                    "novembre",
+                    "décembre",
+                ],
+                date: "{day} {month} {year}",
+                first_day: Some("1er"),
+                currency: "EUR",
+            },
+        }
+    }
+
+    /// Currency used when neither the field nor `meta.currency` names one
+    pub fn default_currency(&self) -> &'static str {
+        self.conventions().currency
+    }
+
+    /// Format a number with grouping; `decimals` fixes the fraction digits
+    pub fn format_number(&self, value: f64, decimals: Option<usize>) -> String {
+        let conventions = self.conventions();
+        let digits = match decimals {
+            Some(decimals) => format!("{:.*}", decimals, value.abs()),
+            None => value.abs().to_string(),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+        let mut grouped = String::new();
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push_str(conventions.group);
+            }
+            grouped.push(c);
+        }
+        if !fraction.is_empty() {
+            grouped.push_str(conventions.decimal);
+            grouped.push_str(fraction);
+        }
+
+        let negative = value < 0.0 && grouped.chars().any(|c| c.is_ascii_digit() && c != '0');
+        if negative {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        }
+    }
+
+    /// Format an amount in `currency` (an ISO 4217 code)
+    pub fn format_currency(&self, value: f64, currency: &str) -> String {
+        let conventions = self.conventions();
+        let code = currency.to_uppercase();
+        let symbol = SYMBOLS
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, s)| *s)
+            .unwrap_or(&code);
+        let amount = self.format_number(value.abs(), Some(currency_decimals(&code) as usize));
+        let sign = if value < 0.0 && amount.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            "-"
+        } else {
+            ""
+        };
+
+        if !conventions.symbol_first {
+            format!("{}{}{}{}", sign, amount, NBSP, symbol)
+        } else if symbol == code {
+            format!("{}{}{}{}", sign, symbol, NBSP, amount)
+        } else {
+            format!("{}{}{}", sign, symbol, amount)
+        }
+    }
+
+    /// Format a date in the locale's long form ("17. Oktober 2026")
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        let conventions = self.conventions();
+        let day = match (date.day(), conventions.first_day) {
+            (1, Some(first)) => first.to_string(),
+            (day, _) => day.to_string(),
+        };
+        conventions
+            .date
+            .replace("{day}", &day)
+            .replace("{month}", conventions.months[date.month0() as usize])
+            .replace("{year}", &date.year().to_string())
+    }
+
+    /// Conventions as passed to Typst (`data._tmpltr.locale`)
+    pub fn to_json(&self, currency: &str) -> serde_json::Value {
+        let conventions = self.conventions();
+        serde_json::json!({
+            "tag": self.tag(),
+            "decimal": conventions.decimal,
+            "group": conventions.group,
+            "currency": currency,
+            "symbols": SYMBOLS
+                .iter()
+                .map(|(code, symbol)| (code.to_string(), serde_json::Value::from(*symbol)))
+                .collect::<serde_json::Map<_, _>>(),
+            "minor_units": MINOR_UNITS
+                .iter()
+                .map(|(code, digits)| (code.to_string(), serde_json::Value::from(*digits)))
+                .collect::<serde_json::Map<_, _>>(),
+            "symbol_first": conventions.symbol_first,
+            "months": conventions.months,
+            "date": conventions.date,
+            "first_day": conventions.first_day
+        })
+    }
+}
+
+/// Minor unit digits of an ISO 4217 currency
+pub fn currency_decimals(code: &str) -> u32 {
+    let code = code.to_ascii_uppercase();
+    MINOR_UNITS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, digits)| *digits)
+        .unwrap_or(2)
+}
+
+/// Parse a date value ("2026-10-17", or a datetime starting with one)
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let date = value.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Top-level key for data tmpltr adds for `tmpltr-lib.typ`
+pub const INTERNAL_KEY: &str = "_tmpltr";
+
+/// Document currency: `meta.currency` or the locale's default
+pub fn currency_for_data(data: &serde_json::Value, locale: Locale) -> String {
+    data.pointer("/meta/currency")
+        .and_then(|v| v.as_str())
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| locale.default_currency().to_string())
+}
+
+/// Add `<field>_formatted` for number, integer, currency and date fields,
+/// and the locale conventions as `data._tmpltr.locale`
+pub fn add_formatted(data: &mut serde_json::Value, fields: &[EditableField]) {
+    let locale = Locale::for_data(data);
+    let currency = currency_for_data(data, locale);
+
+    for field in fields {
+        let pointer = format!("/{}", field.path.replace('.', "/"));
+        let Some(value) = data.pointer(&pointer) else {
+            continue;
+        };
+        let formatted = match (field.field_type, value) {
+            (FieldType::Currency, serde_json::Value::Number(n)) => {
+                n.as_f64().map(|n| locale.format_currency(n, &currency))
+            }
+            (FieldType::Number, serde_json::Value::Number(n)) => {
+                n.as_f64().map(|n| locale.format_number(n, None))
+            }
+            (FieldType::Integer, serde_json::Value::Number(n)) => {
+                n.as_f64().map(|n| locale.format_number(n, Some(0)))
+            }
+            (FieldType::Date, serde_json::Value::String(s)) => {
+                parse_date(s).map(|d| locale.format_date(d))
+            }
+            _ => None,
+        };
+        let Some(formatted) = formatted else {
+            continue;
+        };
+
+        let (parent, key) = match pointer.rsplit_once('/') {
+            Some((parent, key)) => (parent, key),
+            None => continue,
+        };
+        if let Some(parent) = data.pointer_mut(parent).and_then(|v| v.as_object_mut()) {
+            parent.insert(
+                format!("{}_formatted", key),
+                serde_json::Value::String(formatted),
+            );
+        }
+    }
+
+    if let Some(root) = data.as_object_mut() {
+        let internal = root
+            .entry(INTERNAL_KEY)
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let Some(internal) = internal.as_object_mut() {
+            internal.insert("locale".to_string(), locale.to_json(&currency));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::from_tag("en"), Some(Locale::EnUs));
+        assert_eq!(Locale::from_tag("en_GB"), Some(Locale::EnGb));
+        assert_eq!(Locale::from_tag("fr-CA"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("pt-BR"), None);
+    }
+
+    #[test]
+    fn test_german() {
+        let de = Locale::De;
+        assert_eq!(de.format_number(1234.56, Some(2)), "1.234,56");
+        assert_eq!(de.format_number(1234567.0, None), "1.234.567");
+        assert_eq!(de.format_currency(1234.56, "EUR"), "1.234,56\u{a0}€");
+        assert_eq!(de.format_currency(-5.0, "CHF"), "-5,00\u{a0}CHF");
+        assert_eq!(de.format_date(date(2026, 10, 17)), "17. Oktober 2026");
+        assert_eq!(de.format_date(date(2026, 3, 1)), "1. März 2026");
+    }
+
+    #[test]
+    fn test_american_english() {
+        let us = Locale::EnUs;
+        assert_eq!(us.format_number(1234.5, None), "1,234.5");
+        assert_eq!(us.format_currency(1234.56, "USD"), "$1,234.56");
+        assert_eq!(us.format_currency(-1234.5, "EUR"), "-€1,234.50");
+        assert_eq!(us.format_currency(1000.0, "CHF"), "CHF\u{a0}1,000.00");
+        assert_eq!(us.format_date(date(2026, 10, 17)), "October 17, 2026");
+    }
+
+    #[test]
+    fn test_british_english() {
+        let gb = Locale::EnGb;
+        assert_eq!(gb.format_currency(999.999, "GBP"), "£1,000.00");
+        assert_eq!(gb.format_currency(1500.0, "JPY"), "¥1,500");
+        assert_eq!(gb.format_date(date(2026, 10, 17)), "17 October 2026");
+    }
+
+    #[test]
+    fn test_french() {
+        let fr = Locale::Fr;
+        assert_eq!(fr.format_number(1234.56, Some(2)), "1\u{202f}234,56");
+        assert_eq!(fr.format_currency(1234.56, "EUR"), "1\u{202f}234,56\u{a0}€");
+        assert_eq!(fr.format_number(-0.001, Some(2)), "0,00");
+        assert_eq!(fr.format_date(date(2026, 10, 17)), "17 octobre 2026");
+        assert_eq!(fr.format_date(date(2026, 8, 1)), "1er août 2026");
+    }
+
+    #[test]
+    fn test_add_formatted() {
+        let template = crate::template::TemplateInfo::parse_content(
+            "t.typ".into(),
+            r#"
+#editable("quote.total", x, type: "currency")
+#editable("quote.date", x, type: "date")
+#editable("quote.count", x, type: "integer")
+#editable("quote.title", x)
+"#,
+        )
+        .unwrap();
+        let mut data = serde_json::json!({
+            "meta": { "lang": "xx", "currency": "chf" },
+            "brand": { "default_language": "de" },
+            "quote": { "total": 1234.5, "date": "2026-10-17", "count": 12000, "title": "X" },
+            "locale": { "office": "Berlin" }
+        });
+        add_formatted(&mut data, &template.fields);
+
+        let quote = &data["quote"];
+        assert_eq!(quote["total_formatted"], "1.234,50\u{a0}CHF");
+        assert_eq!(quote["date_formatted"], "17. Oktober 2026");
+        assert_eq!(quote["count_formatted"], "12.000");
+        assert!(quote.get("title_formatted").is_none());
+        assert_eq!(data["_tmpltr"]["locale"]["tag"], "de");
+        assert_eq!(data["_tmpltr"]["locale"]["currency"], "CHF");
+        // A content `[locale]` table is not clobbered
+        assert_eq!(data["locale"], serde_json::json!({ "office": "Berlin" }));
+    }
+}
@@ -8,27 +8,65 @@ use env_logger::fmt::WriteStyle;
 use log::LevelFilter;
 
 use tmpltr::cli::commands::{
-    handle_add, handle_blocks, handle_brands, handle_compile, handle_config, handle_example,
-    handle_get, handle_init, handle_new, handle_new_template, handle_recent, handle_set,
-    handle_templates, handle_validate, handle_watch, Context,
+    handle_add, handle_blocks, handle_brands, handle_cache, handle_compile, handle_config,
+    handle_example, handle_get, handle_init, handle_merge, handle_new, handle_new_template,
+    handle_recent, handle_set, handle_templates, handle_validate, handle_watch, Context,
 };
 use tmpltr::cli::{Cli, ColorOption, Command};
-use tmpltr::error::Error;
+use tmpltr::error::{Error, ErrorReport};
 
 fn main() -> ExitCode {
-    match run() {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => return usage_error(err),
+    };
+    let json = cli.common.json;
+
+    match run(cli) {
         Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
             let code = err.exit_code();
-            let _ = writeln!(io::stderr(), "error: {}", err);
+            if json {
+                print_report(&ErrorReport::from(&err));
+            } else {
+                let _ = writeln!(io::stderr(), "error: {}", err);
+            }
             ExitCode::from(code as u8)
         }
     }
 }
 
-fn run() -> Result<(), Error> {
-    let cli = Cli::parse();
+/// Report invalid arguments; as a JSON envelope when `--json` was given
+fn usage_error(err: clap::Error) -> ExitCode {
+    let wants_json = std::env::args_os().any(|arg| arg == "--json");
+    if !wants_json || !err.use_stderr() {
+        err.exit();
+    }
+
+    let code = err.exit_code();
+    let rendered = err.render().to_string();
+    let message = rendered
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("error: ")
+        .to_string();
+    let mut report = ErrorReport::new("usage_error", message, code);
+    report.details = Some(rendered.trim_end().into());
+    print_report(&report);
+    ExitCode::from(code as u8)
+}
+
+fn print_report(report: &ErrorReport) {
+    match serde_json::to_string_pretty(report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            let _ = writeln!(io::stderr(), "error: {}", e);
+        }
+    }
+}
 
+fn run(cli: Cli) -> Result<(), Error> {
     // Initialize logging
     init_logging(&cli)?;
 
@@ -41,6 +79,7 @@ fn run() -> Result<(), Error> {
         Command::New(args) => handle_new(&ctx, args),
         Command::Example(args) => handle_example(&ctx, args),
         Command::Compile(args) => handle_compile(&mut ctx, args),
+        Command::Merge(args) => handle_merge(&ctx, args),
         Command::Get(args) => handle_get(&mut ctx, args),
         Command::Set(args) => handle_set(&mut ctx, args),
         Command::Blocks(args) => handle_blocks(&mut ctx, args),
@@ -51,6 +90,7 @@ fn run() -> Result<(), Error> {
         Command::Brands { command } => handle_brands(&ctx, command),
         Command::Add { command } => handle_add(&ctx, command),
         Command::Config { command } => handle_config(&ctx, command),
+        Command::Cache { command } => handle_cache(&ctx, command),
         Command::NewTemplate(args) => handle_new_template(&ctx, args),
         Command::Completions { shell } => {
             let mut cmd = Cli::command();
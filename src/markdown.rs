@@ -1,19 +1,50 @@
 //! Markdown to Typst conversion
 //!
 //! Converts Markdown content to Typst markup for embedding in templates.
+//! Covers CommonMark and the GFM extensions (tables, task lists,
+//! strikethrough and footnotes) and `$...$` / `$$...$$` LaTeX math, which
+//! is translated to Typst math where possible.
+//!
+//! Typst can be written directly with ```` ```typst ```` fences and inline
+//! `` `...`{=typst} `` spans. Heading attributes (`{#label .unnumbered}`)
+//! become labels, and `@label` references to them are kept as references.
+
+use std::collections::{HashMap, HashSet};
 
-use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, LinkType, Options, Parser, Tag, TagEnd};
 
 use crate::error::Result;
+use crate::math::latex_to_typst;
+
+/// Glyph for an open task list item (`- [ ]`)
+pub(crate) const TASK_OPEN: &str = "☐";
+
+/// Glyph for a completed task list item (`- [x]`)
+pub(crate) const TASK_DONE: &str = "☑";
+
+/// Delimiters of a footnote reference until all definitions are known
+const FOOTNOTE_START: char = '\u{E000}';
+const FOOTNOTE_END: char = '\u{E001}';
+
+/// Info strings of fenced code blocks that are inserted as raw Typst
+const RAW_TYPST: &[&str] = &["typst", "{=typst}"];
+
+/// Suffix marking an inline code span as raw Typst
+const RAW_TYPST_SPAN: &str = "{=typst}";
 
 /// Convert Markdown text to Typst markup
+///
+/// `@label` references resolve to the heading labels of this text only; use
+/// [`markdown_to_typst_with_labels`] to reference headings of other blocks.
 pub fn markdown_to_typst(markdown: &str) -> Result<String> {
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TABLES);
+    markdown_to_typst_with_labels(markdown, &heading_labels(markdown))
+}
 
-    let parser = Parser::new_ext(markdown, options);
-    let mut converter = TypstConverter::new();
+/// Convert Markdown text to Typst markup, turning `@label` into a reference
+/// for every label in `labels`
+pub fn markdown_to_typst_with_labels(markdown: &str, labels: &HashSet<String>) -> Result<String> {
+    let parser = Parser::new_ext(markdown, options());
+    let mut converter = TypstConverter::new(labels);
 
     for event in parser {
         converter.process_event(event);
@@ -22,34 +53,116 @@ pub fn markdown_to_typst(markdown: &str) -> Result<String> {
     Ok(converter.finish())
 }
 
+/// Labels declared with heading attributes (`# Title {#label}`)
+pub fn heading_labels(markdown: &str) -> HashSet<String> {
+    Parser::new_ext(markdown, options())
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) if is_label(&id) => {
+                Some(id.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_MATH);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options
+}
+
+/// An open list
+struct List {
+    /// Start number for ordered lists, `None` for bullet lists
+    start: Option<u64>,
+    /// No item has been written yet
+    first: bool,
+}
+
+/// An open image, whose alt text is being captured
+struct Image {
+    url: String,
+    title: String,
+}
+
+/// An open heading
+struct Heading {
+    label: Option<String>,
+    /// Written as a `#heading(...)[...]` call rather than `= ...`
+    call: bool,
+}
+
 /// Converter state machine
-struct TypstConverter {
+struct TypstConverter<'a> {
     output: String,
-    list_depth: usize,
-    in_code_block: bool,
-    in_table: bool,
-    table_alignments: Vec<pulldown_cmark::Alignment>,
+    /// Open lists, innermost last
+    lists: Vec<List>,
+    /// Outer output while a code block, image or footnote is captured
+    saved: Vec<String>,
+    /// Language of the open code block (empty if none was given)
+    code_lang: Option<String>,
+    images: Vec<Image>,
+    footnote_label: Option<String>,
+    footnotes: HashMap<String, String>,
+    in_table_head: bool,
     table_cell_index: usize,
+    heading: Option<Heading>,
+    /// Labels that `@label` may refer to
+    labels: &'a HashSet<String>,
+    /// Output length before the last inline code span, and its code
+    last_code: Option<(usize, String)>,
 }
 
-impl TypstConverter {
-    fn new() -> Self {
+impl<'a> TypstConverter<'a> {
+    fn new(labels: &'a HashSet<String>) -> Self {
         Self {
             output: String::new(),
-            list_depth: 0,
-            in_code_block: false,
-            in_table: false,
-            table_alignments: Vec::new(),
+            lists: Vec::new(),
+            saved: Vec::new(),
+            code_lang: None,
+            images: Vec::new(),
+            footnote_label: None,
+            footnotes: HashMap::new(),
+            in_table_head: false,
             table_cell_index: 0,
+            heading: None,
+            labels,
+            last_code: None,
         }
     }
 
     fn process_event(&mut self, event: Event) {
+        let last_code = self.last_code.take();
         match event {
             Event::Start(tag) => self.start_tag(tag),
             Event::End(tag) => self.end_tag(tag),
+            // `` `#pagebreak()`{=typst} `` arrives as code followed by text
+            Event::Text(text) if last_code.is_some() && text.starts_with(RAW_TYPST_SPAN) => {
+                if let Some((start, code)) = last_code {
+                    self.output.truncate(start);
+                    self.output.push_str(&code);
+                }
+                self.text(&text[RAW_TYPST_SPAN.len()..]);
+            }
             Event::Text(text) => self.text(&text),
             Event::Code(code) => self.inline_code(&code),
+            Event::InlineMath(latex) => self.math(&latex, false),
+            Event::DisplayMath(latex) => self.math(&latex, true),
+            Event::FootnoteReference(label) => {
+                self.output.push(FOOTNOTE_START);
+                self.output.push_str(&label);
+                self.output.push(FOOTNOTE_END);
+            }
+            Event::TaskListMarker(done) => {
+                self.output
+                    .push_str(if done { TASK_DONE } else { TASK_OPEN });
+                self.output.push(' ');
+            }
             Event::SoftBreak => self.soft_break(),
             Event::HardBreak => self.hard_break(),
             Event::Rule => self.rule(),
@@ -58,34 +171,81 @@ impl TypstConverter {
     }
 
     fn start_tag(&mut self, tag: Tag) {
+        // Inside an image only the plain alt text is kept
+        if !self.images.is_empty() && !matches!(tag, Tag::Image { .. }) {
+            return;
+        }
+
         match tag {
-            Tag::Paragraph => {}
-            Tag::Heading { level, .. } => {
-                let marker = "=".repeat(level as usize);
-                self.output.push_str(&marker);
-                self.output.push(' ');
+            // Further paragraphs of a list item are indented below it
+            Tag::Paragraph if !self.lists.is_empty() && self.output.ends_with('\n') => {
+                self.output.push('\n');
+                self.output.push_str(&self.indent());
             }
-            Tag::BlockQuote(_) => {
-                self.output.push_str("#quote[\n");
+            Tag::Heading {
+                level, id, classes, ..
+            } => {
+                self.ensure_newline();
+                let numbered = !classes.iter().any(|class| &**class == "unnumbered");
+                let outlined = !classes.iter().any(|class| &**class == "unlisted");
+                if numbered && outlined {
+                    self.output.push_str(&"=".repeat(level as usize));
+                    self.output.push(' ');
+                } else {
+                    self.output
+                        .push_str(&format!("#heading(level: {}", level as usize));
+                    if !numbered {
+                        self.output.push_str(", numbering: none");
+                    }
+                    if !outlined {
+                        self.output.push_str(", outlined: false");
+                    }
+                    self.output.push_str(")[");
+                }
+                self.heading = Some(Heading {
+                    label: id.filter(|id| is_label(id)).map(|id| id.to_string()),
+                    call: !(numbered && outlined),
+                });
             }
-            Tag::CodeBlock(_) => {
-                self.in_code_block = true;
-                self.output.push_str("```\n");
+            Tag::BlockQuote(_) => {
+                self.ensure_newline();
+                self.output.push_str("#quote(block: true)[\n");
             }
-            Tag::List(Some(start)) => {
-                self.list_depth += 1;
-                if start != 1 {
-                    // Typst doesn't support custom start numbers directly
-                    // Could emit a comment or use enum with start
-                }
+            Tag::CodeBlock(kind) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_lang = Some(lang);
+                self.begin_capture();
             }
-            Tag::List(None) => {
-                self.list_depth += 1;
+            Tag::List(start) => {
+                self.ensure_newline();
+                self.lists.push(List { start, first: true });
             }
             Tag::Item => {
-                let indent = "  ".repeat(self.list_depth.saturating_sub(1));
+                self.ensure_newline();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
                 self.output.push_str(&indent);
-                self.output.push_str("- ");
+                let marker = match self.lists.last_mut() {
+                    // Typst continues numbering from an explicit number
+                    Some(List {
+                        start: Some(start),
+                        first,
+                    }) if *first && *start != 1 => format!("{}. ", start),
+                    Some(List { start: Some(_), .. }) => "+ ".to_string(),
+                    _ => "- ".to_string(),
+                };
+                self.output.push_str(&marker);
+                if let Some(list) = self.lists.last_mut() {
+                    list.first = false;
+                }
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.footnote_label = Some(label.to_string());
+                self.begin_capture();
             }
             Tag::Emphasis => {
                 self.output.push('_');
@@ -96,64 +256,110 @@ impl TypstConverter {
             Tag::Strikethrough => {
                 self.output.push_str("#strike[");
             }
-            Tag::Link { dest_url, .. } => {
-                self.output.push_str("#link(\"");
-                self.output.push_str(&dest_url);
-                self.output.push_str("\")[");
+            Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            } => {
+                let url = match link_type {
+                    LinkType::Email => format!("mailto:{}", dest_url),
+                    _ => dest_url.to_string(),
+                };
+                self.output.push_str("#link(");
+                self.output.push_str(&string_literal(&url));
+                self.output.push_str(")[");
             }
-            Tag::Image { dest_url, .. } => {
-                self.output.push_str("#image(\"");
-                self.output.push_str(&dest_url);
-                self.output.push_str("\")");
+            Tag::Image {
+                dest_url, title, ..
+            } => {
+                self.images.push(Image {
+                    url: dest_url.to_string(),
+                    title: title.to_string(),
+                });
+                self.begin_capture();
             }
             Tag::Table(alignments) => {
-                self.in_table = true;
-                self.table_alignments = alignments;
+                self.ensure_newline();
+                let columns = vec!["auto"; alignments.len()].join(", ");
                 self.output.push_str("#table(\n  columns: (");
-                for (i, _) in self.table_alignments.iter().enumerate() {
-                    if i > 0 {
-                        self.output.push_str(", ");
-                    }
-                    self.output.push_str("auto");
-                }
+                self.output.push_str(&columns);
                 self.output.push_str("),\n");
+                if alignments.iter().any(|a| *a != Alignment::None) {
+                    let align: Vec<&str> = alignments.iter().map(alignment).collect();
+                    self.output.push_str("  align: (");
+                    self.output.push_str(&align.join(", "));
+                    self.output.push_str("),\n");
+                }
             }
             Tag::TableHead => {
+                self.in_table_head = true;
                 self.table_cell_index = 0;
+                self.output.push_str("  table.header(");
             }
             Tag::TableRow => {
                 self.table_cell_index = 0;
+                self.output.push_str("  ");
             }
             Tag::TableCell => {
-                self.output.push_str("  [");
+                if self.table_cell_index > 0 {
+                    self.output.push_str(", ");
+                }
+                self.output.push('[');
             }
             _ => {}
         }
     }
 
     fn end_tag(&mut self, tag: TagEnd) {
+        if !self.images.is_empty() && tag != TagEnd::Image {
+            return;
+        }
+
         match tag {
             TagEnd::Paragraph => {
-                self.output.push_str("\n\n");
+                if self.lists.is_empty() {
+                    self.output.push_str("\n\n");
+                } else {
+                    self.output.push('\n');
+                }
             }
             TagEnd::Heading(_) => {
+                if let Some(heading) = self.heading.take() {
+                    if heading.call {
+                        self.output.push(']');
+                    }
+                    if let Some(label) = heading.label {
+                        self.output.push_str(&format!(" <{}>", label));
+                    }
+                }
                 self.output.push('\n');
             }
             TagEnd::BlockQuote(_) => {
                 self.output.push_str("]\n");
             }
             TagEnd::CodeBlock => {
-                self.in_code_block = false;
-                self.output.push_str("```\n");
+                let code = self.end_capture();
+                let lang = self.code_lang.take().unwrap_or_default();
+                if RAW_TYPST.contains(&lang.as_str()) {
+                    self.raw_typst_block(&code);
+                } else {
+                    self.code_block(&code, &lang);
+                }
             }
             TagEnd::List(_) => {
-                self.list_depth = self.list_depth.saturating_sub(1);
-                if self.list_depth == 0 {
+                self.lists.pop();
+                if self.lists.is_empty() {
                     self.output.push('\n');
                 }
             }
             TagEnd::Item => {
-                self.output.push('\n');
+                self.ensure_newline();
+            }
+            TagEnd::FootnoteDefinition => {
+                let body = self.end_capture();
+                if let Some(label) = self.footnote_label.take() {
+                    self.footnotes.insert(label, body.trim().to_string());
+                }
             }
             TagEnd::Emphasis => {
                 self.output.push('_');
@@ -167,19 +373,24 @@ impl TypstConverter {
             TagEnd::Link => {
                 self.output.push(']');
             }
-            TagEnd::Image => {}
+            TagEnd::Image => {
+                let alt = self.end_capture();
+                if let Some(image) = self.images.pop() {
+                    self.image(&image, alt.trim());
+                }
+            }
             TagEnd::Table => {
-                self.in_table = false;
-                self.output.push_str(")\n");
+                self.output.push_str(")\n\n");
             }
             TagEnd::TableHead => {
-                self.output.push('\n');
+                self.in_table_head = false;
+                self.output.push_str("),\n");
             }
             TagEnd::TableRow => {
-                self.output.push('\n');
+                self.output.push_str(",\n");
             }
             TagEnd::TableCell => {
-                self.output.push_str("],");
+                self.output.push(']');
                 self.table_cell_index += 1;
             }
             _ => {}
@@ -187,48 +398,342 @@ impl TypstConverter {
     }
 
     fn text(&mut self, text: &str) {
-        if self.in_code_block {
+        if self.code_lang.is_some() || !self.images.is_empty() {
             self.output.push_str(text);
         } else {
             // Escape Typst special characters
-            let escaped = escape_typst(text);
+            let mut escaped = self.escape_with_references(text);
+            if self.at_line_start() {
+                escaped = escape_line_start(&escaped);
+            } else if self.after_line_number() && escaped.starts_with('.') {
+                // `2024\.` arrives as "2024" and "."
+                escaped = escape_line_start(&format!("0{}", escaped))[1..].to_string();
+            }
             self.output.push_str(&escaped);
         }
     }
 
     fn inline_code(&mut self, code: &str) {
-        self.output.push('`');
+        self.last_code = Some((self.output.len(), code.to_string()));
+        if !self.images.is_empty() {
+            self.output.push_str(code);
+        } else if code.contains('`') {
+            self.output.push_str("#raw(");
+            self.output.push_str(&string_literal(code));
+            self.output.push(')');
+        } else {
+            self.output.push('`');
+            self.output.push_str(code);
+            self.output.push('`');
+        }
+    }
+
+    /// Emit math as Typst math, or as raw LaTeX if it cannot be translated
+    fn math(&mut self, latex: &str, display: bool) {
+        if !self.images.is_empty() {
+            self.output.push_str(latex);
+            return;
+        }
+
+        self.output.push_str(&math_markup(latex, display));
+    }
+
+    /// Escape text, keeping `@label` as a reference for known labels
+    fn escape_with_references(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut previous = self.output.chars().last();
+        let mut rest = text;
+
+        while let Some(at) = rest.find('@') {
+            let (before, after) = rest.split_at(at);
+            result.push_str(&escape_typst(before));
+            previous = before.chars().last().or(previous);
+
+            // Typst leaves a trailing `.` or `:` out of the reference
+            let label = after[1..]
+                .split(|c: char| !is_label_char(c))
+                .next()
+                .unwrap_or("")
+                .trim_end_matches(['.', ':']);
+            let word_start = !previous.is_some_and(char::is_alphanumeric);
+            if word_start && self.labels.contains(label) {
+                result.push('@');
+                result.push_str(label);
+                rest = &after[1 + label.len()..];
+                previous = label.chars().last();
+            } else {
+                result.push_str("\\@");
+                rest = &after[1..];
+                previous = Some('@');
+            }
+        }
+
+        result.push_str(&escape_typst(rest));
+        result
+    }
+
+    /// Insert the contents of a ```` ```typst ```` fence unchanged
+    fn raw_typst_block(&mut self, code: &str) {
+        self.ensure_newline();
+        let indent = self.indent();
+        for line in code.lines() {
+            if !line.is_empty() {
+                self.output.push_str(&indent);
+            }
+            self.output.push_str(line);
+            self.output.push('\n');
+        }
+        if self.lists.is_empty() {
+            self.output.push('\n');
+        }
+    }
+
+    fn code_block(&mut self, code: &str, lang: &str) {
+        // The fence must be longer than any backtick run in the code
+        let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat((longest + 1).max(3));
+
+        if self.at_line_start() {
+            self.output.push_str(&self.indent());
+        }
+        self.output.push_str(&fence);
+        self.output.push_str(lang);
+        self.output.push('\n');
         self.output.push_str(code);
-        self.output.push('`');
+        if !code.ends_with('\n') {
+            self.output.push('\n');
+        }
+        self.output.push_str(&self.indent());
+        self.output.push_str(&fence);
+        self.output.push('\n');
+        if self.lists.is_empty() {
+            self.output.push('\n');
+        }
+    }
+
+    fn image(&mut self, image: &Image, alt: &str) {
+        let mut call = format!("image({}", string_literal(&image.url));
+        if !alt.is_empty() {
+            call.push_str(", alt: ");
+            call.push_str(&string_literal(alt));
+        }
+        call.push(')');
+
+        if image.title.is_empty() {
+            self.output.push('#');
+            self.output.push_str(&call);
+        } else {
+            self.output.push_str("#figure(");
+            self.output.push_str(&call);
+            self.output.push_str(", caption: [");
+            self.output.push_str(&escape_typst(&image.title));
+            self.output.push_str("])");
+        }
     }
 
     fn soft_break(&mut self) {
-        self.output.push(' ');
+        if self.code_lang.is_some() {
+            self.output.push('\n');
+        } else {
+            self.output.push(' ');
+        }
     }
 
     fn hard_break(&mut self) {
         self.output.push_str(" \\\n");
+        self.output.push_str(&self.indent());
     }
 
     fn rule(&mut self) {
+        self.ensure_newline();
         self.output.push_str("#line(length: 100%)\n");
     }
 
-    fn finish(mut self) -> String {
+    /// Redirect output into a fresh buffer
+    fn begin_capture(&mut self) {
+        self.saved.push(std::mem::take(&mut self.output));
+    }
+
+    /// Restore the outer output and return what was captured
+    fn end_capture(&mut self) -> String {
+        let outer = self.saved.pop().unwrap_or_default();
+        std::mem::replace(&mut self.output, outer)
+    }
+
+    /// Indentation of content continuing the innermost list item
+    fn indent(&self) -> String {
+        "  ".repeat(self.lists.len())
+    }
+
+    fn ensure_newline(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+    }
+
+    /// Whether the current line so far is only a number, like `2024`
+    fn after_line_number(&self) -> bool {
+        let line = self.output.rsplit('\n').next().unwrap_or("").trim_start();
+        !line.is_empty() && line.chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn at_line_start(&self) -> bool {
+        let line = self.output.rsplit('\n').next().unwrap_or("");
+        line.chars().all(|c| c == ' ')
+    }
+
+    fn finish(self) -> String {
+        let mut output = resolve_footnotes(&self.output, &self.footnotes);
         // Trim trailing whitespace
-        while self.output.ends_with('\n') {
-            self.output.pop();
+        while output.ends_with('\n') {
+            output.pop();
+        }
+        output
+    }
+}
+
+/// Replace footnote references with `#footnote[...]`.
+///
+/// A footnote referenced several times is labelled at its first reference
+/// and repeated with `#footnote(<label>)`.
+fn resolve_footnotes(output: &str, footnotes: &HashMap<String, String>) -> String {
+    let references: Vec<&str> = output
+        .split(FOOTNOTE_START)
+        .skip(1)
+        .filter_map(|part| part.split_once(FOOTNOTE_END).map(|(label, _)| label))
+        .collect();
+    if references.is_empty() {
+        return output.to_string();
+    }
+
+    let mut result = String::with_capacity(output.len());
+    let mut seen: Vec<&str> = Vec::new();
+    let mut parts = output.split(FOOTNOTE_START);
+    result.push_str(parts.next().unwrap_or(""));
+
+    for part in parts {
+        let Some((label, rest)) = part.split_once(FOOTNOTE_END) else {
+            result.push_str(part);
+            continue;
+        };
+        let name = footnote_label(label);
+        match footnotes.get(label) {
+            Some(_) if seen.contains(&label) => {
+                result.push_str(&format!("#footnote(<{}>)", name));
+            }
+            Some(body) => {
+                result.push_str(&format!("#footnote[{}]", body));
+                if references.iter().filter(|r| **r == label).count() > 1 {
+                    result.push_str(&format!(" <{}>", name));
+                }
+                seen.push(label);
+            }
+            None => result.push_str(&escape_typst(&format!("[^{}]", label))),
         }
-        self.output
+        result.push_str(rest);
+    }
+
+    result
+}
+
+/// Typst label for a footnote
+fn footnote_label(label: &str) -> String {
+    let name: String = label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("fn-{}", name)
+}
+
+/// Typst alignment for a table column
+fn alignment(alignment: &Alignment) -> &'static str {
+    match alignment {
+        Alignment::None => "auto",
+        Alignment::Left => "left",
+        Alignment::Center => "center",
+        Alignment::Right => "right",
+    }
+}
+
+/// Typst math for LaTeX source, or the LaTeX as raw text if it cannot be
+/// translated
+pub(crate) fn math_markup(latex: &str, display: bool) -> String {
+    let raw = string_literal(latex.trim());
+    match latex_to_typst(latex) {
+        Some(math) if display => format!("$ {} $", math),
+        Some(math) => format!("${}$", math),
+        None if display => format!("#raw({}, block: true, lang: \"latex\")", raw),
+        None => format!("#raw({}, lang: \"latex\")", raw),
+    }
+}
+
+/// Whether `name` can be written as a Typst label, `<name>`
+pub(crate) fn is_label(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_label_char)
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.')
+}
+
+/// Quote a string for Typst code
+pub(crate) fn string_literal(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            _ => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Escape text that would start a list, heading or term item at line start
+pub(crate) fn escape_line_start(text: &str) -> String {
+    let indent = text.len() - text.trim_start_matches(' ').len();
+    let (spaces, rest) = text.split_at(indent);
+    let mut chars = rest.chars();
+    let marker_end = |rest: &str| rest.is_empty() || rest.starts_with(char::is_whitespace);
+
+    match chars.next() {
+        Some('-' | '+' | '=' | '/') if marker_end(chars.as_str()) => {
+            format!("{}\\{}", spaces, rest)
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let after = &rest[digits..];
+            match after.strip_prefix('.') {
+                Some(tail) if marker_end(tail) => {
+                    format!("{}{}\\.{}", spaces, &rest[..digits], tail)
+                }
+                _ => text.to_string(),
+            }
+        }
+        _ => text.to_string(),
     }
 }
 
 /// Escape special Typst characters in plain text
 pub fn escape_typst(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
 
-    for ch in text.chars() {
+    while let Some(ch) = chars.next() {
         match ch {
+            '\\' => result.push_str("\\\\"),
+            '~' => result.push_str("\\~"),
+            // `//` and `/*` would start a comment
+            '/' if matches!(chars.peek(), Some('/' | '*')) => result.push_str("\\/"),
             '#' => result.push_str("\\#"),
             '$' => result.push_str("\\$"),
             '*' => result.push_str("\\*"),
@@ -248,6 +753,9 @@ pub fn escape_typst(text: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+    use std::path::Path;
+
     use super::*;
 
     #[test]
@@ -281,6 +789,85 @@ mod tests {
         assert!(result.contains("- Item 2"));
     }
 
+    /// Convert `tests/golden/markdown/<name>.md` and compare the result with
+    /// `<name>.typ`. Set `UPDATE_GOLDEN=1` to rewrite the expected output.
+    fn golden(name: &str) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/markdown");
+        let markdown = fs::read_to_string(dir.join(format!("{}.md", name))).unwrap();
+        let actual = markdown_to_typst(&markdown).unwrap() + "\n";
+
+        let expected_path = dir.join(format!("{}.typ", name));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&expected_path, &actual).unwrap();
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap();
+        assert_eq!(actual, expected, "golden output for {}.md", name);
+        assert!(
+            !typst_syntax::parse(&actual).erroneous(),
+            "{}.typ is not valid Typst markup",
+            name
+        );
+    }
+
+    #[test]
+    fn test_golden_ordered_lists() {
+        golden("ordered_lists");
+    }
+
+    #[test]
+    fn test_golden_loose_lists() {
+        golden("loose_lists");
+    }
+
+    #[test]
+    fn test_golden_task_lists() {
+        golden("task_lists");
+    }
+
+    #[test]
+    fn test_golden_footnotes() {
+        golden("footnotes");
+    }
+
+    #[test]
+    fn test_golden_code_blocks() {
+        golden("code_blocks");
+    }
+
+    #[test]
+    fn test_golden_tables() {
+        golden("tables");
+    }
+
+    #[test]
+    fn test_golden_images() {
+        golden("images");
+    }
+
+    #[test]
+    fn test_golden_escaping() {
+        golden("escaping");
+    }
+
+    #[test]
+    fn test_golden_math() {
+        golden("math");
+    }
+
+    #[test]
+    fn test_golden_raw_typst() {
+        golden("raw_typst");
+    }
+
+    #[test]
+    fn test_references_to_other_blocks() {
+        let labels = heading_labels("# Terms {#terms}");
+        let result = markdown_to_typst_with_labels("See @terms.", &labels).unwrap();
+        assert_eq!(result, "See @terms.");
+        let result = markdown_to_typst("See @terms.").unwrap();
+        assert_eq!(result, "See \\@terms.");
+    }
+
     #[test]
     fn test_escape() {
         let escaped = escape_typst("Price: $100 #tag");
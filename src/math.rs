@@ -0,0 +1,681 @@
+//! LaTeX math to Typst math translation
+//!
+//! Translates the LaTeX commonly written between `$...$` in markdown blocks
+//! (fractions, big operators, Greek letters, attachments, `\text`, matrices)
+//! into Typst math. Input using anything else is reported as untranslatable
+//! so the caller can fall back to showing the source.
+
+/// LaTeX commands that map to a Typst symbol or operator
+const SYMBOLS: &[(&str, &str)] = &[
+    // Greek letters
+    ("alpha", "alpha"),
+    ("beta", "beta"),
+    ("gamma", "gamma"),
+    ("delta", "delta"),
+    ("epsilon", "epsilon.alt"),
+    ("varepsilon", "epsilon"),
+    ("zeta", "zeta"),
+    ("eta", "eta"),
+    ("theta", "theta"),
+    ("vartheta", "theta.alt"),
+    ("iota", "iota"),
+    ("kappa", "kappa"),
+    ("lambda", "lambda"),
+    ("mu", "mu"),
+    ("nu", "nu"),
+    ("xi", "xi"),
+    ("omicron", "omicron"),
+    ("pi", "pi"),
+    ("varpi", "pi.alt"),
+    ("rho", "rho"),
+    ("varrho", "rho.alt"),
+    ("sigma", "sigma"),
+    ("varsigma", "sigma.alt"),
+    ("tau", "tau"),
+    ("upsilon", "upsilon"),
+    ("phi", "phi.alt"),
+    ("varphi", "phi"),
+    ("chi", "chi"),
+    ("psi", "psi"),
+    ("omega", "omega"),
+    ("Gamma", "Gamma"),
+    ("Delta", "Delta"),
+    ("Theta", "Theta"),
+    ("Lambda", "Lambda"),
+    ("Xi", "Xi"),
+    ("Pi", "Pi"),
+    ("Sigma", "Sigma"),
+    ("Upsilon", "Upsilon"),
+    ("Phi", "Phi"),
+    ("Psi", "Psi"),
+    ("Omega", "Omega"),
+    // Big operators
+    ("sum", "sum"),
+    ("prod", "product"),
+    ("coprod", "product.co"),
+    ("int", "integral"),
+    ("iint", "integral.double"),
+    ("iiint", "integral.triple"),
+    ("oint", "integral.cont"),
+    ("bigcup", "union.big"),
+    ("bigcap", "sect.big"),
+    // Named operators
+    ("lim", "lim"),
+    ("limsup", "limsup"),
+    ("liminf", "liminf"),
+    ("max", "max"),
+    ("min", "min"),
+    ("sup", "sup"),
+    ("inf", "inf"),
+    ("arg", "arg"),
+    ("det", "det"),
+    ("dim", "dim"),
+    ("exp", "exp"),
+    ("log", "log"),
+    ("ln", "ln"),
+    ("lg", "lg"),
+    ("sin", "sin"),
+    ("cos", "cos"),
+    ("tan", "tan"),
+    ("cot", "cot"),
+    ("sec", "sec"),
+    ("csc", "csc"),
+    ("arcsin", "arcsin"),
+    ("arccos", "arccos"),
+    ("arctan", "arctan"),
+    ("sinh", "sinh"),
+    ("cosh", "cosh"),
+    ("tanh", "tanh"),
+    ("gcd", "gcd"),
+    ("mod", "mod"),
+    ("bmod", "mod"),
+    // Binary operators and relations
+    ("cdot", "dot"),
+    ("times", "times"),
+    ("div", "div"),
+    ("pm", "plus.minus"),
+    ("mp", "minus.plus"),
+    ("ast", "ast"),
+    ("star", "star"),
+    ("circ", "compose"),
+    ("bullet", "bullet"),
+    ("oplus", "plus.circle"),
+    ("otimes", "times.circle"),
+    ("leq", "<="),
+    ("le", "<="),
+    ("geq", ">="),
+    ("ge", ">="),
+    ("neq", "!="),
+    ("ne", "!="),
+    ("ll", "<<"),
+    ("gg", ">>"),
+    ("approx", "approx"),
+    ("equiv", "equiv"),
+    ("sim", "tilde.op"),
+    ("simeq", "tilde.eq"),
+    ("cong", "tilde.equiv"),
+    ("propto", "prop"),
+    ("in", "in"),
+    ("notin", "in.not"),
+    ("ni", "in.rev"),
+    ("subset", "subset"),
+    ("subseteq", "subset.eq"),
+    ("supset", "supset"),
+    ("supseteq", "supset.eq"),
+    ("cup", "union"),
+    ("cap", "sect"),
+    ("setminus", "without"),
+    ("wedge", "and"),
+    ("land", "and"),
+    ("vee", "or"),
+    ("lor", "or"),
+    ("neg", "not"),
+    ("lnot", "not"),
+    ("perp", "perp"),
+    ("parallel", "parallel"),
+    ("mid", "divides"),
+    // Arrows
+    ("to", "->"),
+    ("rightarrow", "->"),
+    ("leftarrow", "<-"),
+    ("gets", "<-"),
+    ("leftrightarrow", "<->"),
+    ("Rightarrow", "=>"),
+    ("Leftarrow", "arrow.l.double"),
+    ("Leftrightarrow", "<=>"),
+    ("implies", "=>"),
+    ("iff", "<=>"),
+    ("mapsto", "|->"),
+    ("uparrow", "arrow.t"),
+    ("downarrow", "arrow.b"),
+    // Miscellaneous symbols
+    ("infty", "infinity"),
+    ("partial", "diff"),
+    ("nabla", "nabla"),
+    ("forall", "forall"),
+    ("exists", "exists"),
+    ("emptyset", "emptyset"),
+    ("varnothing", "emptyset"),
+    ("hbar", "planck.reduce"),
+    ("ell", "ell"),
+    ("angle", "angle"),
+    ("triangle", "triangle.t"),
+    ("degree", "degree"),
+    ("prime", "prime"),
+    ("dots", "dots"),
+    ("ldots", "dots"),
+    ("cdots", "dots.c"),
+    ("vdots", "dots.v"),
+    ("ddots", "dots.down"),
+    ("langle", "angle.l"),
+    ("rangle", "angle.r"),
+    ("lfloor", "floor.l"),
+    ("rfloor", "floor.r"),
+    ("lceil", "ceil.l"),
+    ("rceil", "ceil.r"),
+    ("lvert", "|"),
+    ("rvert", "|"),
+    ("vert", "|"),
+    ("Vert", "||"),
+    // Spacing
+    ("quad", "quad"),
+    ("qquad", "wide"),
+    (",", "thin"),
+    (":", "med"),
+    (">", "med"),
+    (";", "thick"),
+    (" ", "space"),
+    ("!", ""),
+    // Escaped characters
+    ("{", "{"),
+    ("}", "}"),
+    ("%", "%"),
+    ("$", "\\$"),
+    ("#", "\\#"),
+    ("&", "\\&"),
+    ("_", "\\_"),
+    ("|", "||"),
+];
+
+/// Commands taking one math argument, mapped to a Typst function
+const FUNCTIONS: &[(&str, &str)] = &[
+    ("sqrt", "sqrt"),
+    ("mathbf", "bold"),
+    ("boldsymbol", "bold"),
+    ("bm", "bold"),
+    ("mathit", "italic"),
+    ("mathbb", "bb"),
+    ("mathcal", "cal"),
+    ("mathfrak", "frak"),
+    ("mathsf", "sans"),
+    ("mathtt", "mono"),
+    ("hat", "hat"),
+    ("widehat", "hat"),
+    ("bar", "macron"),
+    ("overline", "overline"),
+    ("underline", "underline"),
+    ("vec", "arrow"),
+    ("dot", "dot"),
+    ("ddot", "dot.double"),
+    ("tilde", "tilde"),
+    ("widetilde", "tilde"),
+    ("overbrace", "overbrace"),
+    ("underbrace", "underbrace"),
+    ("cancel", "cancel"),
+    ("abs", "abs"),
+    ("norm", "norm"),
+];
+
+/// Environments translated to `mat(...)`, with their delimiter
+const MATRICES: &[(&str, &str)] = &[
+    ("matrix", "#none"),
+    ("smallmatrix", "#none"),
+    ("pmatrix", "\"(\""),
+    ("bmatrix", "\"[\""),
+    ("Bmatrix", "\"{\""),
+    ("vmatrix", "\"|\""),
+    ("Vmatrix", "\"||\""),
+];
+
+/// Environments whose rows and alignment points carry over unchanged
+const ALIGNED: &[&str] = &[
+    "aligned",
+    "align",
+    "align*",
+    "gathered",
+    "gather",
+    "gather*",
+    "split",
+    "equation",
+    "equation*",
+];
+
+/// Translate LaTeX math to Typst math.
+///
+/// Returns `None` if the input uses a command or environment without a
+/// translation, or is malformed.
+pub fn latex_to_typst(latex: &str) -> Option<String> {
+    let tokens = tokenize(latex);
+    let mut parser = Parser { tokens, pos: 0 };
+    let atoms = parser.sequence(Stop::End)?;
+    Some(join(&atoms))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// `\name` or `\` followed by a single symbol
+    Command(String),
+    /// A run of digits, possibly with a decimal point
+    Number(String),
+    Char(char),
+    Open,
+    Close,
+    Sup,
+    Sub,
+    /// Whitespace, only significant inside `\text{...}`
+    Space,
+}
+
+fn tokenize(latex: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = latex.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                    name.push(c);
+                    chars.next();
+                }
+                if name.is_empty() {
+                    if let Some(c) = chars.next() {
+                        name.push(if c.is_whitespace() { ' ' } else { c });
+                    }
+                }
+                tokens.push(Token::Command(name));
+            }
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '^' => tokens.push(Token::Sup),
+            '_' => tokens.push(Token::Sub),
+            '%' => {
+                // Comment until the end of the line
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '~' => tokens.push(Token::Command(" ".to_string())),
+            c if c.is_ascii_digit() => {
+                let mut number = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    let decimal =
+                        c == '.' && chars.clone().nth(1).is_some_and(|n| n.is_ascii_digit());
+                    if !(c.is_ascii_digit() || decimal) {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_whitespace() => {
+                if tokens.last() != Some(&Token::Space) {
+                    tokens.push(Token::Space);
+                }
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+
+    tokens
+}
+
+/// Where a sequence ends
+#[derive(Clone, Copy, PartialEq)]
+enum Stop {
+    /// End of input
+    End,
+    /// Closing `}`
+    Close,
+    /// Closing `]` of an optional argument
+    Bracket,
+    /// `\right`
+    Right,
+    /// `\end{...}`
+    EndEnv,
+}
+
+/// Row separator inside sequences (`\\`)
+const LINEBREAK: &str = "\\";
+
+/// Cell separator inside sequences (`&`)
+const ALIGN: &str = "&";
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Next token other than whitespace
+    fn next_significant(&mut self) -> Option<Token> {
+        loop {
+            match self.next()? {
+                Token::Space => continue,
+                token => return Some(token),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Parse atoms until `stop`, which is consumed
+    fn sequence(&mut self, stop: Stop) -> Option<Vec<String>> {
+        let mut atoms: Vec<String> = Vec::new();
+
+        loop {
+            let Some(token) = self.next() else {
+                return (stop == Stop::End).then_some(atoms);
+            };
+            match token {
+                Token::Close if stop == Stop::Close => return Some(atoms),
+                Token::Char(']') if stop == Stop::Bracket => return Some(atoms),
+                Token::Command(name) if name == "right" && stop == Stop::Right => {
+                    let delimiter = self.delimiter()?;
+                    atoms.push(delimiter);
+                    return Some(atoms);
+                }
+                Token::Command(name) if name == "end" && stop == Stop::EndEnv => {
+                    self.raw_group()?;
+                    return Some(atoms);
+                }
+                Token::Sup | Token::Sub => {
+                    let marker = if token == Token::Sup { '^' } else { '_' };
+                    let argument = self.argument()?;
+                    let base = atoms.pop().unwrap_or_else(|| "\"\"".to_string());
+                    atoms.push(format!("{}{}{}", base, marker, attachment(&argument)));
+                }
+                Token::Space => {}
+                Token::Char('\'') => match atoms.last_mut() {
+                    Some(last) => last.push('\''),
+                    None => atoms.push("prime".to_string()),
+                },
+                token => {
+                    let atom = self.atom(token)?;
+                    if !atom.is_empty() {
+                        atoms.push(atom);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A single atom for `token`, reading any arguments it takes
+    fn atom(&mut self, token: Token) -> Option<String> {
+        match token {
+            Token::Open => Some(join(&escape_separators(self.sequence(Stop::Close)?))),
+            Token::Number(number) => Some(number),
+            Token::Char(c) => char_atom(c),
+            Token::Command(name) => self.command(&name),
+            Token::Close | Token::Sup | Token::Sub | Token::Space => None,
+        }
+    }
+
+    /// A command argument: a group or a single token
+    fn argument(&mut self) -> Option<String> {
+        match self.next_significant()? {
+            Token::Open => Some(join(&escape_separators(self.sequence(Stop::Close)?))),
+            // `x^12` attaches only the first digit
+            Token::Number(number) if number.len() > 1 => {
+                let (first, rest) = number.split_at(1);
+                self.tokens
+                    .insert(self.pos, Token::Number(rest.to_string()));
+                Some(first.to_string())
+            }
+            token => self.atom(token),
+        }
+    }
+
+    /// The verbatim text of a `{...}` group
+    fn raw_group(&mut self) -> Option<String> {
+        if self.next_significant()? != Token::Open {
+            return None;
+        }
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Close if depth == 0 => return Some(text),
+                Token::Close => {
+                    depth -= 1;
+                    text.push('}');
+                }
+                Token::Open => {
+                    depth += 1;
+                    text.push('{');
+                }
+                Token::Command(name) if name == " " => text.push(' '),
+                Token::Command(name) if name.len() == 1 => text.push_str(&name),
+                Token::Command(_) => return None,
+                Token::Number(number) => text.push_str(&number),
+                Token::Char(c) => text.push(c),
+                Token::Sup => text.push('^'),
+                Token::Sub => text.push('_'),
+                Token::Space => text.push(' '),
+            }
+        }
+    }
+
+    /// Delimiter after `\left` / `\right`
+    fn delimiter(&mut self) -> Option<String> {
+        match self.next_significant()? {
+            Token::Char('.') => Some(String::new()),
+            Token::Char(c) => char_atom(c),
+            Token::Command(name) => symbol(&name).map(str::to_string),
+            _ => None,
+        }
+    }
+
+    fn command(&mut self, name: &str) -> Option<String> {
+        if let Some(symbol) = symbol(name) {
+            return Some(symbol.to_string());
+        }
+        if let Some((_, function)) = FUNCTIONS.iter().find(|(n, _)| *n == name) {
+            if name == "sqrt" && self.peek() == Some(&Token::Char('[')) {
+                self.next();
+                let index = join(&escape_separators(self.sequence(Stop::Bracket)?));
+                let radicand = self.argument()?;
+                return Some(format!("root({}, {})", index, radicand));
+            }
+            let argument = self.argument()?;
+            return Some(format!("{}({})", function, argument));
+        }
+
+        match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.argument()?;
+                let denominator = self.argument()?;
+                Some(format!("frac({}, {})", numerator, denominator))
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.argument()?;
+                let k = self.argument()?;
+                Some(format!("binom({}, {})", n, k))
+            }
+            "text" | "textrm" | "textnormal" | "mbox" => Some(string(&self.raw_group()?)),
+            "textbf" => Some(format!("bold({})", string(&self.raw_group()?))),
+            "textit" | "emph" => Some(format!("italic({})", string(&self.raw_group()?))),
+            "operatorname" => Some(format!("op({})", string(&self.raw_group()?))),
+            // `\mathrm{d}` is an upright symbol, `\mathrm{max}` upright text
+            "mathrm" => {
+                let text = self.raw_group()?;
+                if text.chars().count() == 1 {
+                    Some(format!("upright({})", text))
+                } else {
+                    Some(format!("upright({})", string(&text)))
+                }
+            }
+            "left" => {
+                let open = self.delimiter()?;
+                let body = self.sequence(Stop::Right)?;
+                Some(format!("{} {}", open, join(&body)).trim().to_string())
+            }
+            "begin" => self.environment(),
+            "\\" => Some(LINEBREAK.to_string()),
+            "displaystyle" | "textstyle" | "limits" | "nolimits" | "big" | "Big" | "bigg"
+            | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn environment(&mut self) -> Option<String> {
+        let name = self.raw_group()?;
+        let body = self.sequence(Stop::EndEnv)?;
+        let rows = rows(&body);
+
+        if let Some((_, delim)) = MATRICES.iter().find(|(n, _)| *n == name) {
+            let rows: Vec<String> = rows.iter().map(|cells| cells.join(", ")).collect();
+            return Some(format!("mat(delim: {}, {})", delim, rows.join("; ")));
+        }
+        if name == "cases" {
+            let rows: Vec<String> = rows.iter().map(|cells| cells.join(" & ")).collect();
+            return Some(format!("cases({})", rows.join(", ")));
+        }
+        if ALIGNED.contains(&name.as_str()) {
+            return Some(join(&body));
+        }
+        None
+    }
+}
+
+/// Split a sequence into rows (`\\`) of cells (`&`)
+fn rows(atoms: &[String]) -> Vec<Vec<String>> {
+    atoms
+        .split(|a| a == LINEBREAK)
+        .map(|row| row.split(|a| a == ALIGN).map(join).collect::<Vec<_>>())
+        .filter(|cells| cells.iter().any(|c| !c.is_empty()))
+        .collect()
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, typst)| *typst)
+}
+
+/// Typst math for a literal character
+fn char_atom(c: char) -> Option<String> {
+    Some(match c {
+        // A single slash in LaTeX is not a fraction
+        '/' => "slash".to_string(),
+        '"' => "\\\"".to_string(),
+        '#' | '$' | '@' | '`' => format!("\\{}", c),
+        '&' => ALIGN.to_string(),
+        c if c.is_alphanumeric() || c.is_ascii_punctuation() => c.to_string(),
+        c if !c.is_control() => c.to_string(),
+        _ => return None,
+    })
+}
+
+/// Attachment argument: parenthesized unless it is a single token
+fn attachment(argument: &str) -> String {
+    let simple = !argument.is_empty()
+        && (argument.chars().count() == 1
+            || argument.chars().all(|c| c.is_ascii_digit() || c == '.')
+            || argument.chars().all(|c| c.is_alphabetic() || c == '.'));
+    if simple {
+        argument.to_string()
+    } else {
+        format!("({})", argument)
+    }
+}
+
+/// A Typst string literal (upright text in math)
+fn string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escape top-level `,` and `;` of a group so they stay literal when the
+/// group ends up as a Typst call argument (`\frac{1,5}{2}`)
+fn escape_separators(atoms: Vec<String>) -> Vec<String> {
+    atoms
+        .into_iter()
+        .map(|atom| match atom.as_str() {
+            "," | ";" => format!("\\{}", atom),
+            _ => atom,
+        })
+        .collect()
+}
+
+/// Join atoms with spaces so letters are not read as one identifier
+fn join(atoms: &[String]) -> String {
+    let atoms: Vec<&str> = atoms
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.is_empty())
+        .collect();
+    atoms.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latex_to_typst() {
+        let cases = [
+            (r"\frac{a+b}{2}", "frac(a + b, 2)"),
+            (r"\sum_{i=1}^{n} x_i^2", "sum_(i = 1)^n x_i^2"),
+            (r"\alpha \beta_0 \Omega", "alpha beta_0 Omega"),
+            (r"e^{i\pi} + 1 = 0", "e^(i pi) + 1 = 0"),
+            (
+                r"\text{for all } x \in \mathbb{R}",
+                "\"for all \" x in bb(R)",
+            ),
+            (r"\sqrt[3]{x} \leq \sqrt{y}", "root(3, x) <= sqrt(y)"),
+            (r"a/b \cdot c", "a slash b dot c"),
+            (r"f'(x) = 2x", "f' ( x ) = 2 x"),
+            (r"x^10", "x^1 0"),
+            (r"\mathrm{d}x", "upright(d) x"),
+            (
+                r"\begin{pmatrix} 1 & 0 \\ 0 & 1 \end{pmatrix}",
+                "mat(delim: \"(\", 1, 0; 0, 1)",
+            ),
+            (
+                r"|x| = \begin{cases} x & x \geq 0 \\ -x & x < 0 \end{cases}",
+                "| x | = cases(x & x >= 0, - x & x < 0)",
+            ),
+            (r"\left( \frac{1}{2} \right)", "( frac(1, 2) )"),
+            (r"\frac{1,5}{2}", "frac(1 \\, 5, 2)"),
+            (r"\binom{n;k}{2}", "binom(n \\; k, 2)"),
+            (
+                r"\sqrt{a,b} + \sqrt[3,5]{x}",
+                "sqrt(a \\, b) + root(3 \\, 5, x)",
+            ),
+            (r"\mathbf{x,y}", "bold(x \\, y)"),
+            (r"f(a, b)", "f ( a , b )"),
+        ];
+        for (latex, typst) in cases {
+            assert_eq!(latex_to_typst(latex).as_deref(), Some(typst), "{}", latex);
+        }
+    }
+
+    #[test]
+    fn test_untranslatable_latex() {
+        assert_eq!(latex_to_typst(r"\unknowncommand{x}"), None);
+        assert_eq!(latex_to_typst(r"\frac{a}{"), None);
+        assert_eq!(latex_to_typst(r"\begin{tikzcd} a \end{tikzcd}"), None);
+    }
+}
@@ -0,0 +1,427 @@
+//! Batch / mail-merge generation
+//!
+//! Loads rows from CSV or JSON and deep-merges each row into a content file.
+//! Output names are expanded per row with [`crate::output::expand`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::content::ContentFile;
+use crate::error::{Error, Result};
+
+/// Outcome of compiling a single row
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeEntry {
+    /// 1-based row number in the data file
+    pub row: usize,
+    /// Output file (if the name could be resolved)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    /// "ok" or "error"
+    pub status: String,
+    /// Error message for failed rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Summary written after a merge run
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeManifest {
+    /// "ok" if every row compiled, "error" otherwise
+    pub status: String,
+    /// Content file used as the base for every row
+    pub content: PathBuf,
+    /// CSV or JSON file the rows were read from
+    pub data: PathBuf,
+    /// Number of rows in the data file
+    pub total: usize,
+    /// Rows that compiled successfully
+    pub succeeded: usize,
+    /// Rows that failed to compile
+    pub failed: usize,
+    /// Per-row outcomes in data file order
+    pub documents: Vec<MergeEntry>,
+}
+
+impl MergeManifest {
+    /// Build a manifest from per-row results (sorted by row)
+    pub fn new(content: PathBuf, data: PathBuf, mut documents: Vec<MergeEntry>) -> Self {
+        documents.sort_by_key(|d| d.row);
+        let failed = documents.iter().filter(|d| d.status != "ok").count();
+        Self {
+            status: if failed == 0 { "ok" } else { "error" }.to_string(),
+            content,
+            data,
+            total: documents.len(),
+            succeeded: documents.len() - failed,
+            failed,
+            documents,
+        }
+    }
+}
+
+/// Load merge rows from a `.csv` or `.json` file.
+///
+/// CSV headers and JSON keys may be dotted (`recipient.name`) and are
+/// expanded into nested tables. Empty CSV cells and JSON nulls are skipped so
+/// they do not override values from the content file. CSV cells holding plain
+/// numbers or booleans are typed accordingly.
+pub fn load_rows(path: &Path) -> Result<Vec<toml::Value>> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            Error::FileNotFound {
+                path: path.to_path_buf(),
+            }
+        } else {
+            Error::Io(std::io::Error::new(
+                e.kind(),
+                format!("reading merge data {}: {}", path.display(), e),
+            ))
+        }
+    })?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => parse_csv(&text),
+        Some("json") => parse_json(&text),
+        _ => Err(Error::Merge(format!(
+            "unsupported data file {} (expected .csv or .json)",
+            path.display()
+        ))),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<toml::Value>> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::Merge(format!("reading CSV header: {}", e)))?
+        .clone();
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record =
+            record.map_err(|e| Error::Merge(format!("reading CSV row {}: {}", i + 1, e)))?;
+        let mut row = toml::Value::Table(toml::map::Map::new());
+        for (header, cell) in headers.iter().zip(record.iter()) {
+            if !cell.is_empty() {
+                set_dotted(&mut row, header.trim(), csv_value(cell));
+            }
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Typed value of a CSV cell
+///
+/// Plain integers, decimals and `true` / `false` become numbers and booleans
+/// so rows can feed computed fields and number formatting. Anything else,
+/// including numbers with leading zeros or a sign like `+49`, stays a string.
+fn csv_value(cell: &str) -> toml::Value {
+    let digits = cell.strip_prefix('-').unwrap_or(cell);
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let numeric = is_digits(whole)
+        && fraction.is_none_or(is_digits)
+        && (whole == "0" || !whole.starts_with('0'));
+
+    match cell {
+        "true" => toml::Value::Boolean(true),
+        "false" => toml::Value::Boolean(false),
+        _ if numeric && fraction.is_none() => cell
+            .parse()
+            .map(toml::Value::Integer)
+            .unwrap_or_else(|_| toml::Value::String(cell.to_string())),
+        _ if numeric => cell
+            .parse()
+            .map(toml::Value::Float)
+            .unwrap_or_else(|_| toml::Value::String(cell.to_string())),
+        _ => toml::Value::String(cell.to_string()),
+    }
+}
+
+fn parse_json(text: &str) -> Result<Vec<toml::Value>> {
+    let json: serde_json::Value = serde_json::from_str(text)?;
+    let items = match json {
+        serde_json::Value::Array(items) => items,
+        _ => {
+            return Err(Error::Merge(
+                "JSON merge data must be an array of objects".to_string(),
+            ))
+        }
+    };
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let serde_json::Value::Object(map) = item else {
+                return Err(Error::Merge(format!("JSON row {} is not an object", i + 1)));
+            };
+            let mut row = toml::Value::Table(toml::map::Map::new());
+            for (key, value) in map {
+                if let Some(value) = json_to_toml(value) {
+                    set_dotted(&mut row, &key, value);
+                }
+            }
+            Ok(row)
+        })
+        .collect()
+}
+
+/// Convert JSON to TOML, dropping nulls (TOML has no null)
+fn json_to_toml(value: serde_json::Value) -> Option<toml::Value> {
+    Some(match value {
+        serde_json::Value::Null => return None,
+        serde_json::Value::Bool(b) => toml::Value::Boolean(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64()?),
+        },
+        serde_json::Value::String(s) => toml::Value::String(s),
+        serde_json::Value::Array(items) => {
+            toml::Value::Array(items.into_iter().filter_map(json_to_toml).collect())
+        }
+        serde_json::Value::Object(map) => {
+            let mut table = toml::value::Table::new();
+            for (key, value) in map {
+                if let Some(value) = json_to_toml(value) {
+                    set_dotted_table(&mut table, &key, value);
+                }
+            }
+            toml::Value::Table(table)
+        }
+    })
+}
+
+fn set_dotted(target: &mut toml::Value, key: &str, value: toml::Value) {
+    if let toml::Value::Table(table) = target {
+        set_dotted_table(table, key, value);
+    }
+}
+
+fn set_dotted_table(table: &mut toml::value::Table, key: &str, value: toml::Value) {
+    match key.split_once('.') {
+        Some((head, rest)) if !head.is_empty() => {
+            let entry = table
+                .entry(head.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::value::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::value::Table::new());
+            }
+            set_dotted(entry, rest, value);
+        }
+        _ => match (table.get_mut(key), value) {
+            (Some(existing), value) if existing.is_table() && value.is_table() => {
+                deep_merge(existing, value)
+            }
+            (_, value) => {
+                table.insert(key.to_string(), value);
+            }
+        },
+    }
+}
+
+/// Merge `overlay` into `base`: tables are merged recursively, all other
+/// values (including arrays) are replaced.
+pub fn deep_merge<T: Tree>(base: &mut T, overlay: T) {
+    if !(base.is_table() && overlay.is_table()) {
+        *base = overlay;
+        return;
+    }
+    for (key, value) in overlay.into_entries() {
+        match base.child_mut(&key) {
+            Some(existing) if existing.is_table() && value.is_table() => {
+                deep_merge(existing, value)
+            }
+            _ => base.insert(key, value),
+        }
+    }
+}
+
+/// Nested TOML or JSON data that [`deep_merge`] can merge
+pub trait Tree: Sized {
+    /// Whether this is a table (TOML) or an object (JSON)
+    fn is_table(&self) -> bool;
+    /// Entries of a table; empty for other values
+    fn into_entries(self) -> Vec<(String, Self)>;
+    /// Entry of a table by key
+    fn child_mut(&mut self, key: &str) -> Option<&mut Self>;
+    /// Set an entry of a table; ignored for other values
+    fn insert(&mut self, key: String, value: Self);
+}
+
+impl Tree for toml::Value {
+    fn is_table(&self) -> bool {
+        self.is_table()
+    }
+
+    fn into_entries(self) -> Vec<(String, Self)> {
+        match self {
+            toml::Value::Table(table) => table.into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn child_mut(&mut self, key: &str) -> Option<&mut Self> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: String, value: Self) {
+        if let toml::Value::Table(table) = self {
+            table.insert(key, value);
+        }
+    }
+}
+
+impl Tree for serde_json::Value {
+    fn is_table(&self) -> bool {
+        self.is_object()
+    }
+
+    fn into_entries(self) -> Vec<(String, Self)> {
+        match self {
+            serde_json::Value::Object(object) => object.into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn child_mut(&mut self, key: &str) -> Option<&mut Self> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: String, value: Self) {
+        if let serde_json::Value::Object(object) = self {
+            object.insert(key, value);
+        }
+    }
+}
+
+/// Build the content file for one row.
+///
+/// The row is merged over the content data; `meta` is kept from the content
+/// file so that template resolution stays the same.
+pub fn merge_row(content: &ContentFile, row: &toml::Value) -> Result<ContentFile> {
+    let mut data = content.data.clone();
+    let mut row = row.clone();
+    if let toml::Value::Table(table) = &mut row {
+        table.remove("meta");
+    }
+    deep_merge(&mut data, row);
+
+    let text = toml::to_string(&data)?;
+    let mut merged = ContentFile::parse(content.path.clone(), &text)?;
+    merged.meta.resolved_template = content.meta.resolved_template.clone();
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"
+[meta]
+template = "letter.typ"
+
+[recipient]
+name = "Default"
+city = "Berlin"
+
+[letter]
+subject = "Hello"
+"#;
+
+    fn content() -> ContentFile {
+        ContentFile::parse(PathBuf::from("content.toml"), CONTENT).unwrap()
+    }
+
+    #[test]
+    fn test_load_csv_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.csv");
+        fs::write(
+            &path,
+            "recipient.name,recipient.city,letter.subject\nAda,,Invoice\n\"Grace, Dr.\",NYC,\n",
+        )
+        .unwrap();
+
+        let rows = load_rows(&path).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["recipient"]["name"].as_str(), Some("Ada"));
+        assert!(rows[0]["recipient"].get("city").is_none());
+        assert_eq!(rows[1]["recipient"]["name"].as_str(), Some("Grace, Dr."));
+        assert!(rows[1].get("letter").is_none());
+    }
+
+    #[test]
+    fn test_csv_rows_drive_computed_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.csv");
+        fs::write(
+            &path,
+            "quote.net,quote.vat_rate,quote.draft,recipient.zip,recipient.phone\n\
+             1000,0.19,false,01234,+49 30 1234\n",
+        )
+        .unwrap();
+        let rows = load_rows(&path).unwrap();
+        assert_eq!(rows[0]["quote"]["net"].as_integer(), Some(1000));
+        assert_eq!(rows[0]["quote"]["vat_rate"].as_float(), Some(0.19));
+        assert_eq!(rows[0]["quote"]["draft"].as_bool(), Some(false));
+        assert_eq!(rows[0]["recipient"]["zip"].as_str(), Some("01234"));
+        assert_eq!(rows[0]["recipient"]["phone"].as_str(), Some("+49 30 1234"));
+
+        let content = ContentFile::parse(
+            PathBuf::from("content.toml"),
+            &format!(
+                "{}\n[computed]\nvat = \"quote.net * quote.vat_rate\"\n",
+                CONTENT
+            ),
+        )
+        .unwrap();
+        let merged = merge_row(&content, &rows[0]).unwrap();
+        let mut data = crate::typst::toml_to_json(merged.as_toml()).unwrap();
+        crate::compute::evaluate_into(&merged, &mut data).unwrap();
+        assert_eq!(data["computed"]["vat"].as_f64(), Some(190.0));
+    }
+
+    #[test]
+    fn test_load_json_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.json");
+        fs::write(
+            &path,
+            r#"[{"recipient": {"name": "Ada"}, "letter.amount": 12.5, "note": null},
+                {"recipient.name": "Grace", "count": 3}]"#,
+        )
+        .unwrap();
+
+        let rows = load_rows(&path).unwrap();
+        assert_eq!(rows[0]["recipient"]["name"].as_str(), Some("Ada"));
+        assert_eq!(rows[0]["letter"]["amount"].as_float(), Some(12.5));
+        assert!(rows[0].get("note").is_none());
+        assert_eq!(rows[1]["count"].as_integer(), Some(3));
+
+        fs::write(&path, r#"{"name": "Ada"}"#).unwrap();
+        assert!(matches!(load_rows(&path), Err(Error::Merge(_))));
+    }
+
+    #[test]
+    fn test_merge_row_keeps_unset_values() {
+        let mut row = toml::Value::Table(toml::value::Table::new());
+        set_dotted(&mut row, "recipient.name", "Ada".into());
+        set_dotted(&mut row, "meta.template", "other.typ".into());
+
+        let merged = merge_row(&content(), &row).unwrap();
+        assert_eq!(merged.get("recipient.name").unwrap().as_str(), Some("Ada"));
+        assert_eq!(
+            merged.get("recipient.city").unwrap().as_str(),
+            Some("Berlin")
+        );
+        assert_eq!(merged.meta.template, "letter.typ");
+    }
+}
@@ -0,0 +1,460 @@
+//! Content file migration for tmpltr
+//!
+//! Updates an existing content file in place when its template gains, loses
+//! or renames editable fields and blocks, preserving values and comments.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::content::{write_block_data, BlockType, ContentBlock};
+use crate::error::{Error, Result};
+use crate::template::{DataAccess, TemplateInfo};
+
+/// Comment marker placed above keys the template no longer uses
+const OBSOLETE_MARKER: &str = "tmpltr: not used by template";
+
+/// Top-level keys that are never considered template data
+const RESERVED_KEYS: &[&str] = &["$schema", "meta", "brand", "blocks"];
+
+/// Result of migrating a content file to a newer template
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    /// Status ("ok" or "dry-run")
+    pub status: String,
+    /// Content file that was migrated
+    pub file: PathBuf,
+    /// Template the content was migrated to
+    pub template: PathBuf,
+    /// Template version recorded in the content file before migration
+    pub from_version: Option<String>,
+    /// Template version recorded after migration
+    pub to_version: Option<String>,
+    /// Fields added with their template defaults
+    pub added_fields: Vec<String>,
+    /// Blocks added with their template defaults
+    pub added_blocks: Vec<String>,
+    /// Keys present in the content file but unknown to the template (kept, flagged)
+    pub obsolete: Vec<String>,
+    /// Obsolete keys that were flagged by this run (not flagged before)
+    pub flagged: Vec<String>,
+    /// Number of template fields and blocks whose existing values were kept
+    pub unchanged: usize,
+}
+
+impl MigrationReport {
+    /// Whether the migration changed anything
+    pub fn has_changes(&self) -> bool {
+        !self.added_fields.is_empty()
+            || !self.added_blocks.is_empty()
+            || !self.flagged.is_empty()
+            || self.from_version != self.to_version
+    }
+}
+
+/// Migrate a parsed content document to match a template.
+///
+/// Existing values and comments are kept. Missing fields and blocks are added
+/// with their template defaults, keys the template does not know about are
+/// flagged with a comment (never deleted), and `meta.template_version` is
+/// bumped to the template version.
+///
+/// `data_access` lists additional paths the template reads (see
+/// [`TemplateInfo::extract_data_access`]) so that data consumed without an
+/// `editable()` marker is not reported as obsolete.
+pub fn migrate_document(
+    doc: &mut DocumentMut,
+    template: &TemplateInfo,
+    data_access: &[DataAccess],
+) -> Result<MigrationReport> {
+    let mut report = MigrationReport {
+        status: "ok".to_string(),
+        template: template.path.clone(),
+        ..Default::default()
+    };
+
+    let meta = doc
+        .get_mut("meta")
+        .and_then(Item::as_table_mut)
+        .ok_or_else(|| Error::Content("missing [meta] section in content file".to_string()))?;
+
+    report.from_version = meta
+        .get("template_version")
+        .and_then(Item::as_str)
+        .map(|s| s.to_string());
+
+    if meta.get("template_id").is_none() {
+        meta["template_id"] = toml_edit::value(template.id.as_str());
+    }
+
+    if let Some(ref version) = template.version {
+        meta["template_version"] = toml_edit::value(version.as_str());
+        report.to_version = Some(version.clone());
+    } else {
+        report.to_version = report.from_version.clone();
+    }
+
+    // Add missing fields
+    for field in &template.fields {
+        if path_exists(doc.as_table(), &field.path) {
+            report.unchanged += 1;
+            continue;
+        }
+        insert_at_path(
+            doc.as_table_mut(),
+            &field.path,
+            toml_edit::value(scalar_to_edit(field.default_value())),
+        )?;
+        report.added_fields.push(field.path.clone());
+    }
+
+    // Add missing blocks
+    for block in &template.blocks {
+        let name = block.path.strip_prefix("blocks.").unwrap_or(&block.path);
+        let blocks = ensure_table(doc.as_table_mut(), "blocks", &block.path)?;
+        if blocks.contains_key(name) {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let title = block.title.clone().unwrap_or_else(|| name.to_string());
+        let mut table = Table::new();
+        table["title"] = toml_edit::value(title.clone());
+        if block.block_type == BlockType::Text {
+            table["format"] = toml_edit::value(block.format.as_str());
+            table["content"] = toml_edit::value(block.default_content.clone().unwrap_or_default());
+        } else {
+            let skeleton = ContentBlock::skeleton(
+                title,
+                block.block_type,
+                &block.fields,
+                block.default_content.as_deref(),
+            );
+            table["type"] = toml_edit::value(block.block_type.as_str());
+            write_block_data(&mut table, &skeleton.data());
+        }
+        blocks.insert(name, Item::Table(table));
+        report.added_blocks.push(block.path.clone());
+    }
+
+    // Flag keys the template no longer knows about
+    let known: BTreeSet<String> = template
+        .fields
+        .iter()
+        .map(|f| f.path.clone())
+        .chain(template.blocks.iter().map(|b| b.path.clone()))
+        .chain(data_access.iter().map(|a| a.path.clone()))
+        .collect();
+
+    let marker = format!("{} '{}'", OBSOLETE_MARKER, template.id);
+    let mut obsolete = Vec::new();
+    let mut flagged = Vec::new();
+    let root = doc.as_table_mut();
+
+    let data_keys: Vec<String> = root
+        .iter()
+        .map(|(k, _)| k.to_string())
+        .filter(|k| !RESERVED_KEYS.contains(&k.as_str()))
+        .collect();
+    for key in data_keys {
+        flag_unknown(
+            root,
+            &key,
+            &key,
+            &known,
+            &marker,
+            &mut obsolete,
+            &mut flagged,
+        );
+    }
+
+    if let Some(blocks) = root.get_mut("blocks").and_then(Item::as_table_mut) {
+        let names: Vec<String> = blocks.iter().map(|(k, _)| k.to_string()).collect();
+        for name in names {
+            let path = format!("blocks.{}", name);
+            if !is_known(&path, &known) {
+                if flag_key(blocks, &name, &marker) {
+                    flagged.push(path.clone());
+                }
+                obsolete.push(path);
+            }
+        }
+    }
+
+    report.obsolete = obsolete;
+    report.flagged = flagged;
+    Ok(report)
+}
+
+/// Check whether a dotted path exists in a table
+fn path_exists(table: &Table, path: &str) -> bool {
+    let mut current: &dyn toml_edit::TableLike = table;
+    let parts: Vec<&str> = path.split('.').collect();
+    for (i, part) in parts.iter().enumerate() {
+        let Some(item) = current.get(part) else {
+            return false;
+        };
+        if i == parts.len() - 1 {
+            return true;
+        }
+        match item.as_table_like() {
+            Some(next) => current = next,
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Insert a value at a dotted path, creating intermediate tables as needed
+fn insert_at_path(root: &mut Table, path: &str, value: Item) -> Result<()> {
+    let parts: Vec<&str> = path.split('.').collect();
+    let (last, parents) = parts.split_last().ok_or_else(|| Error::PathNotFound {
+        path: path.to_string(),
+    })?;
+
+    let mut current = root;
+    for part in parents {
+        current = ensure_table(current, part, path)?;
+    }
+    current.insert(last, value);
+    Ok(())
+}
+
+/// Convert a scalar TOML value into its editable counterpart
+fn scalar_to_edit(value: toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(d) => d.into(),
+        other => other.as_str().unwrap_or_default().into(),
+    }
+}
+
+/// Get a child table, creating it if missing
+fn ensure_table<'a>(table: &'a mut Table, key: &str, path: &str) -> Result<&'a mut Table> {
+    if !table.contains_key(key) {
+        let mut child = Table::new();
+        child.set_implicit(true);
+        table.insert(key, Item::Table(child));
+    }
+    table
+        .get_mut(key)
+        .and_then(Item::as_table_mut)
+        .ok_or_else(|| {
+            Error::Content(format!(
+                "cannot add '{}': '{}' is not a table in the content file",
+                path, key
+            ))
+        })
+}
+
+/// Whether a content path is used by the template
+///
+/// A path is known if the template accesses it directly, accesses one of its
+/// parents (e.g. the whole `quote.client` table), or one of its children.
+fn is_known(path: &str, known: &BTreeSet<String>) -> bool {
+    known.iter().any(|k| {
+        k == path || k.starts_with(&format!("{}.", path)) || path.starts_with(&format!("{}.", k))
+    })
+}
+
+/// Recursively flag keys that are not known to the template
+fn flag_unknown(
+    parent: &mut Table,
+    key: &str,
+    path: &str,
+    known: &BTreeSet<String>,
+    marker: &str,
+    obsolete: &mut Vec<String>,
+    flagged: &mut Vec<String>,
+) {
+    if !is_known(path, known) {
+        if flag_key(parent, key, marker) {
+            flagged.push(path.to_string());
+        }
+        obsolete.push(path.to_string());
+        return;
+    }
+
+    // Fully known (exact or parent accessed): nothing to descend into
+    if known
+        .iter()
+        .any(|k| k == path || path.starts_with(&format!("{}.", k)))
+    {
+        return;
+    }
+
+    if let Some(table) = parent.get_mut(key).and_then(Item::as_table_mut) {
+        let children: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+        for child in children {
+            let child_path = format!("{}.{}", path, child);
+            flag_unknown(table, &child, &child_path, known, marker, obsolete, flagged);
+        }
+    }
+}
+
+/// Add an obsolete marker comment above a key (idempotent). Returns whether
+/// the marker was added.
+fn flag_key(parent: &mut Table, key: &str, marker: &str) -> bool {
+    if let Some(table) = parent.get_mut(key).and_then(Item::as_table_mut) {
+        if !table.is_implicit() {
+            let decor = table.decor_mut();
+            let existing = decor
+                .prefix()
+                .and_then(|p| p.as_str())
+                .unwrap_or("\n")
+                .to_string();
+            if existing.contains(OBSOLETE_MARKER) {
+                return false;
+            }
+            decor.set_prefix(format!("{}# {}\n", existing, marker));
+            return true;
+        }
+    }
+
+    if let Some(mut key_mut) = parent.key_mut(key) {
+        let decor = key_mut.leaf_decor_mut();
+        let existing = decor
+            .prefix()
+            .and_then(|p| p.as_str())
+            .unwrap_or("")
+            .to_string();
+        if existing.contains(OBSOLETE_MARKER) {
+            return false;
+        }
+        decor.set_prefix(format!("{}# {}\n", existing, marker));
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"
+// @version: 2.0.0
+#editable("quote.title", type: "text", default: "Untitled")
+#editable("quote.client.email", type: "text", default: "client@example.com")
+
+#editable-block("blocks.intro", title: "Introduction", format: "markdown")[Intro]
+#editable-block("blocks.summary", title: "Summary", format: "markdown")[Summary text]
+"#;
+
+    const CONTENT: &str = r#"[meta]
+template = "quote.typ"
+template_version = "1.0.0"
+
+# The project title
+[quote]
+title = "Death Star" # keep me
+legacy_number = "42"
+
+[blocks.intro]
+title = "Introduction"
+format = "markdown"
+content = "Existing intro"
+
+[blocks.removed]
+title = "Removed"
+content = "Old text"
+"#;
+
+    fn migrate(content: &str) -> (DocumentMut, MigrationReport) {
+        let template = TemplateInfo::parse_content(PathBuf::from("quote.typ"), TEMPLATE).unwrap();
+        let mut doc: DocumentMut = content.parse().unwrap();
+        let report = migrate_document(&mut doc, &template, &[]).unwrap();
+        (doc, report)
+    }
+
+    #[test]
+    fn test_migration_keeps_values_and_comments() {
+        let (doc, report) = migrate(CONTENT);
+        let out = doc.to_string();
+
+        assert!(out.contains("# The project title"));
+        assert!(out.contains("title = \"Death Star\" # keep me"));
+        assert!(out.contains("content = \"Existing intro\""));
+        assert_eq!(report.unchanged, 2);
+    }
+
+    #[test]
+    fn test_migration_adds_fields_and_blocks() {
+        let (doc, report) = migrate(CONTENT);
+
+        assert_eq!(report.added_fields, vec!["quote.client.email"]);
+        assert_eq!(report.added_blocks, vec!["blocks.summary"]);
+        assert_eq!(
+            doc["quote"]["client"]["email"].as_str(),
+            Some("client@example.com")
+        );
+        assert_eq!(
+            doc["blocks"]["summary"]["content"].as_str(),
+            Some("Summary text")
+        );
+    }
+
+    #[test]
+    fn test_migration_adds_structured_blocks() {
+        let template = TemplateInfo::parse_content(
+            PathBuf::from("quote.typ"),
+            r#"#editable-block("blocks.items", type: "records", fields: ("description", "qty"))[]"#,
+        )
+        .unwrap();
+        let mut doc: DocumentMut = CONTENT.parse().unwrap();
+        migrate_document(&mut doc, &template, &[]).unwrap();
+
+        let items = &doc["blocks"]["items"];
+        assert_eq!(items["type"].as_str(), Some("records"));
+        assert_eq!(items["fields"].as_array().map(|a| a.len()), Some(2));
+        assert!(items.get("content").is_none());
+    }
+
+    #[test]
+    fn test_migration_flags_obsolete_keys() {
+        let (doc, report) = migrate(CONTENT);
+        let out = doc.to_string();
+
+        assert_eq!(
+            report.obsolete,
+            vec!["quote.legacy_number", "blocks.removed"]
+        );
+        assert!(out.contains("legacy_number = \"42\""));
+        assert!(out.contains("[blocks.removed]"));
+        assert_eq!(out.matches(OBSOLETE_MARKER).count(), 2);
+
+        assert_eq!(report.flagged, report.obsolete);
+
+        // Running again must not stack markers or count as a change
+        let (doc, report) = migrate(&out);
+        assert_eq!(doc.to_string().matches(OBSOLETE_MARKER).count(), 2);
+        assert_eq!(report.obsolete.len(), 2);
+        assert!(report.flagged.is_empty());
+        assert!(!report.has_changes());
+    }
+
+    #[test]
+    fn test_migration_bumps_version() {
+        let (doc, report) = migrate(CONTENT);
+
+        assert_eq!(report.from_version.as_deref(), Some("1.0.0"));
+        assert_eq!(report.to_version.as_deref(), Some("2.0.0"));
+        assert_eq!(doc["meta"]["template_version"].as_str(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn test_data_access_paths_are_not_obsolete() {
+        let template = TemplateInfo::parse_content(PathBuf::from("quote.typ"), TEMPLATE).unwrap();
+        let mut doc: DocumentMut = CONTENT.parse().unwrap();
+        let access = vec![DataAccess {
+            path: "quote.legacy_number".to_string(),
+            default: None,
+        }];
+        let report = migrate_document(&mut doc, &template, &access).unwrap();
+
+        assert_eq!(report.obsolete, vec!["blocks.removed"]);
+    }
+}
@@ -0,0 +1,370 @@
+//! Output path templating
+//!
+//! Output paths may contain `{placeholders}` that are resolved against the
+//! content file, e.g. `{quote.number}-{meta.template_id}-{date:%Y%m%d}.{ext}`.
+
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::config::OutputConfig;
+use crate::content::ContentFile;
+use crate::error::{Error, Result};
+use crate::typst::OutputFormat;
+
+/// Pattern used when neither `-o`, `meta.output` nor the config set one
+pub const DEFAULT_FILENAME_PATTERN: &str = "{stem}.{ext}";
+
+/// Page placeholders understood by `typst compile` for multi-page output
+const TYPST_PAGE_PLACEHOLDERS: &[&str] = &["p", "0p", "t"];
+
+/// Date format for a bare `{date}` placeholder
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Resolve the output path for compiling `content`.
+///
+/// Precedence: explicit `-o` pattern, then `meta.output` (relative to the
+/// content file), then `[output] filename_pattern` from the config.
+pub fn resolve_output_path(
+    content: &ContentFile,
+    explicit: Option<&Path>,
+    config: &OutputConfig,
+    format: Option<OutputFormat>,
+) -> Result<PathBuf> {
+    let ext = extension(format, config);
+
+    if let Some(path) = explicit {
+        return expand_explicit(&path.to_string_lossy(), content, ext);
+    }
+
+    if let Some(pattern) = content.meta.output.as_deref() {
+        let path = expand(pattern, content, ext, &[])?;
+        if path.is_relative() {
+            let dir = content.path.parent().unwrap_or(Path::new(""));
+            return Ok(dir.join(path));
+        }
+        return Ok(path);
+    }
+
+    let pattern = if config.filename_pattern.trim().is_empty() {
+        DEFAULT_FILENAME_PATTERN
+    } else {
+        &config.filename_pattern
+    };
+    expand(pattern, content, ext, &[])
+}
+
+/// Adapt a resolved path to `format` when compiling several formats at once:
+/// the extension is replaced and paged formats get a `-{p}` page suffix.
+pub fn path_for_format(path: &Path, format: OutputFormat) -> PathBuf {
+    let ext = format.typst_format();
+    let mut path = path.with_extension(ext);
+    let name = path.to_string_lossy();
+    if format.is_paged() && !name.contains("{p}") && !name.contains("{0p}") {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "output".to_string());
+        path.set_file_name(format!("{}-{{p}}.{}", stem, ext));
+    }
+    path
+}
+
+/// Extension for `{ext}`: the requested format, else the configured default
+pub fn extension(format: Option<OutputFormat>, config: &OutputConfig) -> &'static str {
+    format
+        .or_else(|| OutputFormat::from_str(&config.format))
+        .unwrap_or_default()
+        .typst_format()
+}
+
+/// Expand every `{placeholder}` in `pattern`.
+///
+/// Built-in placeholders are `{ext}`, `{stem}` (content file name without
+/// extension) and `{date}` / `{date:<strftime>}`; `extra` adds caller-specific
+/// ones such as `{index}`. Anything else is a dotted path resolved through
+/// [`ContentFile::get`]. Substituted values are sanitized so they cannot
+/// introduce path separators; `{{` and `}}` produce literal braces. Typst's
+/// page placeholders (`{p}`, `{0p}`, `{t}`) are left for `typst compile`.
+pub fn expand(
+    pattern: &str,
+    content: &ContentFile,
+    ext: &str,
+    extra: &[(&str, String)],
+) -> Result<PathBuf> {
+    expand_pattern(pattern, content, ext, extra, false)
+}
+
+/// Expand an explicit `-o` path.
+///
+/// Unlike [`expand`], braces that don't form a placeholder (no closing `}`,
+/// or something other than a built-in or dotted key inside) are kept
+/// literally, so ordinary file names containing `{` still work. A dotted key
+/// without a value is an error, as in [`expand`].
+pub fn expand_explicit(path: &str, content: &ContentFile, ext: &str) -> Result<PathBuf> {
+    expand_pattern(path, content, ext, &[], true)
+}
+
+fn expand_pattern(
+    pattern: &str,
+    content: &ContentFile,
+    ext: &str,
+    extra: &[(&str, String)],
+    keep_unknown: bool,
+) -> Result<PathBuf> {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let rest = &pattern[i + 1..];
+                let Some(end) = rest.find('}') else {
+                    if keep_unknown {
+                        out.push(c);
+                        continue;
+                    }
+                    return Err(Error::Config(format!(
+                        "unclosed '{{' in output pattern '{}'",
+                        pattern
+                    )));
+                };
+                let key = rest[..end].trim();
+                if TYPST_PAGE_PLACEHOLDERS.contains(&key)
+                    || (keep_unknown && !is_dotted_key(key) && !is_known(key, content, extra))
+                {
+                    out.push_str(&pattern[i..i + end + 2]);
+                } else {
+                    let value = placeholder(key, content, ext, extra)?;
+                    out.push_str(&sanitize(&value));
+                }
+                while chars.peek().is_some_and(|&(j, _)| j <= i + 1 + end) {
+                    chars.next();
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    Ok(PathBuf::from(out))
+}
+
+/// Whether `key` names a placeholder that [`placeholder`] can resolve
+fn is_known(key: &str, content: &ContentFile, extra: &[(&str, String)]) -> bool {
+    let name = key.split_once(':').map_or(key, |(name, _)| name.trim());
+    extra.iter().any(|(n, _)| *n == key)
+        || matches!(name, "ext" | "stem" | "date")
+        || content.get(key).is_some()
+}
+
+/// Whether `key` looks like a content path such as `quote.number`
+fn is_dotted_key(key: &str) -> bool {
+    key.contains('.')
+        && key.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && part
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+fn placeholder(
+    key: &str,
+    content: &ContentFile,
+    ext: &str,
+    extra: &[(&str, String)],
+) -> Result<String> {
+    if let Some((_, value)) = extra.iter().find(|(name, _)| *name == key) {
+        return Ok(value.clone());
+    }
+
+    let (name, arg) = match key.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg)),
+        None => (key, None),
+    };
+
+    match name {
+        "ext" => return Ok(ext.to_string()),
+        "stem" => {
+            return Ok(content
+                .path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("output")
+                .to_string())
+        }
+        "date" => return format_date(arg.unwrap_or(DEFAULT_DATE_FORMAT)),
+        _ => {}
+    }
+
+    match content.get(key) {
+        Some(toml::Value::String(s)) => Ok(s.clone()),
+        Some(toml::Value::Integer(i)) => Ok(i.to_string()),
+        Some(toml::Value::Float(f)) => Ok(f.to_string()),
+        Some(toml::Value::Boolean(b)) => Ok(b.to_string()),
+        Some(toml::Value::Datetime(d)) => Ok(d.to_string()),
+        Some(_) => Err(Error::Content(format!(
+            "'{}' in output pattern is not a scalar value",
+            key
+        ))),
+        None => Err(Error::Content(format!(
+            "'{}' in output pattern has no value",
+            key
+        ))),
+    }
+}
+
+fn format_date(format: &str) -> Result<String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(Error::Config(format!(
+            "invalid date format '{}' in output pattern",
+            format
+        )));
+    }
+    Ok(chrono::Local::now()
+        .format_with_items(items.into_iter())
+        .to_string())
+}
+
+/// Replace characters that are unsafe in file names
+pub fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match cleaned.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => cleaned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content() -> ContentFile {
+        ContentFile::parse(
+            PathBuf::from("docs/angebot.toml"),
+            r#"
+[meta]
+template = "angebot.typ"
+template_id = "byteowlz-angebot"
+
+[quote]
+angebot_nr = "2025/001"
+client = "ACME: Sales"
+total = 1200
+items = ["a"]
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let content = content();
+        let path = expand(
+            "out/{quote.angebot_nr}-{meta.template_id}-{date:%Y}.{ext}",
+            &content,
+            "svg",
+            &[],
+        )
+        .unwrap();
+        let year = chrono::Local::now().format("%Y").to_string();
+        assert_eq!(
+            path,
+            PathBuf::from(format!("out/2025_001-byteowlz-angebot-{}.svg", year))
+        );
+
+        let path = expand(
+            "{stem} {quote.client} {{x}} {n}",
+            &content,
+            "pdf",
+            &[("n", "7".into())],
+        );
+        assert_eq!(path.unwrap(), PathBuf::from("angebot ACME_ Sales {x} 7"));
+
+        let path = expand("{stem}-{p}.{ext}", &content, "svg", &[]);
+        assert_eq!(path.unwrap(), PathBuf::from("angebot-{p}.svg"));
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let content = content();
+        assert!(expand("{missing}.pdf", &content, "pdf", &[]).is_err());
+        assert!(expand("{quote.items}.pdf", &content, "pdf", &[]).is_err());
+        assert!(expand("{quote.total.pdf", &content, "pdf", &[]).is_err());
+        assert!(expand("{date:%Q}.pdf", &content, "pdf", &[]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_output_path_precedence() {
+        let mut config = OutputConfig::default();
+        let mut content = content();
+
+        let path = resolve_output_path(&content, None, &config, None).unwrap();
+        assert_eq!(path, PathBuf::from("angebot.pdf"));
+
+        config.filename_pattern = "{quote.total}.{ext}".to_string();
+        let path = resolve_output_path(&content, None, &config, Some(OutputFormat::Svg));
+        assert_eq!(path.unwrap(), PathBuf::from("1200.svg"));
+
+        content.meta.output = Some("build/{stem}.{ext}".to_string());
+        let path = resolve_output_path(&content, None, &config, None).unwrap();
+        assert_eq!(path, PathBuf::from("docs/build/angebot.pdf"));
+
+        let path = resolve_output_path(&content, Some(Path::new("x.pdf")), &config, None);
+        assert_eq!(path.unwrap(), PathBuf::from("x.pdf"));
+    }
+
+    #[test]
+    fn test_explicit_path_keeps_unknown_braces() {
+        let content = content();
+        let path = expand_explicit("out/{draft}-{quote.total}.{ext}", &content, "pdf");
+        assert_eq!(path.unwrap(), PathBuf::from("out/{draft}-1200.pdf"));
+
+        let path = expand_explicit("report {v2.pdf", &content, "pdf");
+        assert_eq!(path.unwrap(), PathBuf::from("report {v2.pdf"));
+
+        let path = expand_explicit("report {v1.2}.pdf", &content, "pdf");
+        assert_eq!(path.unwrap(), PathBuf::from("report {v1.2}.pdf"));
+
+        // Known placeholders and mistyped content paths still fail loudly
+        assert!(expand_explicit("{quote.items}.pdf", &content, "pdf").is_err());
+        let err = expand_explicit("{quote.angbot_nr}.pdf", &content, "pdf").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'quote.angbot_nr' in output pattern has no value"));
+    }
+
+    #[test]
+    fn test_path_for_format() {
+        let path = Path::new("out/quote.pdf");
+        assert_eq!(
+            path_for_format(path, OutputFormat::Pdf),
+            PathBuf::from("out/quote.pdf")
+        );
+        assert_eq!(
+            path_for_format(path, OutputFormat::Png),
+            PathBuf::from("out/quote-{p}.png")
+        );
+        assert_eq!(
+            path_for_format(Path::new("page-{0p}.svg"), OutputFormat::Svg),
+            PathBuf::from("page-{0p}.svg")
+        );
+    }
+}
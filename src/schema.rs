@@ -0,0 +1,255 @@
+//! JSON Schema validation for content files
+//!
+//! Validates content data against a JSON schema and maps each violation back
+//! to its dotted TOML path and line number in the content file.
+
+use std::path::Path;
+
+use serde::Serialize;
+use toml_edit::{ImDocument, Item, Value};
+
+use crate::error::{Error, Result};
+
+/// A single schema violation in a content file
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// TOML path of the offending value (e.g., "quote.items[0].price")
+    pub path: String,
+    /// 1-based line number in the content file (if it could be located)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl SchemaViolation {
+    /// Create a violation, locating `path` in the TOML source
+    pub fn at(source: &str, segments: &[String], message: impl Into<String>) -> Self {
+        Self {
+            path: segments_to_path(segments),
+            line: locate_line(source, segments),
+            message: message.into(),
+        }
+    }
+}
+
+/// Load a JSON schema from disk
+pub fn load_schema(path: &Path) -> Result<serde_json::Value> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            Error::FileNotFound {
+                path: path.to_path_buf(),
+            }
+        } else {
+            Error::Io(e)
+        }
+    })?;
+    serde_json::from_str(&content)
+        .map_err(|e| Error::Validation(format!("invalid schema {}: {}", path.display(), e)))
+}
+
+/// Validate content data against a JSON schema.
+///
+/// `source` is the raw TOML text the data was parsed from; it is used to
+/// attach line numbers to each violation.
+pub fn validate_against_schema(
+    source: &str,
+    data: &serde_json::Value,
+    schema: &serde_json::Value,
+) -> Result<Vec<SchemaViolation>> {
+    let validator = jsonschema::options()
+        .should_validate_formats(true)
+        .build(schema)
+        .map_err(|e| Error::Validation(format!("invalid schema: {}", e)))?;
+
+    let violations = validator
+        .iter_errors(data)
+        .map(|err| {
+            let segments = pointer_segments(err.instance_path.as_str());
+            SchemaViolation::at(source, &segments, err.to_string())
+        })
+        .collect();
+
+    Ok(violations)
+}
+
+/// Split a JSON pointer ("/quote/items/0") into unescaped segments
+fn pointer_segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Render path segments as a TOML-style path ("quote.items[0].price")
+fn segments_to_path(segments: &[String]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        if segment.parse::<usize>().is_ok() && !path.is_empty() {
+            path.push_str(&format!("[{}]", segment));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(segment);
+        }
+    }
+    if path.is_empty() {
+        "(root)".to_string()
+    } else {
+        path
+    }
+}
+
+/// Find the line of the deepest existing node along `segments`
+pub(crate) fn locate_line(source: &str, segments: &[String]) -> Option<usize> {
+    let doc = ImDocument::parse(source).ok()?;
+    let mut span = None;
+    let mut current = Node::Item(doc.as_item());
+
+    for segment in segments {
+        let next = match current {
+            Node::Item(Item::Table(table)) => {
+                table.get_key_value(segment).map(|(k, v)| (k.span(), v))
+            }
+            Node::Item(Item::Value(Value::InlineTable(table)))
+            | Node::Value(Value::InlineTable(table)) => {
+                table.get_key_value(segment).map(|(k, v)| (k.span(), v))
+            }
+            Node::Item(Item::ArrayOfTables(array)) => {
+                let table = segment.parse().ok().and_then(|i: usize| array.get(i));
+                match table {
+                    Some(t) => {
+                        span = t.span().or(span);
+                        current = Node::Table(t);
+                        continue;
+                    }
+                    None => None,
+                }
+            }
+            Node::Item(Item::Value(Value::Array(array))) | Node::Value(Value::Array(array)) => {
+                let value = segment.parse().ok().and_then(|i: usize| array.get(i));
+                match value {
+                    Some(v) => {
+                        span = v.span().or(span);
+                        current = Node::Value(v);
+                        continue;
+                    }
+                    None => None,
+                }
+            }
+            Node::Table(table) => table.get_key_value(segment).map(|(k, v)| (k.span(), v)),
+            _ => None,
+        };
+
+        match next {
+            Some((key_span, item)) => {
+                span = key_span.or_else(|| item.span()).or(span);
+                current = Node::Item(item);
+            }
+            None => break,
+        }
+    }
+
+    span.map(|range| source[..range.start].matches('\n').count() + 1)
+}
+
+/// Cursor into a TOML document while walking a path
+enum Node<'a> {
+    Item(&'a Item),
+    Table(&'a toml_edit::Table),
+    Value(&'a Value),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"[meta]
+template = "quote.typ"
+
+[quote]
+title = 42
+
+[[quote.items]]
+price = "free"
+
+[[quote.items]]
+price = 10
+"#;
+
+    fn data() -> serde_json::Value {
+        serde_json::json!({
+            "meta": { "template": "quote.typ" },
+            "quote": {
+                "title": 42,
+                "items": [{ "price": "free" }, { "price": 10 }]
+            }
+        })
+    }
+
+    #[test]
+    fn test_pointer_to_path() {
+        let segments = pointer_segments("/quote/items/0/price");
+        assert_eq!(segments_to_path(&segments), "quote.items[0].price");
+        assert_eq!(segments_to_path(&pointer_segments("")), "(root)");
+        assert_eq!(pointer_segments("/a~1b/c~0d"), vec!["a/b", "c~d"]);
+    }
+
+    #[test]
+    fn test_locate_line() {
+        let seg = |p: &str| pointer_segments(p);
+        assert_eq!(locate_line(SOURCE, &seg("/quote/title")), Some(5));
+        assert_eq!(locate_line(SOURCE, &seg("/quote/items/1/price")), Some(11));
+        // Missing keys fall back to the closest existing parent
+        assert_eq!(locate_line(SOURCE, &seg("/quote/missing")), Some(4));
+    }
+
+    #[test]
+    fn test_validate_reports_paths_and_lines() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["meta", "quote"],
+            "properties": {
+                "quote": {
+                    "type": "object",
+                    "required": ["number"],
+                    "properties": {
+                        "title": { "type": "string" },
+                        "items": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": { "price": { "type": "number" } }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let mut violations = validate_against_schema(SOURCE, &data(), &schema).unwrap();
+        violations.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let found: Vec<_> = violations
+            .iter()
+            .map(|v| (v.path.as_str(), v.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("quote", Some(4)),
+                ("quote.items[0].price", Some(8)),
+                ("quote.title", Some(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_schema_is_validation_error() {
+        let schema = serde_json::json!({ "type": 12 });
+        let err = validate_against_schema(SOURCE, &data(), &schema).unwrap_err();
+        assert_eq!(err.kind(), "validation_error");
+    }
+}
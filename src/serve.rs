@@ -0,0 +1,355 @@
+//! Live preview server for watch mode
+//!
+//! Serves the latest compile output on an auto-reloading page. The page
+//! listens on `/events` (server-sent events) and refreshes after every
+//! recompile; compile errors are shown as an overlay.
+
+use std::fs;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+use crate::error::{Error, Result};
+use crate::typst::CompileResult;
+
+/// Default address for `watch --serve`
+pub const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+
+/// What the preview page should currently show
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreviewState {
+    /// Incremented on every publish; used for cache busting
+    pub version: u64,
+    /// Output format ("svg", "pdf" or "html")
+    pub format: String,
+    /// Number of files available under `/page/<n>` (1-based)
+    pub pages: usize,
+    /// Compile error from the last build, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Files backing each page
+    #[serde(skip)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<PreviewState>,
+    subscribers: Mutex<Vec<mpsc::Sender<String>>>,
+}
+
+/// Handle to a running preview server
+pub struct PreviewServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl PreviewServer {
+    /// Bind to `addr` and serve requests on a background thread
+    pub fn start(addr: &str) -> Result<Self> {
+        let server = Server::http(addr)
+            .map_err(|e| Error::Watch(format!("starting preview server on {}: {}", addr, e)))?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| Error::Watch(format!("preview server address {} is not TCP", addr)))?;
+
+        let shared = Arc::new(Shared::default());
+        let handler = Arc::clone(&shared);
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let shared = Arc::clone(&handler);
+                thread::spawn(move || handle_request(&shared, request));
+            }
+        });
+
+        Ok(Self { addr, shared })
+    }
+
+    /// URL of the preview page
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// Show a successful compile result
+    pub fn publish(&self, result: &CompileResult) {
+        let files = match (&result.pages, &result.output) {
+            (Some(pages), _) => pages.iter().map(|p| p.file.clone()).collect(),
+            (None, Some(output)) => vec![output.clone()],
+            (None, None) => Vec::new(),
+        };
+        self.update(|state| {
+            state.format = result.format.clone();
+            state.pages = files.len();
+            state.files = files;
+            state.error = None;
+        });
+    }
+
+    /// Show a compile error on top of the last good output
+    pub fn publish_error(&self, message: &str) {
+        self.update(|state| state.error = Some(message.to_string()));
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut PreviewState)) {
+        let event = {
+            let mut state = lock(&self.shared.state);
+            apply(&mut state);
+            state.version += 1;
+            serde_json::to_string(&*state).unwrap_or_default()
+        };
+        lock(&self.shared.subscribers).retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn handle_request(shared: &Shared, request: Request) {
+    if *request.method() != Method::Get {
+        respond(request, text(405, "method not allowed"));
+        return;
+    }
+
+    let path = request.url().split('?').next().unwrap_or("/").to_string();
+    match path.as_str() {
+        "/" | "/index.html" => respond(request, body(200, "text/html; charset=utf-8", INDEX_HTML)),
+        "/state" => {
+            let json = serde_json::to_string(&*lock(&shared.state)).unwrap_or_default();
+            respond(request, body(200, "application/json", json));
+        }
+        "/events" => stream_events(shared, request),
+        _ => match path
+            .strip_prefix("/page/")
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            Some(page) => serve_page(shared, request, page),
+            None => respond(request, text(404, "not found")),
+        },
+    }
+}
+
+fn serve_page(shared: &Shared, request: Request, page: usize) {
+    let file = {
+        let state = lock(&shared.state);
+        page.checked_sub(1)
+            .and_then(|i| state.files.get(i))
+            .cloned()
+    };
+    let Some(file) = file else {
+        respond(request, text(404, "no such page"));
+        return;
+    };
+    match fs::read(&file) {
+        Ok(bytes) => respond(request, body(200, content_type(&file), bytes)),
+        Err(e) => respond(
+            request,
+            text(500, &format!("reading {}: {}", file.display(), e)),
+        ),
+    }
+}
+
+/// Keep the connection open and push a message after every publish
+fn stream_events(shared: &Shared, request: Request) {
+    let (tx, rx) = mpsc::channel();
+    lock(&shared.subscribers).push(tx);
+
+    let mut writer = request.into_writer();
+    let header = "HTTP/1.1 200 OK\r\n\
+                  Content-Type: text/event-stream\r\n\
+                  Cache-Control: no-cache\r\n\
+                  Connection: keep-alive\r\n\r\n\
+                  retry: 1000\n\n";
+    if writer
+        .write_all(header.as_bytes())
+        .and_then(|_| writer.flush())
+        .is_err()
+    {
+        return;
+    }
+
+    for event in rx {
+        let message = format!("event: update\ndata: {}\n\n", event);
+        if writer
+            .write_all(message.as_bytes())
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn respond<R: std::io::Read>(request: Request, response: Response<R>) {
+    if let Err(e) = request.respond(response) {
+        log::debug!("preview client went away: {}", e);
+    }
+}
+
+fn body(
+    status: u16,
+    content_type: &str,
+    data: impl Into<Vec<u8>>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let mut response = Response::from_data(data.into()).with_status_code(StatusCode(status));
+    if let Ok(header) = Header::from_bytes("Content-Type", content_type) {
+        response.add_header(header);
+    }
+    if let Ok(header) = Header::from_bytes("Cache-Control", "no-store") {
+        response.add_header(header);
+    }
+    response
+}
+
+fn text(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    body(status, "text/plain; charset=utf-8", message)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("png") => "image/png",
+        Some("html") => "text/html; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>tmpltr preview</title>
+<style>
+  body { margin: 0; background: #525659; font-family: sans-serif; }
+  #pages { display: flex; flex-direction: column; align-items: center; gap: 16px; padding: 16px; }
+  #pages img { background: white; box-shadow: 0 2px 8px rgba(0,0,0,.4); max-width: 100%; }
+  #pages iframe { border: 0; width: 100vw; height: 100vh; background: white; }
+  #error { display: none; position: fixed; inset: 0; background: rgba(20,0,0,.85); color: #ffd7d7;
+           padding: 24px; overflow: auto; white-space: pre-wrap; font: 14px/1.4 monospace; }
+  #error.visible { display: block; }
+</style>
+</head>
+<body>
+<div id="pages"></div>
+<div id="error"></div>
+<script>
+  const pages = document.getElementById("pages");
+  const error = document.getElementById("error");
+
+  function render(state) {
+    error.textContent = state.error || "";
+    error.classList.toggle("visible", !!state.error);
+    if (state.error) return;
+    pages.innerHTML = "";
+    for (let n = 1; n <= state.pages; n++) {
+      const src = "/page/" + n + "?v=" + state.version;
+      const el = document.createElement(state.format === "svg" || state.format === "png" ? "img" : "iframe");
+      el.src = src;
+      pages.appendChild(el);
+    }
+  }
+
+  fetch("/state").then(r => r.json()).then(render);
+  const events = new EventSource("/events");
+  events.addEventListener("update", e => render(JSON.parse(e.data)));
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typst::PageInfo;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn svg_result(dir: &Path) -> CompileResult {
+        let file = dir.join("page-1.svg");
+        fs::write(&file, "<svg/>").unwrap();
+        CompileResult {
+            status: "ok".to_string(),
+            format: "svg".to_string(),
+            output: None,
+            pages: Some(vec![PageInfo { page: 1, file }]),
+            positions: None,
+            cached: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_serves_index_state_and_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = PreviewServer::start("127.0.0.1:0").unwrap();
+        server.publish(&svg_result(dir.path()));
+
+        assert!(get(server.addr, "/").contains("EventSource"));
+
+        let state = get(server.addr, "/state");
+        assert!(state.contains(r#""format":"svg""#), "{}", state);
+        assert!(state.contains(r#""pages":1"#), "{}", state);
+
+        let page = get(server.addr, "/page/1");
+        assert!(page.contains("image/svg+xml"));
+        assert!(page.ends_with("<svg/>"));
+
+        assert!(get(server.addr, "/page/2").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_events_push_updates_and_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = PreviewServer::start("127.0.0.1:0").unwrap();
+
+        let mut stream = TcpStream::connect(server.addr).unwrap();
+        write!(stream, "GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(stream);
+
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("HTTP/1.1 200"));
+
+        // Wait until the subscriber is registered before publishing
+        while lock(&server.shared.subscribers).is_empty() {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        server.publish(&svg_result(dir.path()));
+        server.publish_error("error: unknown variable: foo");
+
+        let mut data = Vec::new();
+        for line in reader.lines() {
+            let line = line.unwrap();
+            if let Some(json) = line.strip_prefix("data: ") {
+                data.push(json.to_string());
+                if data.len() == 2 {
+                    break;
+                }
+            }
+        }
+        assert!(data[0].contains(r#""version":1"#));
+        assert!(data[1].contains("unknown variable: foo"));
+    }
+}
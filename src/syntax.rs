@@ -0,0 +1,643 @@
+//! Typst syntax analysis for templates
+//!
+//! Walks the Typst syntax tree of a template to find editable() and
+//! editable-block() calls and every path read from the tmpltr data
+//! dictionary. `let` aliases such as `#let quote = data.at("quote")` are
+//! followed so that `quote.title` is reported as `quote.title`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use regex::Regex;
+use typst_syntax::ast::{self, AstNode, Expr};
+use typst_syntax::LinkedNode;
+
+use crate::content::{BlockFormat, BlockType};
+use crate::error::{Error, Result};
+use crate::template::{
+    DataAccess, EditableBlock, EditableField, FieldConstraints, FieldType, SourceSpan,
+};
+
+/// Named arguments accepted by `editable()`
+const FIELD_ARGS: &[&str] = &[
+    "type", "default", "required", "min", "max", "pattern", "options",
+];
+
+/// Named arguments accepted by `editable-block()`
+const BLOCK_ARGS: &[&str] = &["title", "format", "type", "fields"];
+
+/// Result of analysing a template
+#[derive(Debug, Default)]
+pub struct Analysis {
+    /// editable() calls in source order
+    pub fields: Vec<EditableField>,
+    /// editable-block() calls in source order
+    pub blocks: Vec<EditableBlock>,
+    /// Data paths read by the template, sorted by path
+    pub data_access: Vec<DataAccess>,
+    /// Problems with editable calls (unknown arguments, bad constraints)
+    pub errors: Vec<Error>,
+}
+
+/// Analyse Typst template source
+pub fn analyze(source: &str) -> Analysis {
+    let root = typst_syntax::parse(source);
+    let mut analyzer = Analyzer {
+        source,
+        aliases: HashMap::new(),
+        accesses: BTreeMap::new(),
+        analysis: Analysis::default(),
+    };
+    analyzer.walk(&LinkedNode::new(&root));
+
+    // Only keep the most specific paths: `quote` is implied by `quote.title`
+    let paths: Vec<&String> = analyzer.accesses.keys().collect();
+    let data_access = analyzer
+        .accesses
+        .iter()
+        .filter(|(path, _)| {
+            let prefix = format!("{}.", path);
+            !paths.iter().any(|other| other.starts_with(&prefix))
+        })
+        .map(|(path, default)| DataAccess {
+            path: path.clone(),
+            default: default.clone(),
+        })
+        .collect();
+
+    let mut analysis = analyzer.analysis;
+    analysis.data_access = data_access;
+    analysis
+}
+
+/// Keys read from `sys.inputs`, either as `sys.inputs.data` or as
+/// `sys.inputs.at("data", ...)`
+pub fn input_keys(source: &str) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+    collect_input_keys(&typst_syntax::parse(source), &mut keys);
+    keys
+}
+
+fn collect_input_keys(node: &typst_syntax::SyntaxNode, keys: &mut BTreeSet<String>) {
+    match node.cast::<Expr>() {
+        Some(Expr::FieldAccess(access))
+            if access.field().as_str() != "at" && is_sys_inputs(access.target()) =>
+        {
+            keys.insert(access.field().get().to_string());
+        }
+        Some(Expr::FuncCall(call)) => {
+            if let Expr::FieldAccess(access) = call.callee() {
+                if access.field().as_str() == "at" && is_sys_inputs(access.target()) {
+                    let args = Args::collect(call.args());
+                    keys.extend(args.positional.first().and_then(|e| string(*e)));
+                }
+            }
+        }
+        _ => {}
+    }
+    for child in node.children() {
+        collect_input_keys(child, keys);
+    }
+}
+
+fn is_sys_inputs(expr: Expr) -> bool {
+    matches!(expr, Expr::FieldAccess(access)
+        if access.field().as_str() == "inputs"
+            && matches!(access.target(), Expr::Ident(ident) if ident.as_str() == "sys"))
+}
+
+struct Analyzer<'s> {
+    source: &'s str,
+    /// `let` bindings that refer to a data path
+    aliases: HashMap<String, String>,
+    /// Data paths and their literal defaults
+    accesses: BTreeMap<String, Option<String>>,
+    analysis: Analysis,
+}
+
+impl Analyzer<'_> {
+    fn walk(&mut self, node: &LinkedNode) {
+        if let Some(call) = node.get().cast::<ast::FuncCall>() {
+            match callee_name(call) {
+                Some("editable") => {
+                    if let Err(e) = self.editable(call, node) {
+                        self.analysis.errors.push(e);
+                    }
+                }
+                Some("editable-block") => {
+                    if let Err(e) = self.editable_block(call, node) {
+                        self.analysis.errors.push(e);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(expr) = node.get().cast::<Expr>() {
+            if let Some((path, default)) = self.resolve(expr) {
+                if !path.is_empty() {
+                    self.record(path, default);
+                }
+                return;
+            }
+
+            // Method call on a data path (e.g. `blocks.keys()`): the method
+            // name is not part of the path
+            if let Expr::FuncCall(call) = expr {
+                if let Expr::FieldAccess(access) = call.callee() {
+                    if let Some((path, _)) = self.resolve(access.target()) {
+                        if !path.is_empty() {
+                            self.record(path, None);
+                        }
+                        if let Some(args) = node.children().last() {
+                            self.walk(&args);
+                        }
+                        return;
+                    }
+                }
+            }
+        }
+
+        for child in node.children() {
+            self.walk(&child);
+        }
+
+        if let Some(binding) = node.get().cast::<ast::LetBinding>() {
+            self.bind(binding);
+        }
+    }
+
+    /// Remember or forget a `let` alias
+    fn bind(&mut self, binding: ast::LetBinding) {
+        let ast::LetBindingKind::Normal(ast::Pattern::Normal(Expr::Ident(ident))) = binding.kind()
+        else {
+            return;
+        };
+        match binding.init().and_then(|init| self.resolve(init)) {
+            Some((path, _)) => {
+                self.aliases.insert(ident.get().to_string(), path);
+            }
+            None => {
+                self.aliases.remove(ident.as_str());
+            }
+        }
+    }
+
+    fn record(&mut self, mut path: String, default: Option<String>) {
+        // The blocks table itself is not a value
+        if path == "blocks" {
+            return;
+        }
+        // Block accesses are reported per block (blocks.intro.content -> blocks.intro)
+        if let Some(rest) = path.strip_prefix("blocks.") {
+            if let Some(dot) = rest.find('.') {
+                path.truncate("blocks.".len() + dot);
+            }
+        }
+        let entry = self.accesses.entry(path).or_default();
+        if entry.is_none() {
+            *entry = default;
+        }
+    }
+
+    /// Resolve an expression to the data path it reads, with its default
+    fn resolve(&self, expr: Expr) -> Option<(String, Option<String>)> {
+        match expr {
+            Expr::Ident(ident) if ident.as_str() == "data" => Some((String::new(), None)),
+            Expr::Ident(ident) => self
+                .aliases
+                .get(ident.as_str())
+                .map(|path| (path.clone(), None)),
+            Expr::Parenthesized(inner) => self.resolve(inner.expr()),
+            Expr::FieldAccess(access) => {
+                let (base, _) = self.resolve(access.target())?;
+                Some((join_path(&base, access.field().as_str()), None))
+            }
+            Expr::FuncCall(call) => {
+                let args = Args::collect(call.args());
+                let default = args.named("default").and_then(literal);
+                match call.callee() {
+                    // data.at("key", default: ...)
+                    Expr::FieldAccess(access) if access.field().as_str() == "at" => {
+                        let (base, _) = self.resolve(access.target())?;
+                        let key = args.positional.first().and_then(|e| string(*e))?;
+                        Some((join_path(&base, &key), default))
+                    }
+                    // get(data, "a.b", default: ...)
+                    Expr::Ident(ident) if ident.as_str() == "get" => {
+                        let (base, _) = self.resolve(*args.positional.first()?)?;
+                        let key = args.positional.get(1).and_then(|e| string(*e))?;
+                        Some((join_path(&base, &key), default))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Analyse an `editable(id, value, ...)` call
+    fn editable(&mut self, call: ast::FuncCall, node: &LinkedNode) -> Result<()> {
+        let span = self.span(node);
+        let args = Args::collect(call.args());
+        let Some(path) = args.positional.first().and_then(|e| string(*e)) else {
+            log::debug!(
+                "skipping editable() without literal id at line {}",
+                span.line
+            );
+            return Ok(());
+        };
+        let fail = |msg: String| {
+            Error::Template(format!(
+                "line {}, column {}: editable(\"{}\"): {}",
+                span.line, span.column, path, msg
+            ))
+        };
+
+        args.check(FIELD_ARGS, 2).map_err(&fail)?;
+
+        let field_type = match args.named("type") {
+            Some(expr) => {
+                let name = string(expr).ok_or_else(|| fail("type must be a string".into()))?;
+                FieldType::from_str(&name)
+                    .ok_or_else(|| fail(format!("unknown type '{}'", name)))?
+            }
+            None => FieldType::Text,
+        };
+
+        let number = |name: &str| -> Result<Option<f64>> {
+            args.named(name)
+                .map(|e| number(e).ok_or_else(|| fail(format!("{} must be a number", name))))
+                .transpose()
+        };
+
+        let mut constraints = FieldConstraints {
+            required: matches!(args.named("required"), Some(Expr::Bool(b)) if b.get()),
+            min: number("min")?,
+            max: number("max")?,
+            ..Default::default()
+        };
+
+        if let Some(expr) = args.named("pattern") {
+            let pattern = string(expr).ok_or_else(|| fail("pattern must be a string".into()))?;
+            Regex::new(&pattern).map_err(|e| fail(format!("invalid pattern: {}", e)))?;
+            constraints.pattern = Some(pattern);
+        }
+
+        if let Some(expr) = args.named("options") {
+            constraints.options =
+                strings(expr).ok_or_else(|| fail("options must be an array of strings".into()))?;
+        }
+
+        if field_type == FieldType::Enum && constraints.options.is_empty() {
+            return Err(fail("type \"enum\" requires options".into()));
+        }
+        if let (Some(min), Some(max)) = (constraints.min, constraints.max) {
+            if min > max {
+                return Err(fail(format!("min ({}) is greater than max ({})", min, max)));
+            }
+        }
+
+        self.analysis.fields.push(EditableField {
+            path,
+            field_type,
+            default: args.named("default").and_then(literal),
+            constraints,
+            span: Some(span),
+        });
+        Ok(())
+    }
+
+    /// Analyse an `editable-block(id, title: ..., format: ..., type: ...)[body]` call
+    fn editable_block(&mut self, call: ast::FuncCall, node: &LinkedNode) -> Result<()> {
+        let span = self.span(node);
+        let args = Args::collect(call.args());
+        let Some(path) = args.positional.first().and_then(|e| string(*e)) else {
+            log::debug!(
+                "skipping editable-block() without literal id at line {}",
+                span.line
+            );
+            return Ok(());
+        };
+        let fail = |msg: String| {
+            Error::Template(format!(
+                "line {}, column {}: editable-block(\"{}\"): {}",
+                span.line, span.column, path, msg
+            ))
+        };
+
+        args.check(BLOCK_ARGS, 2).map_err(&fail)?;
+
+        let format = match args.named("format") {
+            Some(expr) => {
+                let name = string(expr).ok_or_else(|| fail("format must be a string".into()))?;
+                BlockFormat::from_str(&name)
+                    .ok_or_else(|| fail(format!("unknown format '{}'", name)))?
+            }
+            None => BlockFormat::default(),
+        };
+
+        let block_type = match args.named("type") {
+            Some(expr) => {
+                let name = string(expr).ok_or_else(|| fail("type must be a string".into()))?;
+                BlockType::from_str(&name)
+                    .ok_or_else(|| fail(format!("unknown type '{}'", name)))?
+            }
+            None => BlockType::Text,
+        };
+
+        let fields = match args.named("fields") {
+            Some(expr) => {
+                strings(expr).ok_or_else(|| fail("fields must be an array of strings".into()))?
+            }
+            None => Vec::new(),
+        };
+
+        let default_content = args.positional.get(1).and_then(|body| match body {
+            Expr::Content(block) => Some(block.body().to_untyped().clone().into_text().to_string()),
+            other => literal(*other),
+        });
+
+        self.analysis.blocks.push(EditableBlock {
+            path,
+            title: args.named("title").and_then(string),
+            format,
+            block_type,
+            fields,
+            default_content: default_content.map(|c| c.trim().to_string()),
+            span: Some(span),
+        });
+        Ok(())
+    }
+
+    fn span(&self, node: &LinkedNode) -> SourceSpan {
+        let range = node.range();
+        let before = &self.source[..range.start];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        SourceSpan {
+            start: range.start,
+            end: range.end,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// Arguments of a call, split into positional and named
+struct Args<'a> {
+    positional: Vec<Expr<'a>>,
+    named: Vec<(String, Expr<'a>)>,
+    spread: bool,
+}
+
+impl<'a> Args<'a> {
+    fn collect(args: ast::Args<'a>) -> Self {
+        let mut collected = Self {
+            positional: Vec::new(),
+            named: Vec::new(),
+            spread: false,
+        };
+        for arg in args.items() {
+            match arg {
+                ast::Arg::Pos(expr) => collected.positional.push(expr),
+                ast::Arg::Named(named) => collected
+                    .named
+                    .push((named.name().get().to_string(), named.expr())),
+                ast::Arg::Spread(_) => collected.spread = true,
+            }
+        }
+        collected
+    }
+
+    fn named(&self, name: &str) -> Option<Expr<'a>> {
+        self.named
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, e)| *e)
+    }
+
+    /// Reject unknown named arguments and surplus positional arguments
+    fn check(&self, allowed: &[&str], max_positional: usize) -> std::result::Result<(), String> {
+        if let Some((name, _)) = self
+            .named
+            .iter()
+            .find(|(n, _)| !allowed.contains(&n.as_str()))
+        {
+            return Err(format!(
+                "unknown argument '{}' (expected one of: {})",
+                name,
+                allowed.join(", ")
+            ));
+        }
+        if self.spread {
+            return Err("spread arguments cannot be analysed".to_string());
+        }
+        if self.positional.len() > max_positional {
+            return Err(format!(
+                "expected at most {} positional arguments, got {}",
+                max_positional,
+                self.positional.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Name of the called function (`editable` or `lib.editable`)
+fn callee_name(call: ast::FuncCall<'_>) -> Option<&str> {
+    match call.callee() {
+        Expr::Ident(ident) => Some(ident.as_str()),
+        Expr::FieldAccess(access) => Some(access.field().as_str()),
+        _ => None,
+    }
+}
+
+fn join_path(base: &str, key: &str) -> String {
+    if base.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", base, key)
+    }
+}
+
+fn string(expr: Expr) -> Option<String> {
+    match expr {
+        Expr::Str(s) => Some(s.get().to_string()),
+        _ => None,
+    }
+}
+
+/// Strings of an array literal such as `("a", "b")`
+fn strings(expr: Expr) -> Option<Vec<String>> {
+    let Expr::Array(array) = expr else {
+        return None;
+    };
+    array
+        .items()
+        .map(|item| match item {
+            ast::ArrayItem::Pos(e) => string(e),
+            ast::ArrayItem::Spread(_) => None,
+        })
+        .collect()
+}
+
+fn number(expr: Expr) -> Option<f64> {
+    match expr {
+        Expr::Int(i) => Some(i.get() as f64),
+        Expr::Float(f) => Some(f.get()),
+        Expr::Unary(unary) if unary.op() == ast::UnOp::Neg => number(unary.expr()).map(|n| -n),
+        _ => None,
+    }
+}
+
+/// Text of a literal default value (`none` and non-literals yield nothing)
+fn literal(expr: Expr) -> Option<String> {
+    match expr {
+        Expr::Str(s) => Some(s.get().to_string()),
+        Expr::Bool(b) => Some(b.get().to_string()),
+        Expr::Int(_) | Expr::Float(_) | Expr::Unary(_) => number(expr).map(|n| n.to_string()),
+        Expr::Numeric(n) => Some(n.to_untyped().text().to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(source: &str) -> Vec<String> {
+        analyze(source)
+            .data_access
+            .into_iter()
+            .map(|a| a.path)
+            .collect()
+    }
+
+    #[test]
+    fn test_multiline_call_with_reordered_args() {
+        let analysis = analyze(
+            r#"
+#editable(
+  "quote.total",
+  get(data, "quote.total", default: 0),
+  required: true,
+  type: "currency",
+)
+"#,
+        );
+        assert!(analysis.errors.is_empty());
+        let field = &analysis.fields[0];
+        assert_eq!(field.path, "quote.total");
+        assert_eq!(field.field_type, FieldType::Currency);
+        assert!(field.constraints.required);
+        let span = field.span.as_ref().unwrap();
+        assert_eq!((span.line, span.column), (2, 2));
+    }
+
+    #[test]
+    fn test_block_with_nested_brackets() {
+        let analysis = analyze(
+            r#"#editable-block("blocks.intro", format: "typst", title: "Intro")[
+  Some *bold* text with #link("https://x.y")[a [nested] link].
+]"#,
+        );
+        let block = &analysis.blocks[0];
+        assert_eq!(block.format, BlockFormat::Typst);
+        assert_eq!(block.title.as_deref(), Some("Intro"));
+        assert_eq!(
+            block.default_content.as_deref(),
+            Some(r#"Some *bold* text with #link("https://x.y")[a [nested] link]."#)
+        );
+    }
+
+    #[test]
+    fn test_comments_are_ignored() {
+        let analysis = analyze(
+            r#"
+// #editable("old.field", x)
+/* #editable-block("blocks.old")[x] */
+#editable("new.field", x)
+"#,
+        );
+        assert_eq!(analysis.fields.len(), 1);
+        assert_eq!(analysis.fields[0].path, "new.field");
+        assert!(analysis.blocks.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_argument_is_error() {
+        let analysis = analyze(r#"#editable("a", a, colour: "red")"#);
+        let message = analysis.errors[0].to_string();
+        assert!(message.contains("unknown argument 'colour'"), "{}", message);
+        assert!(message.contains("line 1, column 2"), "{}", message);
+    }
+
+    #[test]
+    fn test_unknown_block_format_is_error() {
+        let analysis = analyze("#let x = 1\n#editable-block(\"blocks.a\", format: \"rst\")[]");
+        let message = analysis.errors[0].to_string();
+        assert!(message.contains("unknown format 'rst'"), "{}", message);
+        assert!(message.contains("line 2, column 2"), "{}", message);
+        assert!(analysis.blocks.is_empty());
+    }
+
+    #[test]
+    fn test_input_keys() {
+        let source = r#"
+#let data = json(bytes(sys.inputs.data))
+#let file = sys.inputs.at("data-file", default: none)
+#let mode = sys.inputs.at(key)
+// sys.inputs.at("comment")
+#let note = "sys.inputs.at(\"string\")"
+"#;
+        assert_eq!(
+            input_keys(source).into_iter().collect::<Vec<_>>(),
+            vec!["data", "data-file"]
+        );
+        assert!(input_keys("#let data = tmpltr-data()").is_empty());
+    }
+
+    #[test]
+    fn test_let_aliases_and_accessors() {
+        let source = r#"
+#let data = tmpltr-data()
+#let quote = data.at("quote", default: (:))
+#let client = quote.client
+#let blocks = data.at("blocks", default: (:))
+#quote.title
+#client.at("name", default: "Anon")
+#get(data, "brand.logo-width", default: "4cm")
+#if "intro" in blocks.keys() [ #blocks.intro.content ]
+#data.labels.date.len()
+"#;
+        assert!(!typst_syntax::parse(source).erroneous());
+        assert_eq!(
+            paths(source),
+            vec![
+                "blocks.intro",
+                "brand.logo-width",
+                "labels.date",
+                "quote.client.name",
+                "quote.title",
+            ]
+        );
+
+        let analysis = analyze(source);
+        let name = analysis
+            .data_access
+            .iter()
+            .find(|a| a.path == "quote.client.name")
+            .unwrap();
+        assert_eq!(name.default.as_deref(), Some("Anon"));
+    }
+
+    #[test]
+    fn test_shadowed_alias_is_forgotten() {
+        let source = r#"
+#let quote = data.quote
+#let quote = (title: "static")
+#quote.title
+"#;
+        assert_eq!(paths(source), vec!["quote"]);
+    }
+}
@@ -1,6 +1,7 @@
 //! Template parsing for tmpltr
 //!
 //! Parses Typst templates to extract editable() and editable-block() markers.
+//! The syntax tree walk itself lives in [`crate::syntax`].
 
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -8,18 +9,214 @@ use std::path::{Path, PathBuf};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::content::BlockFormat;
+use crate::content::{BlockFormat, BlockType, IMAGE_WIDTH_PATTERN};
+use crate::convert::{ConverterRegistry, DEFAULT_FORMAT};
 use crate::error::{Error, Result};
 
+/// Type of an editable field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// Single-line text
+    #[default]
+    Text,
+    /// Multi-line text
+    Multiline,
+    /// Floating point number
+    Number,
+    /// Whole number
+    Integer,
+    /// true / false
+    Boolean,
+    /// Calendar date (YYYY-MM-DD)
+    Date,
+    /// Monetary amount
+    Currency,
+    /// Email address
+    Email,
+    /// URL
+    Url,
+    /// One of a fixed set of options
+    Enum,
+}
+
+impl FieldType {
+    /// Parse from string
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "text" | "string" => Some(Self::Text),
+            "multiline" => Some(Self::Multiline),
+            "number" => Some(Self::Number),
+            "integer" => Some(Self::Integer),
+            "boolean" | "bool" => Some(Self::Boolean),
+            "date" => Some(Self::Date),
+            "currency" => Some(Self::Currency),
+            "email" => Some(Self::Email),
+            "url" => Some(Self::Url),
+            "enum" => Some(Self::Enum),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Multiline => "multiline",
+            Self::Number => "number",
+            Self::Integer => "integer",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::Currency => "currency",
+            Self::Email => "email",
+            Self::Url => "url",
+            Self::Enum => "enum",
+        }
+    }
+}
+
+/// Constraints declared on an editable field
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldConstraints {
+    /// Whether the field must be present in the content file
+    #[serde(default)]
+    pub required: bool,
+    /// Minimum value (numbers) or length (text)
+    pub min: Option<f64>,
+    /// Maximum value (numbers) or length (text)
+    pub max: Option<f64>,
+    /// Regular expression text values must match
+    pub pattern: Option<String>,
+    /// Allowed values (enum fields)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// Location of a marker in the template source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// Byte offset of the start of the call
+    pub start: usize,
+    /// Byte offset just past the end of the call
+    pub end: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column (in characters)
+    pub column: usize,
+}
+
 /// Information about an editable field extracted from a template
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditableField {
     /// Field path (e.g., "quote.kunde.name")
     pub path: String,
-    /// Field type (e.g., "text")
-    pub field_type: String,
+    /// Field type
+    pub field_type: FieldType,
     /// Default value
     pub default: Option<String>,
+    /// Declared constraints
+    #[serde(default)]
+    pub constraints: FieldConstraints,
+    /// Location of the editable() call in the template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+}
+
+impl EditableField {
+    /// Typed TOML value for a freshly generated content skeleton
+    pub fn default_value(&self) -> toml::Value {
+        let raw = self.default.as_deref();
+        match self.field_type {
+            FieldType::Number | FieldType::Currency => toml::Value::Float(
+                raw.and_then(|s| s.parse().ok())
+                    .or(self.constraints.min)
+                    .unwrap_or(0.0),
+            ),
+            FieldType::Integer => toml::Value::Integer(
+                raw.and_then(|s| s.parse().ok())
+                    .or(self.constraints.min.map(|m| m.ceil() as i64))
+                    .unwrap_or(0),
+            ),
+            FieldType::Boolean => toml::Value::Boolean(raw == Some("true")),
+            FieldType::Date => raw
+                .and_then(|s| s.parse::<toml::value::Datetime>().ok())
+                .map(toml::Value::Datetime)
+                .unwrap_or_else(|| {
+                    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+                    today
+                        .parse()
+                        .map(toml::Value::Datetime)
+                        .unwrap_or(toml::Value::String(today))
+                }),
+            FieldType::Enum => toml::Value::String(
+                raw.map(|s| s.to_string())
+                    .or_else(|| self.constraints.options.first().cloned())
+                    .unwrap_or_default(),
+            ),
+            _ => toml::Value::String(raw.unwrap_or_default().to_string()),
+        }
+    }
+
+    /// JSON schema describing valid values for this field
+    pub fn json_schema(&self) -> serde_json::Value {
+        let mut schema = serde_json::Map::new();
+        let c = &self.constraints;
+
+        let json_type = match self.field_type {
+            FieldType::Number | FieldType::Currency => "number",
+            FieldType::Integer => "integer",
+            FieldType::Boolean => "boolean",
+            _ => "string",
+        };
+        schema.insert("type".to_string(), json_type.into());
+        schema.insert(
+            "description".to_string(),
+            format!("Field: {} ({})", self.path, self.field_type.as_str()).into(),
+        );
+
+        match self.field_type {
+            FieldType::Date => {
+                schema.insert("format".to_string(), "date".into());
+            }
+            FieldType::Email => {
+                schema.insert("format".to_string(), "email".into());
+            }
+            FieldType::Url => {
+                schema.insert("format".to_string(), "uri".into());
+            }
+            FieldType::Enum => {
+                schema.insert("enum".to_string(), c.options.clone().into());
+            }
+            _ => {}
+        }
+
+        if json_type == "string" {
+            if let Some(min) = c.min {
+                schema.insert("minLength".to_string(), (min.max(0.0) as u64).into());
+            }
+            if let Some(max) = c.max {
+                schema.insert("maxLength".to_string(), (max.max(0.0) as u64).into());
+            }
+            if let Some(ref pattern) = c.pattern {
+                schema.insert("pattern".to_string(), pattern.clone().into());
+            }
+        } else if json_type != "boolean" {
+            if let Some(min) = c.min {
+                schema.insert("minimum".to_string(), min.into());
+            }
+            if let Some(max) = c.max {
+                schema.insert("maximum".to_string(), max.into());
+            }
+        }
+
+        if self.default.is_some() {
+            if let Ok(default) = crate::typst::toml_to_json(&self.default_value()) {
+                schema.insert("default".to_string(), default);
+            }
+        }
+
+        serde_json::Value::Object(schema)
+    }
 }
 
 /// Information about an editable block extracted from a template
@@ -31,8 +228,17 @@ pub struct EditableBlock {
     pub title: Option<String>,
     /// Content format
     pub format: BlockFormat,
+    /// Block type
+    #[serde(default, rename = "type")]
+    pub block_type: BlockType,
+    /// Record fields or table columns, in display order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
     /// Default content
     pub default_content: Option<String>,
+    /// Location of the editable-block() call in the template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
 }
 
 /// Parsed template information
@@ -86,8 +292,15 @@ impl TemplateInfo {
             .unwrap_or("unknown")
             .to_string();
 
-        let fields = Self::extract_fields(content)?;
-        let blocks = Self::extract_blocks(content)?;
+        let analysis = crate::syntax::analyze(content);
+        if let Some(err) = analysis.errors.into_iter().next() {
+            return Err(match err {
+                Error::Template(msg) => Error::Template(format!("{}: {}", path.display(), msg)),
+                other => other,
+            });
+        }
+        let fields = analysis.fields;
+        let blocks = analysis.blocks;
 
         // Try to extract metadata from comments
         let description = Self::extract_comment_value(content, "description");
@@ -105,126 +318,7 @@ impl TemplateInfo {
 
     /// Extract data access patterns from template (data.*, get(data, ...), etc.)
     pub fn extract_data_access(content: &str) -> Vec<DataAccess> {
-        let mut accesses = std::collections::HashSet::new();
-        let mut results = Vec::new();
-
-        // Match data.path patterns (e.g., data.quote.client.name)
-        let data_re = Regex::new(r"data\.([a-zA-Z_][a-zA-Z0-9_.]*)").expect("invalid regex");
-        for cap in data_re.captures_iter(content) {
-            if let Some(path) = cap.get(1) {
-                let path_str = path.as_str().to_string();
-                if accesses.insert(path_str.clone()) {
-                    results.push(DataAccess {
-                        path: path_str,
-                        default: None,
-                    });
-                }
-            }
-        }
-
-        // Match get(data, "path", default: value) patterns
-        let get_re = Regex::new(
-            r#"get\s*\(\s*data\s*,\s*"([^"]+)"(?:\s*,\s*default:\s*(?:"([^"]+)"|([^\s,)]+)))?\s*\)"#
-        ).expect("invalid regex");
-        for cap in get_re.captures_iter(content) {
-            if let Some(path) = cap.get(1) {
-                let path_str = path.as_str().to_string();
-                let default = cap.get(2).or(cap.get(3)).map(|m| m.as_str().to_string());
-                if accesses.insert(path_str.clone()) {
-                    results.push(DataAccess {
-                        path: path_str,
-                        default,
-                    });
-                }
-            }
-        }
-
-        // Match blocks.name or data.blocks.name patterns
-        let blocks_re = Regex::new(r#"blocks\.([a-zA-Z_][a-zA-Z0-9_]*)"#).expect("invalid regex");
-        for cap in blocks_re.captures_iter(content) {
-            if let Some(name) = cap.get(1) {
-                let path_str = format!("blocks.{}", name.as_str());
-                if accesses.insert(path_str.clone()) {
-                    results.push(DataAccess {
-                        path: path_str,
-                        default: None,
-                    });
-                }
-            }
-        }
-
-        results.sort_by(|a, b| a.path.cmp(&b.path));
-        results
-    }
-
-    /// Extract editable() calls from template content
-    fn extract_fields(content: &str) -> Result<Vec<EditableField>> {
-        let mut fields = Vec::new();
-
-        // Match #editable("path", type: "text", default: value)
-        // This regex is simplified - a full parser would be more robust
-        let re = Regex::new(
-            r#"#editable\(\s*"([^"]+)"(?:\s*,\s*type:\s*"([^"]+)")?(?:\s*,\s*default:\s*(?:"([^"]+)"|([^\s,)]+)))?\s*\)"#
-        ).map_err(|e| Error::Template(format!("regex error: {}", e)))?;
-
-        for cap in re.captures_iter(content) {
-            let path = cap
-                .get(1)
-                .map(|m| m.as_str().to_string())
-                .unwrap_or_default();
-            let field_type = cap
-                .get(2)
-                .map(|m| m.as_str().to_string())
-                .unwrap_or_else(|| "text".to_string());
-            let default = cap
-                .get(3)
-                .or_else(|| cap.get(4))
-                .map(|m| m.as_str().to_string());
-
-            fields.push(EditableField {
-                path,
-                field_type,
-                default,
-            });
-        }
-
-        Ok(fields)
-    }
-
-    /// Extract editable-block() calls from template content
-    fn extract_blocks(content: &str) -> Result<Vec<EditableBlock>> {
-        let mut blocks = Vec::new();
-
-        // Match #editable-block("path", title: "Title", format: "markdown")[content]
-        // This is a simplified pattern - handles common cases
-        let re = Regex::new(
-            r#"#editable-block\(\s*"([^"]+)"(?:\s*,\s*title:\s*"([^"]+)")?(?:\s*,\s*format:\s*"([^"]+)")?\s*\)\s*\[([^\]]*)\]"#
-        ).map_err(|e| Error::Template(format!("regex error: {}", e)))?;
-
-        for cap in re.captures_iter(content) {
-            let path = cap
-                .get(1)
-                .map(|m| m.as_str().to_string())
-                .unwrap_or_default();
-            let title = cap.get(2).map(|m| m.as_str().to_string());
-            let format_str = cap.get(3).map(|m| m.as_str()).unwrap_or("markdown");
-            let default_content = cap.get(4).map(|m| m.as_str().trim().to_string());
-
-            let format = match format_str {
-                "typst" => BlockFormat::Typst,
-                "plain" => BlockFormat::Plain,
-                _ => BlockFormat::Markdown,
-            };
-
-            blocks.push(EditableBlock {
-                path,
-                title,
-                format,
-                default_content,
-            });
-        }
-
-        Ok(blocks)
+        crate::syntax::analyze(content).data_access
     }
 
     /// Extract a value from template comments (e.g., "// @description: ...")
@@ -320,6 +414,12 @@ impl From<&TemplateInfo> for TemplateSummary {
 impl TemplateInfo {
     /// Generate a JSON schema for content files based on this template
     pub fn generate_schema(&self) -> serde_json::Value {
+        self.generate_schema_with(&ConverterRegistry::builtin())
+    }
+
+    /// Generate a JSON schema allowing the block formats in `converters`
+    pub fn generate_schema_with(&self, converters: &ConverterRegistry) -> serde_json::Value {
+        let formats = converters.names();
         let mut properties = serde_json::Map::new();
         let mut required = Vec::new();
 
@@ -366,6 +466,9 @@ impl TemplateInfo {
             if group == "blocks" {
                 continue; // Handle blocks separately
             }
+            if fields.iter().any(|f| f.constraints.required) {
+                required.push(group.clone());
+            }
             properties.insert(group.clone(), Self::build_field_schema(&fields, &group));
         }
 
@@ -376,25 +479,7 @@ impl TemplateInfo {
                 let block_name = block.path.strip_prefix("blocks.").unwrap_or(&block.path);
                 block_properties.insert(
                     block_name.to_string(),
-                    serde_json::json!({
-                        "type": "object",
-                        "description": block.title.clone().unwrap_or_else(|| block_name.to_string()),
-                        "properties": {
-                            "title": {
-                                "type": "string",
-                                "description": "Block title"
-                            },
-                            "format": {
-                                "type": "string",
-                                "enum": ["markdown", "typst", "plain"],
-                                "default": "markdown"
-                            },
-                            "content": {
-                                "type": "string",
-                                "description": "Block content"
-                            }
-                        }
-                    }),
+                    Self::build_block_schema(block, block_name, &formats),
                 );
             }
             properties.insert(
@@ -419,10 +504,143 @@ impl TemplateInfo {
         })
     }
 
+    /// Build schema for one block, matching its type
+    fn build_block_schema(
+        block: &EditableBlock,
+        name: &str,
+        formats: &[&str],
+    ) -> serde_json::Value {
+        let strings = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+        let scalar = serde_json::json!({ "type": ["string", "number", "boolean"] });
+
+        let mut properties = serde_json::Map::new();
+        properties.insert(
+            "title".to_string(),
+            serde_json::json!({
+                "type": "string",
+                "description": "Block title"
+            }),
+        );
+        let mut required = Vec::new();
+
+        match block.block_type {
+            BlockType::Text => {
+                properties.insert(
+                    "format".to_string(),
+                    serde_json::json!({
+                        "type": "string",
+                        "enum": formats,
+                        "default": DEFAULT_FORMAT
+                    }),
+                );
+                properties.insert(
+                    "content".to_string(),
+                    serde_json::json!({
+                        "type": "string",
+                        "description": "Block content"
+                    }),
+                );
+            }
+            BlockType::Table => {
+                properties.insert("columns".to_string(), strings.clone());
+                properties.insert(
+                    "rows".to_string(),
+                    serde_json::json!({ "type": "array", "items": { "type": "array", "items": scalar } }),
+                );
+                required.extend(["columns", "rows"]);
+            }
+            BlockType::Image => {
+                properties.insert(
+                    "path".to_string(),
+                    serde_json::json!({
+                        "type": "string",
+                        "description": "Image file, relative to the content file"
+                    }),
+                );
+                properties.insert(
+                    "caption".to_string(),
+                    serde_json::json!({ "type": "string" }),
+                );
+                properties.insert(
+                    "width".to_string(),
+                    serde_json::json!({
+                        "type": "string",
+                        "pattern": IMAGE_WIDTH_PATTERN,
+                        "description": "Typst length, e.g. \"8cm\" or \"50%\""
+                    }),
+                );
+                required.push("path");
+            }
+            BlockType::List => {
+                properties.insert("items".to_string(), strings.clone());
+                required.push("items");
+            }
+            BlockType::KeyValue => {
+                properties.insert(
+                    "entries".to_string(),
+                    serde_json::json!({
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "key": { "type": "string" },
+                                "value": { "type": "string" }
+                            },
+                            "required": ["key", "value"]
+                        }
+                    }),
+                );
+                required.push("entries");
+            }
+            BlockType::Records => {
+                let mut record = serde_json::json!({ "type": "object" });
+                if !block.fields.is_empty() {
+                    let fields: serde_json::Map<String, serde_json::Value> = block
+                        .fields
+                        .iter()
+                        .map(|f| (f.clone(), scalar.clone()))
+                        .collect();
+                    record["properties"] = serde_json::Value::Object(fields);
+                }
+                properties.insert("fields".to_string(), strings.clone());
+                properties.insert(
+                    "records".to_string(),
+                    serde_json::json!({ "type": "array", "items": record }),
+                );
+                required.push("records");
+            }
+        }
+
+        if block.block_type != BlockType::Text {
+            properties.insert(
+                "type".to_string(),
+                serde_json::json!({ "const": block.block_type.as_str() }),
+            );
+            required.push("type");
+        }
+
+        serde_json::json!({
+            "type": "object",
+            "description": block.title.clone().unwrap_or_else(|| name.to_string()),
+            "properties": properties,
+            "required": required
+        })
+    }
+
     /// Build schema for a group of fields
     fn build_field_schema(fields: &[&EditableField], prefix: &str) -> serde_json::Value {
+        // A top-level scalar field (e.g. "title") is not an object
+        if let [field] = fields {
+            if field.path == prefix {
+                return field.json_schema();
+            }
+        }
+
         // Build nested object structure based on field paths
-        let mut props = serde_json::Map::new();
+        let mut object = serde_json::json!({
+            "type": "object",
+            "properties": {}
+        });
 
         for field in fields {
             let relative_path = field
@@ -431,69 +649,47 @@ impl TemplateInfo {
                 .unwrap_or(&field.path);
             let parts: Vec<&str> = relative_path.split('.').collect();
 
-            Self::insert_field_schema(&mut props, &parts, field);
+            Self::insert_field_schema(&mut object, &parts, field);
         }
 
-        if props.len() == 1
-            && props
-                .values()
-                .next()
-                .map(|v| v.is_string())
-                .unwrap_or(false)
-        {
-            // Single field, return directly
-            props.into_iter().next().map(|(_, v)| v).unwrap()
-        } else {
-            serde_json::json!({
-                "type": "object",
-                "properties": props
-            })
-        }
+        object
     }
 
     /// Insert a field into the nested schema structure
-    fn insert_field_schema(
-        props: &mut serde_json::Map<String, serde_json::Value>,
-        parts: &[&str],
-        field: &EditableField,
-    ) {
+    fn insert_field_schema(object: &mut serde_json::Value, parts: &[&str], field: &EditableField) {
         if parts.is_empty() {
             return;
         }
 
         let key = parts[0].to_string();
 
+        if field.constraints.required {
+            let required = object
+                .as_object_mut()
+                .map(|o| o.entry("required").or_insert_with(|| serde_json::json!([])));
+            if let Some(list) = required.and_then(|r| r.as_array_mut()) {
+                if !list.iter().any(|k| k.as_str() == Some(key.as_str())) {
+                    list.push(serde_json::Value::String(key.clone()));
+                }
+            }
+        }
+
+        let Some(props) = object.get_mut("properties").and_then(|p| p.as_object_mut()) else {
+            return;
+        };
+
         if parts.len() == 1 {
             // Leaf field
-            let mut schema = serde_json::Map::new();
-            schema.insert(
-                "type".to_string(),
-                serde_json::Value::String("string".to_string()),
-            );
-            schema.insert(
-                "description".to_string(),
-                serde_json::Value::String(format!("Field: {}", field.path)),
-            );
-            if let Some(ref default) = field.default {
-                schema.insert(
-                    "default".to_string(),
-                    serde_json::Value::String(default.clone()),
-                );
-            }
-            props.insert(key, serde_json::Value::Object(schema));
+            props.insert(key, field.json_schema());
         } else {
             // Nested field
-            let entry = props.entry(key.clone()).or_insert_with(|| {
+            let entry = props.entry(key).or_insert_with(|| {
                 serde_json::json!({
                     "type": "object",
                     "properties": {}
                 })
             });
-
-            if let Some(nested_props) = entry.get_mut("properties").and_then(|p| p.as_object_mut())
-            {
-                Self::insert_field_schema(nested_props, &parts[1..], field);
-            }
+            Self::insert_field_schema(entry, &parts[1..], field);
         }
     }
 }
@@ -536,6 +732,46 @@ mod tests {
         assert_eq!(info.blocks[0].format, BlockFormat::Markdown);
     }
 
+    #[test]
+    fn test_schema_lists_registered_formats() {
+        let info = TemplateInfo::parse_content(PathBuf::from("test.typ"), SAMPLE_TEMPLATE).unwrap();
+        let schema = info.generate_schema();
+        let format = &schema["properties"]["blocks"]["properties"]["intro"]["properties"]["format"];
+        assert_eq!(
+            format["enum"],
+            serde_json::json!(["asciidoc", "djot", "html", "markdown", "plain", "typst"])
+        );
+    }
+
+    #[test]
+    fn test_structured_block_schema() {
+        let info = TemplateInfo::parse_content(
+            PathBuf::from("t.typ"),
+            r#"
+#editable-block("blocks.items", title: "Line items", type: "records", fields: ("description", "qty"))[]
+#editable-block("blocks.photo", type: "image")[photo.png]
+"#,
+        )
+        .unwrap();
+        assert_eq!(info.blocks[0].block_type, BlockType::Records);
+        assert_eq!(info.blocks[0].fields, vec!["description", "qty"]);
+
+        let schema = info.generate_schema();
+        let blocks = &schema["properties"]["blocks"]["properties"];
+        let items = &blocks["items"]["properties"];
+        assert_eq!(items["type"]["const"], "records");
+        assert!(items.get("content").is_none());
+        assert!(items["records"]["items"]["properties"]["qty"].is_object());
+        assert_eq!(
+            blocks["photo"]["required"],
+            serde_json::json!(["path", "type"])
+        );
+
+        let parse = |src: &str| TemplateInfo::parse_content(PathBuf::from("t.typ"), src);
+        assert!(parse(r#"#editable-block("blocks.x", type: "gallery")[]"#).is_err());
+        assert!(parse(r#"#editable-block("blocks.x", fields: "a")[]"#).is_err());
+    }
+
     #[test]
     fn test_extract_metadata() {
         let info = TemplateInfo::parse_content(PathBuf::from("test.typ"), SAMPLE_TEMPLATE).unwrap();
@@ -546,4 +782,82 @@ mod tests {
         );
         assert_eq!(info.version, Some("1.0.0".to_string()));
     }
+
+    const TYPED_TEMPLATE: &str = r#"
+#editable("quote.total", total, min: 0, type: "currency", required: true)
+#editable("quote.count", count, type: "integer", default: 3, max: 10)
+#editable("quote.status", status, type: "enum", options: ("draft", "sent"))
+#editable("quote.number", get(data, "quote.number", default: "Q"), pattern: "^Q-[0-9]+$")
+#editable("quote.date", date, type: "date", default: "2025-01-31")
+#editable("title", title, type: "text", min: 3)
+"#;
+
+    #[test]
+    fn test_parse_typed_fields() {
+        let info = TemplateInfo::parse_content(PathBuf::from("t.typ"), TYPED_TEMPLATE).unwrap();
+        let types: Vec<_> = info.fields.iter().map(|f| f.field_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                FieldType::Currency,
+                FieldType::Integer,
+                FieldType::Enum,
+                FieldType::Text,
+                FieldType::Date,
+                FieldType::Text,
+            ]
+        );
+
+        let total = &info.fields[0];
+        assert!(total.constraints.required);
+        assert_eq!(total.constraints.min, Some(0.0));
+        assert_eq!(info.fields[2].constraints.options, vec!["draft", "sent"]);
+        assert_eq!(
+            info.fields[3].constraints.pattern.as_deref(),
+            Some("^Q-[0-9]+$")
+        );
+
+        assert_eq!(total.default_value(), toml::Value::Float(0.0));
+        assert_eq!(info.fields[1].default_value(), toml::Value::Integer(3));
+        assert_eq!(
+            info.fields[2].default_value(),
+            toml::Value::String("draft".into())
+        );
+        assert!(matches!(
+            info.fields[4].default_value(),
+            toml::Value::Datetime(_)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_field_declarations() {
+        let parse = |src: &str| TemplateInfo::parse_content(PathBuf::from("t.typ"), src);
+        assert!(parse(r#"#editable("a", a, type: "colour")"#).is_err());
+        assert!(parse(r#"#editable("a", a, type: "enum")"#).is_err());
+        assert!(parse(r#"#editable("a", a, min: 5, max: 1)"#).is_err());
+        assert!(parse(r#"#editable("a", a, pattern: "([")"#).is_err());
+    }
+
+    #[test]
+    fn test_typed_schema() {
+        let info = TemplateInfo::parse_content(PathBuf::from("t.typ"), TYPED_TEMPLATE).unwrap();
+        let schema = info.generate_schema();
+        let quote = &schema["properties"]["quote"];
+
+        assert_eq!(quote["required"], serde_json::json!(["total"]));
+        assert_eq!(quote["properties"]["total"]["type"], "number");
+        assert_eq!(quote["properties"]["total"]["minimum"], 0.0);
+        assert_eq!(quote["properties"]["count"]["type"], "integer");
+        assert_eq!(quote["properties"]["count"]["default"], 3);
+        assert_eq!(
+            quote["properties"]["status"]["enum"],
+            serde_json::json!(["draft", "sent"])
+        );
+        assert_eq!(quote["properties"]["date"]["format"], "date");
+        assert_eq!(schema["properties"]["title"]["minLength"], 3);
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("quote")));
+    }
 }
@@ -3,15 +3,26 @@
 //! Handles invoking the Typst compiler with proper arguments and error handling.
 
 use std::env;
+use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
 
+use crate::build_cache::{BuildCache, CacheKey};
+use crate::compute;
 use crate::config::AppConfig;
-use crate::content::ContentFile;
+use crate::content::{BlockType, ContentFile};
+use crate::convert::ConverterRegistry;
+use crate::diagnostics::{self, Diagnostic, Severity};
 use crate::error::{Error, Result};
-use crate::markdown::markdown_to_typst;
+use crate::language::LanguageChain;
+use crate::locale;
+use crate::merge::deep_merge;
+use crate::template::TemplateInfo;
+use crate::watch::WatchSet;
 
 /// Output format for compilation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -19,15 +30,18 @@ pub enum OutputFormat {
     #[default]
     Pdf,
     Svg,
+    Png,
     Html,
 }
 
 impl OutputFormat {
     /// Parse from string
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "pdf" => Some(Self::Pdf),
             "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
             "html" => Some(Self::Html),
             _ => None,
         }
@@ -45,9 +59,28 @@ impl OutputFormat {
         match self {
             Self::Pdf => "pdf",
             Self::Svg => "svg",
+            Self::Png => "png",
             Self::Html => "html",
         }
     }
+
+    /// Whether typst writes one file per page for this format
+    pub fn is_paged(&self) -> bool {
+        matches!(self, Self::Svg | Self::Png)
+    }
+
+    /// Parse a comma-separated list such as "pdf,svg,png"
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>> {
+        let mut formats = Vec::new();
+        for name in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let format = Self::from_str(name)
+                .ok_or_else(|| Error::Config(format!("unknown output format '{}'", name)))?;
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        Ok(formats)
+    }
 }
 
 /// Compilation options
@@ -61,12 +94,24 @@ pub struct CompileOptions {
     pub brand_data: Option<serde_json::Value>,
     /// Additional font paths from brand
     pub brand_font_paths: Vec<PathBuf>,
+    /// Document language (overrides `meta.lang`)
+    pub lang: Option<String>,
     /// Include position information
     pub with_positions: bool,
     /// Enable experimental HTML
     pub experimental_html: bool,
     /// Check-only mode (validate without generating output)
     pub check_only: bool,
+    /// Resolution for PNG output (pixels per inch)
+    pub ppi: Option<u32>,
+}
+
+/// One artifact produced by [`TypstCompiler::compile_all`]
+#[derive(Debug, Clone)]
+pub struct OutputTarget {
+    pub format: OutputFormat,
+    /// Output path; paged formats may contain `{p}`
+    pub output: PathBuf,
 }
 
 /// Position of an editable element in the output
@@ -98,15 +143,20 @@ pub struct CompileResult {
     /// Output file (for PDF)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<PathBuf>,
-    /// Pages (for SVG)
+    /// Pages (for SVG and PNG)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pages: Option<Vec<PageInfo>>,
     /// Element positions (if requested)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub positions: Option<Vec<ElementPosition>>,
+    /// Whether the output was restored from the build cache
+    pub cached: bool,
+    /// Warnings reported by Typst
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-/// Page information for SVG output
+/// Page information for SVG and PNG output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageInfo {
     pub page: u32,
@@ -121,6 +171,12 @@ pub struct TypstCompiler {
     font_paths: Vec<PathBuf>,
     /// Package path for bundled tmpltr Typst library
     package_path: PathBuf,
+    /// Build cache for unchanged inputs (disabled when `None`)
+    build_cache: Option<BuildCache>,
+    /// `typst --version` output, looked up on first use
+    version: OnceLock<String>,
+    /// Converters for text block formats
+    converters: ConverterRegistry,
 }
 
 impl TypstCompiler {
@@ -146,56 +202,350 @@ impl TypstCompiler {
             binary,
             font_paths,
             package_path,
+            build_cache: None,
+            version: OnceLock::new(),
+            converters: ConverterRegistry::builtin(),
         })
     }
 
+    /// Reuse outputs from `cache` when all inputs are unchanged
+    pub fn with_build_cache(mut self, cache: BuildCache) -> Self {
+        self.build_cache = Some(cache);
+        self
+    }
+
+    /// Convert text blocks with `converters` instead of the built-in formats
+    pub fn with_converters(mut self, converters: ConverterRegistry) -> Self {
+        self.converters = converters;
+        self
+    }
+
     /// Compile content to output
     pub fn compile(
         &self,
         content: &ContentFile,
         options: &CompileOptions,
     ) -> Result<CompileResult> {
-        // For check-only mode, use a temp file
-        let (output_path, temp_file) = if options.check_only {
-            let temp = tempfile::NamedTempFile::new().map_err(|e| {
-                Error::Io(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("creating temp file: {}", e),
-                ))
-            })?;
-            let path = temp.path().to_path_buf();
-            (path, Some(temp))
-        } else {
-            (options.output.clone(), None)
+        let target = OutputTarget {
+            format: options
+                .format
+                .or_else(|| OutputFormat::from_path(&options.output))
+                .unwrap_or_default(),
+            output: options.output.clone(),
         };
+        let mut results = self.compile_all(content, options, &[target])?;
+        Ok(results.remove(0))
+    }
 
-        let format = options
-            .format
-            .or_else(|| OutputFormat::from_path(&output_path))
-            .unwrap_or_default();
-
+    /// Compile content to several outputs, preparing the data only once.
+    ///
+    /// Returns one result per target, in order. In check-only mode the
+    /// targets' paths are ignored and a single check result is returned.
+    pub fn compile_all(
+        &self,
+        content: &ContentFile,
+        options: &CompileOptions,
+        targets: &[OutputTarget],
+    ) -> Result<Vec<CompileResult>> {
         // Check for experimental HTML
-        if format == OutputFormat::Html && !options.experimental_html && !options.check_only {
+        if !options.experimental_html
+            && !options.check_only
+            && targets.iter().any(|t| t.format == OutputFormat::Html)
+        {
             return Err(Error::Config(
                 "HTML output requires --experimental-html flag".to_string(),
             ));
         }
 
         // Prepare data for Typst
-        let data = self.prepare_data(content, options.brand_data.as_ref())?;
+        let data = self.prepare_data(content, options)?;
         let data_json = serde_json::to_string(&data)?;
 
+        // For check-only mode, compile once into a temp file
+        if options.check_only {
+            let data = self.write_data(&data_json, content)?;
+            let temp = tempfile::NamedTempFile::new().map_err(|e| {
+                Error::Io(std::io::Error::other(format!("creating temp file: {}", e)))
+            })?;
+            let format = targets.first().map(|t| t.format).unwrap_or_default();
+            let diagnostics = self.run_compile(content, options, &data, format, temp.path())?;
+            return Ok(vec![CompileResult {
+                status: "ok".to_string(),
+                format: "check".to_string(),
+                output: None,
+                pages: None,
+                positions: None,
+                cached: false,
+                diagnostics,
+            }]);
+        }
+
+        let inputs_key = self
+            .build_cache
+            .as_ref()
+            .map(|_| self.inputs_key(&data_json, content, options));
+
+        let mut data_file = None;
+        let mut positions = None;
+        let mut results = Vec::with_capacity(targets.len());
+        for target in targets {
+            let key = inputs_key
+                .as_ref()
+                .map(|inputs| target_key(inputs, target, options));
+            if let (Some(cache), Some(key)) = (&self.build_cache, &key) {
+                if let Some(result) = cache.restore(key, target)? {
+                    log::debug!("build cache hit for {}", target.output.display());
+                    results.push(result);
+                    continue;
+                }
+            }
+
+            let data = match data_file {
+                Some(ref data) => data,
+                None => data_file.insert(self.write_data(&data_json, content)?),
+            };
+            let diagnostics =
+                self.run_compile(content, options, data, target.format, &target.output)?;
+            let (output, pages) = if target.format.is_paged() {
+                let pages = self.collect_pages(&target.output, target.format)?;
+                (None, Some(pages))
+            } else {
+                (Some(target.output.clone()), None)
+            };
+
+            // Query editable positions from the laid-out document (same for
+            // every format, so only once)
+            let laid_out = target.format != OutputFormat::Html;
+            if options.with_positions && laid_out && positions.is_none() {
+                positions = Some(self.query_positions(content, options, data)?);
+            }
+
+            let result = CompileResult {
+                status: "ok".to_string(),
+                format: target.format.typst_format().to_string(),
+                output,
+                pages,
+                positions: positions.clone().filter(|_| laid_out),
+                cached: false,
+                diagnostics,
+            };
+            if let (Some(cache), Some(key)) = (&self.build_cache, &key) {
+                if let Err(e) = cache.store(key, &result) {
+                    log::warn!("could not store build cache entry: {}", e);
+                }
+            }
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    /// Hash of everything a compile reads, except per-target options
+    fn inputs_key(
+        &self,
+        data_json: &str,
+        content: &ContentFile,
+        options: &CompileOptions,
+    ) -> String {
+        let mut key = CacheKey::new();
+        key.add("tmpltr", env!("CARGO_PKG_VERSION"))
+            .add(
+                "tmpltr-lib",
+                include_str!("../typst_templates/tmpltr-lib.typ"),
+            )
+            .add("typst", self.typst_version())
+            .add("data", data_json)
+            .add("html", [options.experimental_html as u8]);
+
+        // Template, its imports, referenced files and the brand directories
+        let brand_roots: Vec<PathBuf> = options
+            .brand_data
+            .as_ref()
+            .and_then(|brand| brand.get("layers"))
+            .and_then(|layers| layers.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|layer| layer.get("root").and_then(|root| root.as_str()))
+            .map(PathBuf::from)
+            .collect();
+        let inputs = WatchSet::collect(content, &brand_roots);
+        for file in &inputs.files {
+            key.add_file(file);
+        }
+        for dir in &inputs.dirs {
+            key.add_dir(dir);
+        }
+
+        for font_path in self.font_paths.iter().chain(&options.brand_font_paths) {
+            key.add("font-path", font_path.to_string_lossy().as_bytes());
+        }
+        key.finish()
+    }
+
+    /// `typst --version`, or "unknown" if it cannot be run
+    fn typst_version(&self) -> &str {
+        self.version.get_or_init(|| {
+            Command::new(&self.binary)
+                .arg("--version")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+        })
+    }
+
+    /// Run `typst compile` for one output
+    fn run_compile(
+        &self,
+        content: &ContentFile,
+        options: &CompileOptions,
+        data: &DataInput,
+        format: OutputFormat,
+        output_path: &Path,
+    ) -> Result<Vec<Diagnostic>> {
         // Build command
-        let mut cmd = Command::new(&self.binary);
-        cmd.arg("compile");
+        let mut cmd = self.command("compile", data, options, content);
 
         // Format
         cmd.arg("--format");
         cmd.arg(format.typst_format());
+        if format == OutputFormat::Png {
+            if let Some(ppi) = options.ppi {
+                cmd.arg("--ppi");
+                cmd.arg(ppi.to_string());
+            }
+        }
 
-        // Pass data as input
+        // Output (Typst expects positional output argument)
+        cmd.arg(output_path);
+
+        // Execute
+        let output = cmd.output().map_err(|e| Error::TypstCompilation {
+            message: format!("failed to execute typst: {}", e),
+            details: None,
+            diagnostics: Vec::new(),
+        })?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut diagnostics = diagnostics::parse(&stderr);
+        diagnostics::locate_in_content(&mut diagnostics, content, &self.converters);
+
+        let failed = !output.status.success()
+            && (diagnostics.iter().any(Diagnostic::is_error)
+                || stderr.to_lowercase().contains("error"));
+        if failed {
+            if !diagnostics.iter().any(Diagnostic::is_error) {
+                let summary = stderr
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .unwrap_or("Typst compilation failed");
+                diagnostics.push(Diagnostic::new(Severity::Error, summary.trim()));
+            }
+
+            // Check for common error patterns and provide helpful guidance
+            let enhanced_message = enhance_error_message(&stderr);
+
+            let summary = diagnostics
+                .iter()
+                .find(|d| d.is_error())
+                .map(summarize)
+                .unwrap_or_default();
+            return Err(Error::TypstCompilation {
+                message: format!("Typst compilation failed: {}", summary),
+                details: Some(enhanced_message),
+                diagnostics,
+            });
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Write the document data to a temp file inside the tmpltr package.
+    ///
+    /// `tmpltr-data()` reads it via the `data-file` input, which keeps large
+    /// documents off the command line. Templates that read `sys.inputs.data`
+    /// themselves additionally get the JSON inline as `data`, as long as it
+    /// fits on the command line.
+    fn write_data(&self, json: &str, content: &ContentFile) -> Result<DataInput> {
+        let dir = package_root(&self.package_path).join(DATA_DIR);
+        fs::create_dir_all(&dir).map_err(|e| {
+            Error::Io(std::io::Error::new(
+                e.kind(),
+                format!("creating data directory {}: {}", dir.display(), e),
+            ))
+        })?;
+
+        let mut file = tempfile::Builder::new()
+            .prefix("data-")
+            .suffix(".json")
+            .tempfile_in(&dir)
+            .map_err(|e| {
+                Error::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("creating data file in {}: {}", dir.display(), e),
+                ))
+            })?;
+        file.write_all(json.as_bytes()).map_err(|e| {
+            Error::Io(std::io::Error::new(
+                e.kind(),
+                format!("writing data file {}: {}", file.path().display(), e),
+            ))
+        })?;
+
+        // Local copies of the library resolve absolute paths against the
+        // project root (`/`), the package resolves them against itself
+        let usage = content
+            .meta
+            .resolved_template
+            .as_deref()
+            .map(template_data_usage)
+            .unwrap_or_default();
+        let path = if usage.local_library {
+            file.path().to_string_lossy().into_owned()
+        } else {
+            let name = file.path().file_name().unwrap_or_default();
+            format!("/{}/{}", DATA_DIR, name.to_string_lossy())
+        };
+
+        let inline = if !usage.reads_inputs {
+            None
+        } else if json.len() > MAX_INLINE_DATA {
+            log::warn!(
+                "template reads sys.inputs.data directly but the document data ({} bytes) \
+                 is too large to pass on the command line; use tmpltr-data() instead",
+                json.len()
+            );
+            None
+        } else {
+            Some(json.to_string())
+        };
+
+        Ok(DataInput {
+            _file: file,
+            path,
+            inline,
+        })
+    }
+
+    /// Build a typst command with data, fonts, packages and the template
+    fn command(
+        &self,
+        subcommand: &str,
+        data: &DataInput,
+        options: &CompileOptions,
+        content: &ContentFile,
+    ) -> Command {
+        let mut cmd = Command::new(&self.binary);
+        cmd.arg(subcommand);
+
+        // Pass the data file (read by tmpltr-data())
         cmd.arg("--input");
-        cmd.arg(format!("data={}", data_json));
+        cmd.arg(format!("data-file={}", data.path));
+        if let Some(ref json) = data.inline {
+            cmd.arg("--input");
+            cmd.arg(format!("data={}", json));
+        }
 
         // Font paths from config
         for font_path in &self.font_paths {
@@ -221,14 +571,10 @@ impl TypstCompiler {
         let template_path = content
             .meta
             .resolved_template
-            .as_ref()
-            .map(|p| p.as_path())
+            .as_deref()
             .unwrap_or(Path::new(&content.meta.template));
         cmd.arg(template_path);
 
-        // Output (Typst expects positional output argument)
-        cmd.arg(&output_path);
-
         // Preserve existing package path env if set
         let package_paths = if let Ok(existing) = env::var("TYPST_PACKAGE_PATH") {
             let mut paths = env::split_paths(&existing).collect::<Vec<_>>();
@@ -241,155 +587,89 @@ impl TypstCompiler {
             cmd.env("TYPST_PACKAGE_PATH", paths);
         }
 
-        // Execute
+        cmd
+    }
+
+    /// Query positions of editable elements via `typst query`
+    fn query_positions(
+        &self,
+        content: &ContentFile,
+        options: &CompileOptions,
+        data: &DataInput,
+    ) -> Result<Vec<ElementPosition>> {
+        let mut cmd = self.command("query", data, options, content);
+        cmd.arg(POSITION_LABEL);
+        cmd.arg("--field");
+        cmd.arg("value");
+
         let output = cmd.output().map_err(|e| Error::TypstCompilation {
             message: format!("failed to execute typst: {}", e),
             details: None,
+            diagnostics: Vec::new(),
         })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            let warnings_only = stderr.lines().all(|line| {
-                let lt = line.trim().to_lowercase();
-                lt.is_empty() || lt.starts_with("warning")
+            return Err(Error::TypstCompilation {
+                message: "querying element positions failed".to_string(),
+                details: Some(stderr.to_string()),
+                diagnostics: diagnostics::parse(&stderr),
             });
-
-            if warnings_only {
-                if !stderr.trim().is_empty() {
-                    eprintln!("{}", stderr);
-                }
-            } else {
-                let has_error = stderr
-                    .lines()
-                    .any(|line| line.to_lowercase().contains("error"));
-
-                if has_error {
-                    // Check for common error patterns and provide helpful guidance
-                    let enhanced_message = enhance_error_message(&stderr);
-
-                    let summary = stderr
-                        .lines()
-                        .find(|line| !line.trim().is_empty())
-                        .unwrap_or("Typst compilation failed");
-                    return Err(Error::TypstCompilation {
-                        message: format!("Typst compilation failed: {}", summary),
-                        details: Some(enhanced_message),
-                    });
-                } else if !stderr.trim().is_empty() {
-                    eprintln!("{}", stderr);
-                }
-            }
         }
 
-        // Drop temp file if in check mode (cleans up temp file)
-        drop(temp_file);
-
-        // Build result
-        let result = if options.check_only {
-            CompileResult {
-                status: "ok".to_string(),
-                format: "check".to_string(),
-                output: None,
-                pages: None,
-                positions: None,
-            }
-        } else {
-            match format {
-                OutputFormat::Pdf | OutputFormat::Html => CompileResult {
-                    status: "ok".to_string(),
-                    format: format.typst_format().to_string(),
-                    output: Some(options.output.clone()),
-                    pages: None,
-                    positions: if options.with_positions {
-                        Some(Vec::new()) // TODO: Extract positions
-                    } else {
-                        None
-                    },
-                },
-                OutputFormat::Svg => {
-                    let pages = self.collect_svg_pages(&options.output)?;
-                    CompileResult {
-                        status: "ok".to_string(),
-                        format: format.typst_format().to_string(),
-                        output: None,
-                        pages: Some(pages),
-                        positions: if options.with_positions {
-                            Some(Vec::new()) // TODO: Extract positions
-                        } else {
-                            None
-                        },
-                    }
-                }
-            }
-        };
-
-        Ok(result)
+        parse_positions(&String::from_utf8_lossy(&output.stdout))
     }
 
     /// Prepare data structure for Typst
     fn prepare_data(
         &self,
         content: &ContentFile,
-        brand_data: Option<&serde_json::Value>,
+        options: &CompileOptions,
     ) -> Result<serde_json::Value> {
-        // Convert TOML to JSON, processing markdown blocks
-        let mut data = toml_to_json(content.as_toml())?;
-
-        // Merge brand data if provided
-        if let Some(brand) = brand_data {
-            if let (Some(data_obj), Some(brand_obj)) = (data.as_object_mut(), brand.as_object()) {
-                // Merge brand data under "brand" key
-                data_obj.insert(
-                    "brand".to_string(),
-                    serde_json::Value::Object(brand_obj.clone()),
-                );
-            }
-        }
+        let mut data = resolve_data(
+            content,
+            options.brand_data.as_ref(),
+            options.lang.as_deref(),
+        )?;
 
-        // Process markdown blocks
-        if let Some(blocks) = data.get_mut("blocks").and_then(|v| v.as_object_mut()) {
-            for (_name, block) in blocks.iter_mut() {
-                if let Some(block_obj) = block.as_object_mut() {
-                    let format = block_obj
-                        .get("format")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("markdown");
-
-                    if format == "markdown" {
-                        if let Some(content) = block_obj.get("content").and_then(|v| v.as_str()) {
-                            let typst_content = markdown_to_typst(content)?;
-                            block_obj.insert(
-                                "content".to_string(),
-                                serde_json::Value::String(typst_content),
-                            );
-                        }
-                    }
-                }
-            }
-        }
+        // Convert text blocks to Typst
+        self.converters.convert_blocks(&mut data)?;
+
+        // Image paths are relative to the content file, Typst runs with root `/`
+        let content_dir = content.path.parent().unwrap_or(Path::new("."));
+        resolve_image_paths(&mut data, content_dir);
 
         Ok(data)
     }
 
-    /// Collect SVG page files
-    fn collect_svg_pages(&self, output_pattern: &Path) -> Result<Vec<PageInfo>> {
+    /// Collect page files written for a paged format
+    fn collect_pages(&self, output_pattern: &Path, format: OutputFormat) -> Result<Vec<PageInfo>> {
         let mut pages = Vec::new();
+        let extension = format!(".{}", format.typst_format());
 
-        // SVG output uses patterns like output-{p}.svg
+        // Paged output uses patterns like output-{p}.svg
         let pattern = output_pattern.to_string_lossy();
         if pattern.contains("{p}") || pattern.contains("{0p}") {
-            let parent = output_pattern.parent().unwrap_or(Path::new("."));
+            let parent = output_pattern.parent().unwrap_or(Path::new(""));
+            // "page-{p}.svg" -> files are named "page-1.svg", "page-2.svg", ...
             let stem = output_pattern
                 .file_stem()
                 .and_then(|s| s.to_str())
+                .and_then(|s| s.split('{').next())
+                .map(|s| s.trim_end_matches(['-', '_']))
                 .unwrap_or("output");
 
             // Look for numbered files
-            if let Ok(entries) = std::fs::read_dir(parent) {
+            let dir = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            if let Ok(entries) = std::fs::read_dir(dir) {
                 for entry in entries.flatten() {
-                    let path = entry.path();
+                    let path = parent.join(entry.file_name());
                     if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-                        if name.starts_with(stem) && name.ends_with(".svg") {
+                        if name.starts_with(stem) && name.ends_with(&extension) {
                             // Extract page number
                             if let Some(num) = extract_page_number(name, stem) {
                                 pages.push(PageInfo {
@@ -414,8 +694,92 @@ impl TypstCompiler {
     }
 }
 
+/// Label of the metadata emitted by `editable` and `editable-block`
+const POSITION_LABEL: &str = "<tmpltr-position>";
+
+/// Directory inside the tmpltr package that holds per-compile data files
+const DATA_DIR: &str = "data";
+
+/// Largest document data passed inline as `--input data=...`; stays below
+/// the command line limits of common platforms (32 KiB on Windows)
+const MAX_INLINE_DATA: usize = 24 * 1024;
+
+/// Document data handed to a typst invocation
+struct DataInput {
+    /// Kept alive until typst has run; removed on drop
+    _file: tempfile::NamedTempFile,
+    /// Path of the data file as seen from the library reading it
+    path: String,
+    /// Inline JSON for templates that read `sys.inputs.data` directly
+    inline: Option<String>,
+}
+
+/// Content data with the brand merged in, localized text resolved, computed
+/// fields evaluated and typed fields formatted, as templates see it before
+/// text blocks are converted to Typst.
+///
+/// `brand_data` is the loaded brand (content `[brand]` tables are merged over
+/// it) and `lang` overrides `meta.lang`.
+pub fn resolve_data(
+    content: &ContentFile,
+    brand_data: Option<&serde_json::Value>,
+    lang: Option<&str>,
+) -> Result<serde_json::Value> {
+    // Convert TOML to JSON, processing markdown blocks
+    let mut data = toml_to_json(content.as_toml())?;
+
+    // Content `[brand]` tables are merged over the loaded brand
+    if let Some(brand) = brand_data {
+        if let Some(data_obj) = data.as_object_mut() {
+            let mut merged = brand.clone();
+            if let Some(overlay) = data_obj.remove("brand") {
+                deep_merge(&mut merged, overlay);
+            }
+            data_obj.insert("brand".to_string(), merged);
+        }
+    }
+
+    // Resolve localized brand text and content values
+    let languages = LanguageChain::for_data(lang, &data);
+    languages.localize(&mut data);
+    if let (Some(lang), Some(data_obj)) = (languages.language(), data.as_object_mut()) {
+        let meta = data_obj
+            .entry("meta")
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert("lang".to_string(), serde_json::Value::from(lang));
+        }
+    }
+
+    // Evaluate computed fields
+    compute::evaluate_into(content, &mut data)?;
+
+    // Locale-aware variants of typed template fields
+    let fields = match TemplateInfo::parse(content.template_path()) {
+        Ok(template) => template.fields,
+        Err(e) => {
+            log::debug!("not formatting template fields: {}", e);
+            Vec::new()
+        }
+    };
+    locale::add_formatted(&mut data, &fields);
+
+    Ok(data)
+}
+
+/// Parse the JSON printed by `typst query <tmpltr-position> --field value`
+fn parse_positions(output: &str) -> Result<Vec<ElementPosition>> {
+    let mut positions: Vec<ElementPosition> = serde_json::from_str(output.trim())?;
+    positions.sort_by(|a, b| {
+        (a.page, a.y, a.x)
+            .partial_cmp(&(b.page, b.y, b.x))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(positions)
+}
+
 /// Convert TOML value to JSON
-fn toml_to_json(value: &toml::Value) -> Result<serde_json::Value> {
+pub(crate) fn toml_to_json(value: &toml::Value) -> Result<serde_json::Value> {
     let json = match value {
         toml::Value::String(s) => serde_json::Value::String(s.clone()),
         toml::Value::Integer(i) => serde_json::Value::Number((*i).into()),
@@ -439,6 +803,25 @@ fn toml_to_json(value: &toml::Value) -> Result<serde_json::Value> {
     Ok(json)
 }
 
+/// Make the `path` of every image block absolute
+fn resolve_image_paths(data: &mut serde_json::Value, base_dir: &Path) {
+    let Some(blocks) = data.get_mut("blocks").and_then(|v| v.as_object_mut()) else {
+        return;
+    };
+    for block in blocks.values_mut() {
+        if block.get("type").and_then(|v| v.as_str()) != Some(BlockType::Image.as_str()) {
+            continue;
+        }
+        if let Some(path) = block.get_mut("path") {
+            if let Some(relative) = path.as_str().filter(|p| !p.is_empty()) {
+                let resolved = base_dir.join(relative);
+                let resolved = resolved.canonicalize().unwrap_or(resolved);
+                *path = serde_json::Value::String(resolved.to_string_lossy().into_owned());
+            }
+        }
+    }
+}
+
 /// Find typst binary in PATH
 fn which_typst() -> Result<PathBuf> {
     which::which("typst").map_err(|_| {
@@ -451,7 +834,7 @@ fn which_typst() -> Result<PathBuf> {
 
 fn prepare_tmpltr_package() -> Result<PathBuf> {
     let base = env::temp_dir().join("tmpltr-typst-packages");
-    let pkg_root = base.join("local").join("tmpltr-lib").join("1.0.0");
+    let pkg_root = package_root(&base);
     let package_file = pkg_root.join("typst.toml");
     let entrypoint = pkg_root.join("lib.typ");
 
@@ -494,16 +877,85 @@ description = "tmpltr helper library"
     Ok(base)
 }
 
-/// Extract page number from SVG filename
+/// Root of the bundled tmpltr package below the package path
+fn package_root(package_path: &Path) -> PathBuf {
+    package_path.join("local").join("tmpltr-lib").join("1.0.0")
+}
+
+/// Cache key for one output of a compile
+fn target_key(inputs_key: &str, target: &OutputTarget, options: &CompileOptions) -> String {
+    let mut key = CacheKey::new();
+    key.add("inputs", inputs_key)
+        .add("format", target.format.typst_format())
+        .add("positions", [options.with_positions as u8]);
+    if target.format == OutputFormat::Png {
+        key.add("ppi", options.ppi.unwrap_or_default().to_le_bytes());
+    }
+    key.finish()
+}
+
+/// How a template and its local imports get at the document data
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DataUsage {
+    /// Reads `sys.inputs.data` itself instead of going through `tmpltr-data()`
+    reads_inputs: bool,
+    /// Imports a local copy of the helper library
+    local_library: bool,
+}
+
+fn template_data_usage(template: &Path) -> DataUsage {
+    let mut usage = DataUsage::default();
+    for path in crate::watch::template_dependencies(template) {
+        if path.extension().and_then(|e| e.to_str()) != Some("typ") {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+        // The library itself reads `data` as a fallback for `data-file`
+        let keys = crate::syntax::input_keys(&source);
+        if keys.contains("data-file") {
+            usage.local_library = true;
+        } else if keys.contains("data") {
+            usage.reads_inputs = true;
+        }
+    }
+    usage
+}
+
+/// Extract page number from a page filename (e.g. "output-3.svg")
 fn extract_page_number(filename: &str, stem: &str) -> Option<u32> {
     let suffix = filename.strip_prefix(stem)?;
     let suffix = suffix
         .strip_prefix('-')
         .or_else(|| suffix.strip_prefix('_'))?;
-    let num_str = suffix.strip_suffix(".svg")?;
+    let (num_str, _extension) = suffix.rsplit_once('.')?;
     num_str.parse().ok()
 }
 
+/// One-line summary of an error, pointing at the content value when known
+fn summarize(diagnostic: &Diagnostic) -> String {
+    match (&diagnostic.content, &diagnostic.file) {
+        (Some(content), _) => match content.line {
+            Some(line) => format!(
+                "{} (in {}, {}:{})",
+                diagnostic.message,
+                content.path,
+                content.file.display(),
+                line
+            ),
+            None => format!("{} (in {})", diagnostic.message, content.path),
+        },
+        (None, Some(file)) => match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => {
+                format!("{} ({}:{}:{})", diagnostic.message, file, line, column)
+            }
+            _ => format!("{} ({})", diagnostic.message, file),
+        },
+        (None, None) => diagnostic.message.clone(),
+    }
+}
+
 /// Enhance error messages with helpful guidance for common issues
 fn enhance_error_message(stderr: &str) -> String {
     let stderr_lower = stderr.to_lowercase();
@@ -543,18 +995,17 @@ fn enhance_error_message(stderr: &str) -> String {
     }
 
     // Check for missing function errors
-    if stderr_lower.contains("unknown variable") || stderr_lower.contains("cannot find") {
-        if stderr_lower.contains("tmpltr-data")
+    if (stderr_lower.contains("unknown variable") || stderr_lower.contains("cannot find"))
+        && (stderr_lower.contains("tmpltr-data")
             || stderr_lower.contains("editable")
-            || stderr_lower.contains("tmpltr-lib")
-        {
-            hints.push(
-                "HINT: Make sure your template imports the tmpltr library:\n\
-                 \n\
-                 #import \"@local/tmpltr-lib:1.0.0\": editable, editable-block, tmpltr-data, md, get"
-                    .to_string(),
-            );
-        }
+            || stderr_lower.contains("tmpltr-lib"))
+    {
+        hints.push(
+            "HINT: Make sure your template imports the tmpltr library:\n\
+             \n\
+             #import \"@local/tmpltr-lib:1.0.0\": editable, editable-block, tmpltr-data, md, get"
+                .to_string(),
+        );
     }
 
     // Check for missing data field errors
@@ -574,34 +1025,47 @@ fn enhance_error_message(stderr: &str) -> String {
     }
 }
 
-/// Compilation error details
-#[derive(Debug, Clone, Serialize)]
-pub struct CompileError {
-    pub status: String,
-    pub kind: String,
-    pub message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub details: Option<String>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl From<Error> for CompileError {
-    fn from(err: Error) -> Self {
-        Self {
-            status: "error".to_string(),
-            kind: err.kind().to_string(),
-            message: err.to_string(),
-            details: if let Error::TypstCompilation { details, .. } = &err {
-                details.clone()
-            } else {
-                None
-            },
+    /// Compiler running `binary` without a build cache
+    fn stub_compiler(binary: PathBuf, package_path: PathBuf) -> TypstCompiler {
+        TypstCompiler {
+            binary,
+            font_paths: Vec::new(),
+            build_cache: None,
+            version: OnceLock::new(),
+            converters: ConverterRegistry::builtin(),
+            package_path,
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Write an executable `typst` shell script into `dir`
+    #[cfg(unix)]
+    fn write_stub(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let binary = dir.join("typst");
+        fs::write(&binary, format!("#!/bin/sh\n{}", script)).unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        binary
+    }
+
+    /// Default options writing to `output`
+    fn options(output: PathBuf) -> CompileOptions {
+        CompileOptions {
+            output,
+            format: None,
+            brand_data: None,
+            brand_font_paths: Vec::new(),
+            lang: None,
+            with_positions: false,
+            experimental_html: false,
+            check_only: false,
+            ppi: None,
+        }
+    }
 
     #[test]
     fn test_format_from_path() {
@@ -617,13 +1081,344 @@ mod tests {
             OutputFormat::from_path(Path::new("output.html")),
             Some(OutputFormat::Html)
         );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("output.png")),
+            Some(OutputFormat::Png)
+        );
         assert_eq!(OutputFormat::from_path(Path::new("output.txt")), None);
     }
 
+    #[test]
+    fn test_parse_positions() {
+        let output = r#"[
+  {"id": "blocks.intro", "kind": "block", "page": 2, "x": 56.7, "y": 80.0, "width": 481.9, "height": 120.5},
+  {"id": "quote.title", "kind": "field", "page": 1, "x": 56.7, "y": 100.25, "width": 90.0, "height": 12.0}
+]"#;
+        let positions = parse_positions(output).unwrap();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].id, "quote.title");
+        assert_eq!(positions[0].page, 1);
+        assert_eq!(positions[1].kind, "block");
+        assert_eq!(positions[1].height, 120.5);
+
+        assert!(parse_positions("[]").unwrap().is_empty());
+        assert!(parse_positions("not json").is_err());
+    }
+
+    #[test]
+    fn test_collect_pages_from_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "page-2.svg",
+            "page-1.svg",
+            "page-10.svg",
+            "other-1.svg",
+            "page-1.png",
+        ] {
+            std::fs::write(dir.path().join(name), "<svg/>").unwrap();
+        }
+        let compiler = stub_compiler(PathBuf::from("typst"), PathBuf::new());
+        let pages = compiler
+            .collect_pages(&dir.path().join("page-{p}.svg"), OutputFormat::Svg)
+            .unwrap();
+        let numbers: Vec<u32> = pages.iter().map(|p| p.page).collect();
+        assert_eq!(numbers, vec![1, 2, 10]);
+
+        let pages = compiler
+            .collect_pages(&dir.path().join("page-{0p}.png"), OutputFormat::Png)
+            .unwrap();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].file.ends_with("page-1.png"));
+    }
+
+    #[test]
+    fn test_parse_format_list() {
+        assert_eq!(
+            OutputFormat::parse_list("pdf, svg,png,pdf").unwrap(),
+            vec![OutputFormat::Pdf, OutputFormat::Svg, OutputFormat::Png]
+        );
+        assert!(OutputFormat::parse_list("pdf,docx").is_err());
+    }
+
     #[test]
     fn test_extract_page_number() {
         assert_eq!(extract_page_number("output-1.svg", "output"), Some(1));
         assert_eq!(extract_page_number("output-01.svg", "output"), Some(1));
         assert_eq!(extract_page_number("output_2.svg", "output"), Some(2));
+        assert_eq!(extract_page_number("output-3.png", "output"), Some(3));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compile_passes_large_data_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let package_path = root.join("packages");
+
+        // Stub typst: refuses oversized arguments and copies the data file
+        // it was given to the output path
+        let binary = write_stub(
+            root,
+            &format!(
+                "for a; do\n\
+                 \x20 [ ${{#a}} -gt 65536 ] && {{ echo 'error: argument too long' >&2; exit 1; }}\n\
+                 \x20 case \"$a\" in data-file=*) file=\"${{a#data-file=}}\";; esac\n\
+                 \x20 last=\"$a\"\n\
+                 done\n\
+                 cp \"{}$file\" \"$last\"\n",
+                package_root(&package_path).display()
+            ),
+        );
+
+        fs::write(
+            root.join("template.typ"),
+            "#import \"@local/tmpltr-lib:1.0.0\": tmpltr-data\n#let data = tmpltr-data()\n",
+        )
+        .unwrap();
+        let body = "Lorem ipsum dolor sit amet. ".repeat(150_000);
+        let content_path = root.join("content.toml");
+        let toml = format!(
+            "[meta]\ntemplate = \"template.typ\"\n\n[blocks.body]\nformat = \"typst\"\ncontent = \"\"\"\n{}\"\"\"\n",
+            body
+        );
+        assert!(toml.len() > 4_000_000);
+        fs::write(&content_path, toml).unwrap();
+
+        let compiler = stub_compiler(binary, package_path.clone());
+        let content = ContentFile::load(&content_path).unwrap();
+        let options = options(root.join("out.pdf"));
+        compiler.compile(&content, &options).unwrap();
+
+        let data: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join("out.pdf")).unwrap()).unwrap();
+        assert_eq!(
+            data["blocks"]["body"]["content"].as_str().map(str::len),
+            Some(body.len())
+        );
+
+        // The data file is removed once typst is done
+        let data_dir = package_root(&package_path).join(DATA_DIR);
+        assert_eq!(fs::read_dir(data_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_prepare_data_merges_brand_and_resolves_language() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_path = dir.path().join("content.toml");
+        fs::write(
+            &content_path,
+            "[meta]\ntemplate = \"template.typ\"\nlang = \"de\"\n\n\
+             [quote]\ntitle = { de = \"Angebot\", en = \"Quote\" }\n\n\
+             [blocks.intro]\ncontent.de = \"Hallo\"\ncontent.en = \"Hello\"\n\n\
+             [brand.contact]\ncity = \"Paderborn\"\n",
+        )
+        .unwrap();
+        let content = ContentFile::load(&content_path).unwrap();
+
+        let compiler = stub_compiler(PathBuf::from("typst"), dir.path().join("packages"));
+        let mut options = CompileOptions {
+            brand_data: Some(serde_json::json!({
+                "default_language": "en",
+                "name": { "default": "Acme", "de": "Acme GmbH" },
+                "contact": { "email": "hi@acme.test", "city": "Berlin" }
+            })),
+            ..options(dir.path().join("out.pdf"))
+        };
+
+        let data = compiler.prepare_data(&content, &options).unwrap();
+        assert_eq!(data["quote"]["title"], "Angebot");
+        assert_eq!(data["brand"]["name"], "Acme GmbH");
+        assert_eq!(data["blocks"]["intro"]["content"], "Hallo");
+        assert_eq!(data["brand"]["contact"]["email"], "hi@acme.test");
+        assert_eq!(data["brand"]["contact"]["city"], "Paderborn");
+
+        options.lang = Some("fr".to_string());
+        let data = compiler.prepare_data(&content, &options).unwrap();
+        assert_eq!(data["meta"]["lang"], "fr");
+        assert_eq!(data["quote"]["title"], "Quote");
+        assert_eq!(data["brand"]["name"], "Acme");
+    }
+
+    #[test]
+    fn test_resolve_data_evaluates_computed_with_brand() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_path = dir.path().join("content.toml");
+        fs::write(
+            &content_path,
+            "[meta]\ntemplate = \"template.typ\"\n\n\
+             [quote]\nnet = 100\n\n\
+             [computed]\nvat = \"quote.net * brand.vat_rate\"\n",
+        )
+        .unwrap();
+        let content = ContentFile::load(&content_path).unwrap();
+
+        let brand = serde_json::json!({ "vat_rate": 0.19 });
+        let data = resolve_data(&content, Some(&brand), Some("de")).unwrap();
+        assert_eq!(data["computed"]["vat"], 19.0);
+        assert_eq!(data["meta"]["lang"], "de");
+        assert!(resolve_data(&content, None, None).is_err());
+    }
+
+    #[test]
+    fn test_template_data_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("modern.typ"),
+            "#import \"@local/tmpltr-lib:1.0.0\": tmpltr-data\n#let data = tmpltr-data()\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("legacy.typ"),
+            "#import \"parts.typ\": x\n#let data = json(bytes(sys.inputs.data))\n",
+        )
+        .unwrap();
+        fs::write(root.join("parts.typ"), "#let x = 1\n").unwrap();
+        fs::write(
+            root.join("mode.typ"),
+            "// sys.inputs is not read here\n#let mode = sys.inputs.at(\"mode\", default: \"\")\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("tmpltr-lib.typ"),
+            include_str!("../typst_templates/tmpltr-lib.typ"),
+        )
+        .unwrap();
+        fs::write(
+            root.join("copy.typ"),
+            "#import \"tmpltr-lib.typ\": tmpltr-data\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            template_data_usage(&root.join("modern.typ")),
+            DataUsage::default()
+        );
+        assert!(template_data_usage(&root.join("legacy.typ")).reads_inputs);
+        assert!(!template_data_usage(&root.join("mode.typ")).reads_inputs);
+        let usage = template_data_usage(&root.join("copy.typ"));
+        assert!(usage.local_library && !usage.reads_inputs);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compile_all_shares_prepared_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        // Stub typst: logs its arguments and writes page 1 for paged output
+        let binary = write_stub(
+            root,
+            &format!(
+                "echo \"$*\" >> {log}\n\
+                 for a; do last=\"$a\"; done\n\
+                 echo x > \"$(echo \"$last\" | sed 's/{{p}}/1/')\"\n",
+                log = root.join("calls.log").display()
+            ),
+        );
+        fs::write(root.join("t.typ"), "Hello").unwrap();
+        fs::write(root.join("c.toml"), "[meta]\ntemplate = \"t.typ\"\n").unwrap();
+
+        let compiler = stub_compiler(binary, root.join("packages"));
+        let content = ContentFile::load(root.join("c.toml")).unwrap();
+        let options = CompileOptions {
+            ppi: Some(200),
+            ..options(PathBuf::new())
+        };
+        let targets: Vec<OutputTarget> = [
+            (OutputFormat::Pdf, "out.pdf"),
+            (OutputFormat::Svg, "out-{p}.svg"),
+            (OutputFormat::Png, "out-{p}.png"),
+        ]
+        .into_iter()
+        .map(|(format, name)| OutputTarget {
+            format,
+            output: root.join(name),
+        })
+        .collect();
+
+        let results = compiler.compile_all(&content, &options, &targets).unwrap();
+        let formats: Vec<&str> = results.iter().map(|r| r.format.as_str()).collect();
+        assert_eq!(formats, vec!["pdf", "svg", "png"]);
+        assert_eq!(
+            results[0].output.as_deref(),
+            Some(root.join("out.pdf").as_path())
+        );
+        assert!(results[2].pages.as_ref().unwrap()[0]
+            .file
+            .ends_with("out-1.png"));
+
+        let log = fs::read_to_string(root.join("calls.log")).unwrap();
+        let calls: Vec<&str> = log.lines().collect();
+        assert_eq!(calls.len(), 3);
+        let data_file = |call: &str| {
+            call.split(' ')
+                .find(|a| a.starts_with("data-file="))
+                .map(str::to_string)
+        };
+        assert!(data_file(calls[0]).is_some());
+        assert!(calls.iter().all(|c| data_file(c) == data_file(calls[0])));
+        assert!(!calls[0].contains("--ppi"));
+        assert!(calls[2].contains("--format png --ppi 200"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_cache_skips_unchanged_compile() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let binary = write_stub(
+            root,
+            &format!(
+                "[ \"$1\" = --version ] && {{ echo 'typst 0.13.1'; exit 0; }}\n\
+                 echo \"$1\" >> {log}\n\
+                 for a; do last=\"$a\"; done\n\
+                 echo compiled > \"$last\"\n",
+                log = root.join("calls.log").display()
+            ),
+        );
+        fs::write(root.join("t.typ"), "Hello").unwrap();
+        let content_path = root.join("c.toml");
+        fs::write(
+            &content_path,
+            "[meta]\ntemplate = \"t.typ\"\n\n[a]\nb = 1\n",
+        )
+        .unwrap();
+
+        let mut compiler = stub_compiler(binary, root.join("packages"));
+        compiler.build_cache = Some(BuildCache::new(&root.join("cache")));
+        let options = options(root.join("out.pdf"));
+        let compile = || {
+            let content = ContentFile::load(&content_path).unwrap();
+            compiler.compile(&content, &options).unwrap()
+        };
+        let calls = || {
+            fs::read_to_string(root.join("calls.log"))
+                .unwrap()
+                .lines()
+                .count()
+        };
+
+        assert!(!compile().cached);
+        fs::remove_file(root.join("out.pdf")).unwrap();
+        let result = compile();
+        assert!(result.cached);
+        assert_eq!(calls(), 1);
+        assert_eq!(
+            fs::read_to_string(root.join("out.pdf")).unwrap(),
+            "compiled\n"
+        );
+
+        // Template and data changes both invalidate the entry
+        fs::write(root.join("t.typ"), "Hello!").unwrap();
+        assert!(!compile().cached);
+        fs::write(
+            &content_path,
+            "[meta]\ntemplate = \"t.typ\"\n\n[a]\nb = 2\n",
+        )
+        .unwrap();
+        assert!(!compile().cached);
+        assert!(compile().cached);
+        assert_eq!(calls(), 3);
     }
 }
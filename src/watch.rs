@@ -0,0 +1,276 @@
+//! Dependency tracking for watch mode
+//!
+//! Collects every file a compile depends on: the content file, the resolved
+//! template and its local imports, files referenced from the content and the
+//! brand directories.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use typst_syntax::ast::{self, Expr};
+use typst_syntax::SyntaxNode;
+
+use crate::content::ContentFile;
+
+/// Typst functions whose first argument is a path to a local file
+const FILE_FUNCTIONS: &[&str] = &[
+    "read",
+    "image",
+    "json",
+    "yaml",
+    "toml",
+    "csv",
+    "xml",
+    "cbor",
+    "bibliography",
+];
+
+/// Files and directories a compile depends on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchSet {
+    /// Individual files
+    pub files: BTreeSet<PathBuf>,
+    /// Directories watched recursively (e.g. the brand root)
+    pub dirs: BTreeSet<PathBuf>,
+}
+
+impl WatchSet {
+    /// Collect the dependencies of compiling `content`
+    pub fn collect(content: &ContentFile, brand_roots: &[PathBuf]) -> Self {
+        let mut set = Self::default();
+        set.add_file(&content.path);
+
+        if let Some(template) = content.meta.resolved_template.as_deref() {
+            for file in template_dependencies(template) {
+                set.add_file(&file);
+            }
+        }
+
+        let content_dir = content.path.parent().unwrap_or(Path::new("."));
+        collect_content_files(content.as_toml(), content_dir, &mut set);
+
+        for root in brand_roots {
+            set.dirs.insert(normalize(root));
+        }
+
+        set
+    }
+
+    fn add_file(&mut self, path: &Path) {
+        self.files.insert(normalize(path));
+    }
+
+    /// Whether a changed path belongs to this set
+    pub fn contains(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        self.files.contains(&path) || self.dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// Directories to register with the watcher and whether to recurse.
+    ///
+    /// Files are watched through their parent directory so that editors which
+    /// save by replacing the file are still noticed.
+    pub fn watch_targets(&self) -> BTreeSet<(PathBuf, bool)> {
+        let mut targets: BTreeSet<(PathBuf, bool)> =
+            self.dirs.iter().map(|dir| (dir.clone(), true)).collect();
+        for file in &self.files {
+            let parent = file.parent().unwrap_or(Path::new("/"));
+            if !self.dirs.iter().any(|dir| parent.starts_with(dir)) {
+                targets.insert((parent.to_path_buf(), false));
+            }
+        }
+        targets
+    }
+}
+
+/// Whether a changed path lies inside `dir`
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    normalize(path).starts_with(normalize(dir))
+}
+
+/// The template and every local file it imports, includes or reads
+pub fn template_dependencies(template: &Path) -> Vec<PathBuf> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![normalize(template)];
+
+    while let Some(file) = pending.pop() {
+        if !seen.insert(file.clone()) {
+            continue;
+        }
+        if file.extension().and_then(|e| e.to_str()) != Some("typ") {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let dir = file.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let mut references = Vec::new();
+        local_references(&typst_syntax::parse(&source), &mut references);
+        for reference in references {
+            pending.push(resolve_reference(&dir, &reference));
+        }
+    }
+
+    seen.into_iter().collect()
+}
+
+/// Collect string paths from imports, includes and file-reading calls
+fn local_references(node: &SyntaxNode, out: &mut Vec<String>) {
+    let path = match node.cast::<Expr>() {
+        Some(Expr::Import(import)) => literal_path(import.source()),
+        Some(Expr::Include(include)) => literal_path(include.source()),
+        Some(Expr::FuncCall(call)) => match call.callee() {
+            Expr::Ident(ident) if FILE_FUNCTIONS.contains(&ident.as_str()) => call
+                .args()
+                .items()
+                .find_map(|arg| match arg {
+                    ast::Arg::Pos(expr) => Some(expr),
+                    _ => None,
+                })
+                .and_then(literal_path),
+            _ => None,
+        },
+        _ => None,
+    };
+    out.extend(path);
+
+    for child in node.children() {
+        local_references(child, out);
+    }
+}
+
+/// A string literal that names a local file (packages start with '@')
+fn literal_path(expr: Expr) -> Option<String> {
+    match expr {
+        Expr::Str(s) if !s.get().starts_with('@') => Some(s.get().to_string()),
+        _ => None,
+    }
+}
+
+/// Resolve a Typst path: relative to the importing file, absolute from the
+/// project root (tmpltr compiles with `--root /`)
+fn resolve_reference(dir: &Path, reference: &str) -> PathBuf {
+    let path = Path::new(reference);
+    if path.is_absolute() {
+        normalize(path)
+    } else {
+        normalize(&dir.join(path))
+    }
+}
+
+/// Add string values of the content that name existing files
+fn collect_content_files(value: &toml::Value, content_dir: &Path, set: &mut WatchSet) {
+    match value {
+        toml::Value::String(s) if !s.contains('\n') && s.len() < 1024 => {
+            let expanded = shellexpand::tilde(s);
+            let path = Path::new(expanded.as_ref());
+            let candidate = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                content_dir.join(path)
+            };
+            if !s.is_empty() && candidate.is_file() {
+                set.add_file(&candidate);
+            }
+        }
+        toml::Value::Array(items) => {
+            for item in items {
+                collect_content_files(item, content_dir, set);
+            }
+        }
+        toml::Value::Table(table) => {
+            for item in table.values() {
+                collect_content_files(item, content_dir, set);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Canonicalize when possible so watcher events compare equal
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .map(|cwd| cwd.join(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_dependencies_follow_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("parts")).unwrap();
+        fs::write(
+            root.join("main.typ"),
+            r#"#import "@local/tmpltr-lib:1.0.0": editable
+#import "parts/header.typ": header
+// #import "commented.typ"
+#include "parts/footer.typ"
+#image("logo.svg")
+"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("parts/header.typ"),
+            "#let header = read(\"../notes.txt\")",
+        )
+        .unwrap();
+        fs::write(root.join("parts/footer.typ"), "Footer").unwrap();
+        fs::write(root.join("notes.txt"), "").unwrap();
+
+        let deps = template_dependencies(&root.join("main.typ"));
+        let root = normalize(root);
+        let names: Vec<_> = deps
+            .iter()
+            .map(|p| p.strip_prefix(&root).unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "logo.svg",
+                "main.typ",
+                "notes.txt",
+                "parts/footer.typ",
+                "parts/header.typ"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watch_set_contains() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("logo.png"), "").unwrap();
+        fs::write(root.join("template.typ"), "Hello").unwrap();
+        fs::create_dir_all(root.join("brand")).unwrap();
+        let content_path = root.join("content.toml");
+        fs::write(
+            &content_path,
+            "[meta]\ntemplate = \"template.typ\"\n\n[quote]\nlogo = \"logo.png\"\nname = \"ACME\"\n",
+        )
+        .unwrap();
+
+        let content = ContentFile::load(&content_path).unwrap();
+        let set = WatchSet::collect(&content, &[root.join("brand")]);
+
+        assert!(set.contains(&content_path));
+        assert!(set.contains(&root.join("template.typ")));
+        assert!(set.contains(&root.join("logo.png")));
+        assert!(set.contains(&root.join("brand/fonts/Inter.ttf")));
+        assert!(!set.contains(&root.join("other.toml")));
+
+        let targets = set.watch_targets();
+        assert!(targets.contains(&(normalize(root), false)));
+        assert!(targets.contains(&(normalize(&root.join("brand")), true)));
+    }
+}
@@ -66,7 +66,7 @@
 // Timeline table
 #heading(level: 1)[Timeline]
 
-#editable-block("blocks.timeline", title: "Timeline", format: "table")[
+#editable-block("blocks.timeline", title: "Timeline", type: "table")[
   #let timeline = get(data, "blocks.timeline")
   #if timeline != none [
     #render-table(timeline)
@@ -0,0 +1,21 @@
+Run this:
+
+```rust
+fn main() {
+    println!("hi");
+}
+```
+
+A fence inside a fence:
+
+````markdown
+```toml
+key = "value"
+```
+````
+
+```
+indented code
+```
+
+Inline `code` and #raw("a `tick` inside").
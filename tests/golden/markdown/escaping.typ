@@ -0,0 +1,9 @@
+Costs: \$100 \#tag \@mention  \[x\] and a\\path \~ \// not a comment.
+
+\- not a list
+
+\+ not an enum
+
+2024\. was a year
+
+See #link("https://example.org/a\"b")[https:\//example.org/a"b] or #link("mailto:info@example.org")[info\@example.org].
@@ -0,0 +1,3 @@
+Prices exclude VAT#footnote[Currently _19 %_ in Germany.] <fn-vat> and travel#footnote[Billed at cost.].
+
+Again see the VAT note#footnote(<fn-vat>), and a missing one\[^nope\].
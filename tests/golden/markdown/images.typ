@@ -0,0 +1,5 @@
+#image("logo.png", alt: "Company logo")
+
+#figure(image("plans/floor", alt: "Floor plan"), caption: [Ground floor "A"])
+
+#image("bare.svg")
@@ -0,0 +1,5 @@
+- First paragraph of item one.
+
+  Second paragraph of item one.
+- Item two \
+  with a hard break.
@@ -0,0 +1,13 @@
+The area of a circle is $A = pi r^2$, and the total is $sum_(i = 1)^n x_i$.
+
+$ frac(- b plus.minus sqrt(b^2 - 4 a c), 2 a) $
+
+Speed is $v = frac(upright(d) s, upright(d) t)$ with $Delta t -> 0$ and $x in bb(R)$.
+
+$ f ( x ) = cases(1 & "if " x >= 0, 0 & "otherwise") $
+
+Unsupported commands stay visible: #raw("\\xymatrix{A \\ar[r] & B}", lang: "latex").
+
+#raw("\\begin{tikzcd} A \\arrow[r] & B \\end{tikzcd}", block: true, lang: "latex")
+
+Prices like \$100 and \$200 are not math.
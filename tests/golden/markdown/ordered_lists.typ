@@ -0,0 +1,11 @@
++ First
++ Second
+  + Nested one
+  + Nested two
++ Third
+
+Then a list starting later:
+
+4. Fourth
++ Fifth
+  - bullet inside
@@ -0,0 +1,21 @@
+= Scope <scope>
+The work is described in @scope; costs follow in @costs.
+
+== Costs <costs>
+Totals are #text(fill: red)[net] amounts. Regular `code` stays code.
+
+#pagebreak()
+#v(1cm)
+
+#heading(level: 2, numbering: none)[Notes] <notes>
+Not numbered.
+
+#heading(level: 2, numbering: none, outlined: false)[Appendix]
+Contact \@support or mail team\@example.com. The label \@missing does not exist.
+
+- An item
+  #line(length: 100%)
+
+```typ
+#let listing = "shown as code"
+```
@@ -0,0 +1,7 @@
+#table(
+  columns: (auto, auto, auto, auto),
+  align: (left, center, right, auto),
+  table.header([Item], [Qty], [Price], [Note]),
+  [Design], [2], [1.200 €], [_incl._ review],
+  [Hosting], [12], [40 €], [],
+)
@@ -0,0 +1,4 @@
+- ☑ Write the offer
+- ☐ Send it to the client
+  - ☐ Follow up
+- Plain item
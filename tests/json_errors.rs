@@ -0,0 +1,136 @@
+//! `--json` error envelopes printed by the CLI
+
+use std::fs;
+use std::path::Path;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+
+const CONTENT: &str = r#"[meta]
+template = "quote.typ"
+
+[quote]
+title = "Project"
+
+[blocks.intro]
+title = "Overview"
+content = "First"
+
+[blocks.summary]
+title = "Overview"
+content = "Second"
+"#;
+
+/// `tmpltr` with config, data and cache directories inside `home`
+fn tmpltr(home: &Path) -> Command {
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("tmpltr");
+    cmd.current_dir(home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("HOME", home)
+        .env_remove("RUST_LOG");
+    cmd
+}
+
+/// Run a failing command and parse its stdout as JSON
+fn envelope(cmd: &mut Command, code: i32) -> Value {
+    let output = cmd
+        .assert()
+        .failure()
+        .code(code)
+        .get_output()
+        .stdout
+        .clone();
+    let json: Value = serde_json::from_slice(&output).expect("stdout is one JSON document");
+    assert_eq!(json["status"], "error");
+    assert_eq!(json["exit_code"], code);
+    json
+}
+
+fn setup() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("content.toml"), CONTENT).unwrap();
+    dir
+}
+
+#[test]
+fn missing_file_reports_path() {
+    let dir = setup();
+    let json = envelope(
+        tmpltr(dir.path()).args(["--json", "get", "quote.title", "missing.toml"]),
+        1,
+    );
+    assert_eq!(json["kind"], "file_not_found");
+    assert_eq!(json["path"], "missing.toml");
+    assert!(json.get("details").is_some());
+}
+
+#[test]
+fn unknown_title_reports_title() {
+    let dir = setup();
+    let json = envelope(
+        tmpltr(dir.path()).args(["--json", "get", "Appendix", "content.toml"]),
+        1,
+    );
+    assert_eq!(json["kind"], "title_not_found");
+    assert_eq!(json["title"], "Appendix");
+}
+
+#[test]
+fn ambiguous_title_lists_candidates() {
+    let dir = setup();
+    let json = envelope(
+        tmpltr(dir.path()).args(["--json", "get", "Overview", "content.toml"]),
+        1,
+    );
+    assert_eq!(json["kind"], "ambiguous_title");
+    assert_eq!(json["title"], "Overview");
+    assert_eq!(
+        json["candidates"],
+        serde_json::json!(["blocks.intro", "blocks.summary"])
+    );
+}
+
+#[test]
+fn validation_errors_are_details() {
+    let dir = setup();
+    fs::write(
+        dir.path().join("schema.json"),
+        r#"{"type": "object", "properties": {"quote": {"type": "object",
+            "properties": {"title": {"type": "integer"}}}}}"#,
+    )
+    .unwrap();
+    let json = envelope(
+        tmpltr(dir.path()).args([
+            "--json",
+            "validate",
+            "content.toml",
+            "--schema",
+            "schema.json",
+        ]),
+        1,
+    );
+    assert_eq!(json["kind"], "validation_error");
+    assert_eq!(json["details"]["errors"][0]["path"], "quote.title");
+}
+
+#[test]
+fn usage_errors_are_reported_as_json() {
+    let dir = setup();
+    let json = envelope(tmpltr(dir.path()).args(["--json", "get"]), 2);
+    assert_eq!(json["kind"], "usage_error");
+}
+
+#[test]
+fn plain_errors_go_to_stderr() {
+    let dir = setup();
+    tmpltr(dir.path())
+        .args(["get", "Appendix", "content.toml"])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("error: block with title"));
+}
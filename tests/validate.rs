@@ -0,0 +1,77 @@
+//! `validate` checks that need the resolved document data
+
+use std::fs;
+use std::path::Path;
+
+use assert_cmd::Command;
+use serde_json::Value;
+
+const CONTENT: &str = r#"[meta]
+template = "quote.typ"
+
+[computed]
+total = "brand.hourly_rate * 2"
+"#;
+
+/// `tmpltr` with config, data and cache directories inside `home`
+fn tmpltr(home: &Path) -> Command {
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("tmpltr");
+    cmd.current_dir(home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("HOME", home)
+        .env_remove("RUST_LOG");
+    cmd
+}
+
+/// Paths of the validation errors reported by `--json validate`
+fn error_paths(cmd: &mut Command) -> Vec<String> {
+    let output = cmd.assert().failure().code(1).get_output().stdout.clone();
+    let json: Value = serde_json::from_slice(&output).expect("stdout is one JSON document");
+    assert_eq!(json["kind"], "validation_error");
+    json["details"]["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn setup() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let brand = dir.path().join("data/tmpltr/brands/acme");
+    fs::create_dir_all(&brand).unwrap();
+    fs::write(
+        brand.join("brand.toml"),
+        "id = \"acme\"\nname = \"ACME\"\nhourly_rate = 120\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("rate.toml"), CONTENT).unwrap();
+    dir
+}
+
+#[test]
+fn computed_fields_validate_on_resolved_data() {
+    let dir = setup();
+    tmpltr(dir.path())
+        .args(["validate", "rate.toml", "--brand", "acme"])
+        .assert()
+        .success();
+
+    let paths = error_paths(tmpltr(dir.path()).args(["--json", "validate", "rate.toml"]));
+    assert_eq!(paths, vec!["computed.total"]);
+}
+
+#[test]
+fn brand_load_failures_are_reported_as_errors() {
+    let dir = setup();
+    let paths = error_paths(tmpltr(dir.path()).args([
+        "--json",
+        "validate",
+        "rate.toml",
+        "--brand",
+        "missing",
+    ]));
+    assert_eq!(paths, vec!["brand", "computed.total"]);
+}
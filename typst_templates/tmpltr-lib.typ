@@ -14,8 +14,15 @@
 // -----------------------------------------------------------------------------
 
 /// Get parsed data from tmpltr CLI input.
+/// tmpltr writes the data to a file inside this package and passes its path
+/// as `data-file`; inline `data` JSON is still accepted.
 /// Returns empty dictionary if no data is provided.
 #let tmpltr-data() = {
+  let file = sys.inputs.at("data-file", default: none)
+  if file != none {
+    // Absolute paths resolve against the package root
+    return json(file)
+  }
   let raw = sys.inputs.at("data", default: "{}")
   // Modern Typst: pass bytes directly to json() instead of using json.decode()
   json(bytes(raw))
@@ -108,23 +115,79 @@
 // EDITABLE FIELDS
 // -----------------------------------------------------------------------------
 
-/// Mark an inline field as editable (for future editor integration).
-/// Currently renders the value directly.
+/// Emit locatable metadata describing where an editable element was placed.
+/// tmpltr queries `<tmpltr-position>` to report positions (--with-positions).
+#let tmpltr-position(id, kind, size) = context {
+  let pos = here().position()
+  [#metadata((
+    id: id,
+    kind: kind,
+    page: pos.page,
+    x: pos.x.pt(),
+    y: pos.y.pt(),
+    width: size.width.pt(),
+    height: size.height.pt(),
+  )) <tmpltr-position>]
+}
+
+/// Mark an inline field as editable.
+/// Renders the value and records its position for editor overlays.
+/// Constraints (required, min, max, pattern, options) are read by tmpltr for
+/// schema generation and validation; they do not affect rendering.
 /// Example: editable("workshop.title", "Meeting Title", type: "text")
-#let editable(id, value, type: "text", default: none) = {
+/// Example: editable("quote.total", total, type: "currency", min: 0, required: true)
+#let editable(
+  id,
+  value,
+  type: "text",
+  default: none,
+  required: false,
+  min: none,
+  max: none,
+  pattern: none,
+  options: (),
+) = {
   let display-value = if value == none or value == "" { default } else { value }
-  display-value
+  let body = [#display-value]
+  context tmpltr-position(id, "field", measure(body))
+  body
 }
 
-/// Mark a content block as editable (for future editor integration).
-/// Currently renders the body directly.
-/// Example: editable-block("intro", "Introduction", format: "markdown")[Content here]
-#let editable-block(id, title: none, format: "text", body) = {
+/// Record the position of a block from a placed marker at its top-left
+/// corner to the `<tmpltr-block-end>` marker at its bottom-right corner (or
+/// the bottom of the page when the block breaks across pages). Placed
+/// markers take no space, so the block is laid out as if it weren't editable.
+#let tmpltr-block-position(id) = place(left, context {
+  let start = here().position()
+  let end = query(selector(<tmpltr-block-end>).after(here())).find(m => m.value == id)
+  if end == none { return }
+  let end = end.location().position()
+  let bottom = if end.page == start.page { end.y } else { page.height }
+  [#metadata((
+    id: id,
+    kind: "block",
+    page: start.page,
+    x: start.x.pt(),
+    y: start.y.pt(),
+    width: (end.x - start.x).pt(),
+    height: (bottom - start.y).pt(),
+  )) <tmpltr-position>]
+})
+
+/// Mark a content block as editable.
+/// Renders the body as-is and records the position of the whole block.
+/// `type` declares structured blocks ("table", "image", "list", "keyvalue",
+/// "records"); `fields` names their columns for `tmpltr init`.
+/// Example: editable-block("intro", title: "Introduction")[Content here]
+/// Example: editable-block("blocks.items", type: "records", fields: ("description", "qty"))[...]
+#let editable-block(id, title: none, format: "markdown", type: "text", fields: (), body) = {
+  tmpltr-block-position(id)
   if title != none {
     [*#title*]
     linebreak()
   }
   body
+  place(right)[#metadata(id) <tmpltr-block-end>]
 }
 
 // -----------------------------------------------------------------------------
@@ -141,6 +204,112 @@
   }
 }
 
+// -----------------------------------------------------------------------------
+// LOCALE FORMATTING
+// -----------------------------------------------------------------------------
+
+/// Locale conventions prepared by tmpltr (en-US when missing).
+#let tmpltr-locale(data) = get(data, "_tmpltr.locale", default: (
+  tag: "en-US",
+  decimal: ".",
+  group: ",",
+  currency: "USD",
+  symbols: (EUR: "€", USD: "$", GBP: "£", JPY: "¥"),
+  minor_units: (JPY: 0),
+  symbol_first: true,
+  months: ("January", "February", "March", "April", "May", "June", "July",
+    "August", "September", "October", "November", "December"),
+  date: "{month} {day}, {year}",
+  first_day: none,
+))
+
+/// Format a number with the document's separators.
+/// Typed fields already come pre-formatted as `<field>_formatted`.
+/// Example: fmt-number(data, 1234.5, decimals: 2) // "1.234,50" for de
+#let fmt-number(data, value, decimals: auto) = {
+  let locale = tmpltr-locale(data)
+  let digits = if decimals == auto {
+    str(calc.abs(value))
+  } else {
+    let factor = calc.pow(10, decimals)
+    let scaled = int(calc.round(calc.abs(value) * factor))
+    let whole = str(calc.quo(scaled, factor))
+    if decimals == 0 {
+      whole
+    } else {
+      let fraction = str(calc.rem(scaled, factor))
+      whole + "." + "0" * (decimals - fraction.len()) + fraction
+    }
+  }
+
+  let parts = digits.split(".")
+  let integer = parts.at(0)
+  let grouped = ""
+  for (i, c) in integer.clusters().enumerate() {
+    if i > 0 and calc.rem(integer.len() - i, 3) == 0 {
+      grouped += locale.group
+    }
+    grouped += c
+  }
+  if parts.len() > 1 {
+    grouped += locale.decimal + parts.at(1)
+  }
+
+  let zero = grouped.clusters().all(c => c == "0" or c == locale.group or c == locale.decimal)
+  if value < 0 and not zero { "-" + grouped } else { grouped }
+}
+
+/// Format a currency amount; `currency` defaults to the document currency
+/// and `decimals` to the currency's minor unit.
+/// Example: fmt-currency(data, 1234.56) // "1.234,56 €" for de
+#let fmt-currency(data, value, currency: auto, decimals: auto) = {
+  let locale = tmpltr-locale(data)
+  let code = upper(if currency == auto { locale.currency } else { currency })
+  let symbol = locale.symbols.at(code, default: code)
+  let decimals = if decimals == auto {
+    locale.at("minor_units", default: (:)).at(code, default: 2)
+  } else {
+    decimals
+  }
+  let amount = fmt-number(data, calc.abs(value), decimals: decimals)
+  let sign = if value < 0 and amount.clusters().any(c => c not in ("0", locale.group, locale.decimal)) { "-" } else { "" }
+
+  if not locale.symbol_first {
+    sign + amount + "\u{a0}" + symbol
+  } else if symbol == code {
+    sign + symbol + "\u{a0}" + amount
+  } else {
+    sign + symbol + amount
+  }
+}
+
+/// Format a date ("2026-10-17" or a datetime) in the long local form.
+/// Strings that are not a valid YYYY-MM-DD date are returned unchanged.
+/// Example: fmt-date(data, "2026-10-17") // "17. Oktober 2026" for de
+#let fmt-date(data, value) = {
+  let locale = tmpltr-locale(data)
+  let date = value
+  if type(value) == str {
+    let found = value.match(regex("^(\\d{4})-(\\d{2})-(\\d{2})"))
+    if found == none {
+      return value
+    }
+    let (year, month, day) = found.captures.map(int)
+    let leap = calc.rem(year, 4) == 0 and (calc.rem(year, 100) != 0 or calc.rem(year, 400) == 0)
+    let month-days = (31, if leap { 29 } else { 28 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31)
+    if month < 1 or month > 12 or day < 1 or day > month-days.at(month - 1) {
+      return value
+    }
+    date = datetime(year: year, month: month, day: day)
+  }
+  let first-day = locale.at("first_day", default: none)
+  let day = if date.day() == 1 and first-day != none { first-day } else { str(date.day()) }
+  locale.date
+    .replace("{day}", day)
+    .replace("{month}", locale.months.at(date.month() - 1))
+    .replace("{year}", str(date.year()))
+}
+
 // -----------------------------------------------------------------------------
 // TABLE RENDERING
 // -----------------------------------------------------------------------------
@@ -169,3 +338,95 @@
     ..rows.flatten().map(cell => [#cell])
   )
 }
+
+// -----------------------------------------------------------------------------
+// STRUCTURED BLOCK RENDERING
+// -----------------------------------------------------------------------------
+
+/// Render an image block ("path", optional "caption" and "width").
+/// tmpltr makes the path absolute. `image()` resolves paths relative to the
+/// file that calls it, so the loader must be defined in your template.
+/// Example: render-image(get(data, "blocks.photo"), (..args) => image(..args))
+#let render-image(block-data, loader, width: auto) = {
+  if block-data == none or block-data.at("path", default: "") == "" {
+    return []
+  }
+
+  // Same lengths and ratios as accepted by `tmpltr validate`, e.g. "8cm" or "50%"
+  let units = ("pt": 1pt, "mm": 1mm, "cm": 1cm, "in": 1in, "em": 1em, "%": 1%)
+  let size = block-data.at("width", default: "")
+  let found = if type(size) == str { size.match(regex("^(\\d+(?:\\.\\d+)?)(pt|mm|cm|in|em|%)$")) } else { none }
+  let width = if found != none { float(found.captures.at(0)) * units.at(found.captures.at(1)) } else { width }
+  let img = loader(block-data.path, width: width)
+
+  let caption = block-data.at("caption", default: none)
+  if caption != none and caption != "" {
+    figure(img, caption: caption)
+  } else {
+    img
+  }
+}
+
+/// Render a list block as a bullet list.
+/// Expects a dictionary with "items" (array of strings).
+/// Example: render-list(get(data, "blocks.deliverables"))
+#let render-list(block-data) = {
+  if block-data == none {
+    return []
+  }
+
+  let items = block-data.at("items", default: ())
+  list(..items.map(item => [#item]))
+}
+
+/// Render a key/value block as a two-column grid with bold keys.
+/// Expects a dictionary with "entries" (array of (key: ..., value: ...)).
+/// Example: render-key-value(get(data, "blocks.payment"))
+#let render-key-value(block-data, gutter: 1em) = {
+  if block-data == none {
+    return []
+  }
+
+  let entries = block-data.at("entries", default: ())
+  grid(
+    columns: (auto, 1fr),
+    column-gutter: gutter,
+    row-gutter: 0.65em,
+    ..entries.map(e => ([*#e.key*], [#e.at("value", default: "")])).flatten()
+  )
+}
+
+/// Render a records block as a table, one row per record.
+/// Columns follow `fields`, the block's "fields" or the first record's keys;
+/// `headers` overrides the header labels. Numbers are right-aligned.
+/// Example: render-records(get(data, "blocks.items"), headers: ("Item", "Qty", "Price"))
+#let render-records(block-data, fields: none, headers: none) = {
+  if block-data == none {
+    return []
+  }
+
+  let records = block-data.at("records", default: ())
+  let fields = if fields != none { fields } else { block-data.at("fields", default: ()) }
+  if fields.len() == 0 and records.len() > 0 {
+    fields = records.first().keys()
+  }
+  if fields.len() == 0 {
+    return []
+  }
+  let headers = if headers != none { headers } else { fields }
+
+  let cell(value) = {
+    if type(value) == int or type(value) == float {
+      align(right)[#value]
+    } else {
+      [#value]
+    }
+  }
+
+  table(
+    columns: (1fr,) + (fields.len() - 1) * (auto,),
+    stroke: 0.5pt,
+    table.header(..headers.map(h => [*#h*])),
+    ..records.map(r => fields.map(f => cell(r.at(f, default: "")))).flatten()
+  )
+}